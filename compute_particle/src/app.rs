@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use compute_particle::State;
 use winit::{
@@ -8,13 +8,13 @@ use winit::{
     window::Window,
 };
 
-pub struct App<'window> {
-    window: Option<Window>,
-    state: Option<State<'window>>,
+pub struct App {
+    window: Option<Arc<Window>>,
+    state: Option<State>,
     last_render_time: Instant,
 }
 
-impl Default for App<'_> {
+impl Default for App {
     fn default() -> Self {
         Self {
             window: None,
@@ -24,15 +24,16 @@ impl Default for App<'_> {
     }
 }
 
-impl ApplicationHandler for App<'_> {
+impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         self.window = Some(
             event_loop
                 .create_window(Window::default_attributes())
+                .map(Arc::new)
                 .unwrap(),
         );
         if let Some(window) = &self.window {
-            self.state = Some(futures::executor::block_on(State::new(window)).unwrap());
+            self.state = Some(futures::executor::block_on(State::new(window.clone())).unwrap());
         }
     }
 
@@ -42,6 +43,11 @@ impl ApplicationHandler for App<'_> {
         _window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
+        if let Some(state) = &mut self.state
+            && state.input(&event)
+        {
+            return;
+        }
         match event {
             WindowEvent::CloseRequested => {
                 event_loop.exit();
@@ -55,10 +61,10 @@ impl ApplicationHandler for App<'_> {
                     match state.render() {
                         Ok(_) => {}
                         Err(wgpu::SurfaceError::Lost) => {
-                            dbg!("surface lost");
+                            tracing::warn!("surface lost");
                         }
                         Err(e) => {
-                            eprintln!("{:?}", e);
+                            tracing::error!("{e:?}");
                         }
                     }
                 }
@@ -80,7 +86,7 @@ impl ApplicationHandler for App<'_> {
     }
 }
 
-impl App<'_> {
+impl App {
     pub fn run() {
         let event_loop = EventLoop::new().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
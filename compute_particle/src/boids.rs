@@ -0,0 +1,366 @@
+//! 鸟群模拟
+//!
+//! 每只鸟是一个 [`Particle`]，按分离、对齐、聚集三条规则和周围的邻居互动。邻居用 [`crate::grid`]
+//! 里的均匀网格查找，每只鸟只需要检查周围 3x3 个格子，累加邻居的顺序是固定的，
+//! 同样的输入每次得到完全相同的结果。[`cpu_step`] 按同样的顺序在 CPU 上模拟，用来检查 GPU 的结果。
+//!
+//! ```ignore
+//! let boids = spawn_flock(100_000, 7, world_bounds(width, height));
+//! let mut flock = Flock::new(device, &boids, FlockSettings::default(), shader, 64, None)?;
+//! flock.update(queue, bounds, delta_time);
+//! flock.record(&mut compute_pass);
+//! ```
+use anyhow::{Result, bail};
+use glam::{UVec2, Vec2, Vec4};
+use gpu::UniformBuffer;
+
+pub use crate::grid::MAX_GRID_CELLS;
+use crate::{
+    Particle, create_compute_pipeline,
+    grid::{CellList, GridParams, GridPipelines, SpatialGrid},
+    ping_pong::PingPong,
+    wgsl::pcg_hash,
+};
+
+/// 可以在运行时修改的鸟群参数，下一帧生效
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlockSettings {
+    /// 对齐和聚集的感知半径
+    pub radius: f32,
+    /// 比这更近的邻居会互相推开
+    pub separation_radius: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// 离边界不到这个距离时往回转
+    pub edge_margin: f32,
+    pub edge_turn: f32,
+}
+
+impl Default for FlockSettings {
+    /// 适合十万只鸟铺满窗口的参数
+    fn default() -> Self {
+        Self {
+            radius: 0.015,
+            separation_radius: 0.006,
+            separation: 0.002,
+            alignment: 2.0,
+            cohesion: 1.5,
+            min_speed: 0.1,
+            max_speed: 0.3,
+            edge_margin: 0.1,
+            edge_turn: 1.0,
+        }
+    }
+}
+
+impl FlockSettings {
+    /// 世界坐标在 ±`bounds` 之间、共 `count` 只鸟时的模拟参数
+    pub fn to_gpu(&self, bounds: Vec2, count: u32, delta_time: f32) -> FlockParams {
+        let grid = GridParams::fit(bounds, self.radius, count);
+        FlockParams {
+            bounds,
+            grid_size: grid.size,
+            cell_size: grid.cell_size,
+            delta_time,
+            radius: self.radius,
+            separation_radius: self.separation_radius,
+            separation: self.separation,
+            alignment: self.alignment,
+            cohesion: self.cohesion,
+            min_speed: self.min_speed,
+            max_speed: self.max_speed,
+            edge_margin: self.edge_margin,
+            edge_turn: self.edge_turn,
+            count,
+        }
+    }
+}
+
+/// 和 boids.wgsl 里的 `FlockParams` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FlockParams {
+    pub bounds: Vec2,
+    pub grid_size: UVec2,
+    pub cell_size: f32,
+    pub delta_time: f32,
+    pub radius: f32,
+    pub separation_radius: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub edge_margin: f32,
+    pub edge_turn: f32,
+    pub count: u32,
+}
+
+impl FlockParams {
+    pub fn cell_count(&self) -> u32 {
+        self.grid_size.x * self.grid_size.y
+    }
+
+    /// 建网格用的参数
+    pub fn grid(&self) -> GridParams {
+        GridParams {
+            bounds: self.bounds,
+            size: self.grid_size,
+            cell_size: self.cell_size,
+            count: self.count,
+        }
+    }
+
+    fn limit_speed(&self, vel: Vec2) -> Vec2 {
+        let speed = vel.length();
+        if speed > self.max_speed {
+            vel * (self.max_speed / speed)
+        } else if speed < self.min_speed && speed > 0.0 {
+            vel * (self.min_speed / speed)
+        } else {
+            vel
+        }
+    }
+
+    fn edge_steer(&self, pos: Vec2) -> Vec2 {
+        let inner = self.bounds - self.edge_margin;
+        // 和 WGSL 的 sign 一样，0 的符号是 0
+        let steer = |p: f32, inner: f32| {
+            if p.abs() <= inner || p == 0.0 {
+                0.0
+            } else {
+                -p.signum() * self.edge_turn
+            }
+        };
+        Vec2::new(steer(pos.x, inner.x), steer(pos.y, inner.y))
+    }
+}
+
+/// 在 ±`bounds` 里随机撒 `count` 只鸟，速度方向随机，颜色按初始方向取色
+pub fn spawn_flock(count: u32, seed: u32, bounds: Vec2) -> Vec<Particle> {
+    let settings = FlockSettings::default();
+    (0..count)
+        .map(|i| {
+            let random = |salt: u32| pcg_hash(i.wrapping_mul(4).wrapping_add(salt) ^ seed);
+            let pos = (Vec2::new(random(0), random(1)) * 2.0 - 1.0) * bounds;
+            let angle = random(2) * std::f32::consts::TAU;
+            let speed = settings.min_speed + (settings.max_speed - settings.min_speed) * random(3);
+            let color = Vec4::new(
+                0.6 + 0.4 * angle.cos(),
+                0.6 + 0.4 * (angle + 2.094).cos(),
+                0.6 + 0.4 * (angle + 4.189).cos(),
+                1.0,
+            );
+            Particle::new(pos, Vec2::from_angle(angle) * speed, color, 1.0)
+        })
+        .collect()
+}
+
+/// 在 CPU 上按 GPU 的顺序模拟一步：网格里同一个格子按下标升序，邻居按 3x3 格子从下到上、从左到右累加
+pub fn cpu_step(params: &FlockParams, boids: &[Particle]) -> Vec<Particle> {
+    let cells = CellList::new(params.grid(), boids.iter().map(|boid| boid.pos));
+    let radius_sq = params.radius * params.radius;
+    let separation_sq = params.separation_radius * params.separation_radius;
+    boids
+        .iter()
+        .enumerate()
+        .map(|(i, &boid)| {
+            let mut neighbors = 0u32;
+            let mut velocity_sum = Vec2::ZERO;
+            let mut position_sum = Vec2::ZERO;
+            let mut separation = Vec2::ZERO;
+            for j in cells.neighbors(boid.pos) {
+                if j == i {
+                    continue;
+                }
+                let other = &boids[j];
+                let offset = other.pos - boid.pos;
+                let distance_sq = offset.dot(offset);
+                if distance_sq >= radius_sq {
+                    continue;
+                }
+                neighbors += 1;
+                velocity_sum += other.vel;
+                position_sum += other.pos;
+                if distance_sq < separation_sq {
+                    separation -= offset / distance_sq.max(1e-8);
+                }
+            }
+
+            let mut acceleration = params.edge_steer(boid.pos);
+            if neighbors > 0 {
+                let n = neighbors as f32;
+                acceleration += params.alignment * (velocity_sum / n - boid.vel);
+                acceleration += params.cohesion * (position_sum / n - boid.pos);
+                acceleration += params.separation * separation;
+            }
+            let mut next = boid;
+            next.vel = params.limit_speed(boid.vel + acceleration * params.delta_time);
+            next.pos =
+                (boid.pos + next.vel * params.delta_time).clamp(-params.bounds, params.bounds);
+            next
+        })
+        .collect()
+}
+
+/// GPU 上的鸟群：交替读写的状态缓冲区、网格和每帧的五个计算管线
+pub struct Flock {
+    boids: PingPong<Particle>,
+    settings: FlockSettings,
+    params: FlockParams,
+    params_buffer: UniformBuffer<FlockParams>,
+    grid: SpatialGrid,
+    workgroup_size: u32,
+    /// 第 `i` 个从状态缓冲区 `i` 读
+    bind_groups: [wgpu::BindGroup; 2],
+    /// 热重载时用同样的布局重建管线
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: FlockPipelines,
+}
+
+struct FlockPipelines {
+    count: wgpu::ComputePipeline,
+    grid: GridPipelines,
+    flock: wgpu::ComputePipeline,
+}
+
+impl Flock {
+    /// `shader` 是按 `workgroup_size` 编译的 boids.wgsl
+    pub fn new(
+        device: &wgpu::Device,
+        boids: &[Particle],
+        settings: FlockSettings,
+        shader: &wgpu::ShaderModule,
+        workgroup_size: u32,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        if boids.is_empty() {
+            bail!("鸟群不能为空");
+        }
+        let count = boids.len() as u32;
+        let boid_buffers =
+            PingPong::from_slice(device, "Boid Buffer", boids, wgpu::BufferUsages::empty())?;
+        let params = FlockParams::default();
+        let params_buffer = UniformBuffer::new(device, "Flock Params Buffer", &params)?;
+        let grid = SpatialGrid::new(device, count)?;
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Flock Bind Group Layout"),
+            entries: &[
+                boid_buffers
+                    .latest()
+                    .layout_entry(0, wgpu::ShaderStages::COMPUTE, true),
+                boid_buffers
+                    .latest()
+                    .layout_entry(1, wgpu::ShaderStages::COMPUTE, false),
+                params_buffer.layout_entry(2, wgpu::ShaderStages::COMPUTE),
+            ],
+        });
+        let bind_groups = boid_buffers.pair(|source, destination| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Flock Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    source.bind_group_entry(0),
+                    destination.bind_group_entry(1),
+                    params_buffer.bind_group_entry(2),
+                ],
+            })
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Flock Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, grid.bind_group_layout()],
+            immediate_size: 0,
+        });
+        let pipelines = FlockPipelines::new(device, &pipeline_layout, shader, cache)?;
+
+        Ok(Self {
+            boids: boid_buffers,
+            settings,
+            params,
+            params_buffer,
+            grid,
+            workgroup_size,
+            bind_groups,
+            pipeline_layout,
+            pipelines,
+        })
+    }
+
+    /// 修改之后下一次 [`Flock::update`] 生效
+    pub fn settings_mut(&mut self) -> &mut FlockSettings {
+        &mut self.settings
+    }
+
+    /// 每帧模拟之前调用，边界跟着窗口变化
+    pub fn update(&mut self, queue: &wgpu::Queue, bounds: Vec2, delta_time: f32) {
+        self.params = self.settings.to_gpu(bounds, self.len(), delta_time);
+        self.params_buffer.update(queue, &self.params);
+        self.grid.update(queue, self.params.grid());
+    }
+
+    /// 最近一次 [`Flock::update`] 写入的参数
+    pub fn params(&self) -> &FlockParams {
+        &self.params
+    }
+
+    /// 着色器改动之后重建管线，失败时保留旧管线
+    pub fn rebuild(
+        &mut self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<()> {
+        self.pipelines = FlockPipelines::new(device, &self.pipeline_layout, shader, cache)?;
+        Ok(())
+    }
+
+    /// 在计算通道里录制一步模拟：重建网格，再按邻居更新每只鸟，新状态写进另一个缓冲区
+    pub fn record(&mut self, pass: &mut wgpu::ComputePass) {
+        let boid_groups = self.len().div_ceil(self.workgroup_size);
+        pass.set_bind_group(0, &self.bind_groups[self.boids.latest_index()], &[]);
+        pass.set_bind_group(1, self.grid.bind_group(), &[]);
+        pass.set_pipeline(&self.pipelines.count);
+        pass.dispatch_workgroups(boid_groups, 1, 1);
+        self.pipelines
+            .grid
+            .record(pass, &self.grid, self.workgroup_size);
+        pass.set_pipeline(&self.pipelines.flock);
+        pass.dispatch_workgroups(boid_groups, 1, 1);
+        self.boids.swap();
+    }
+
+    /// 鸟的状态，渲染时只读绑定最新的那个
+    pub fn boids(&self) -> &PingPong<Particle> {
+        &self.boids
+    }
+
+    pub fn len(&self) -> u32 {
+        self.boids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl FlockPipelines {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        let pipeline = |entry_point, label| {
+            create_compute_pipeline(device, layout, shader, entry_point, label, cache)
+        };
+        Ok(Self {
+            count: pipeline("count", "Flock Count Pipeline")?,
+            grid: GridPipelines::new(device, layout, shader, cache)?,
+            flock: pipeline("flock", "Flock Pipeline")?,
+        })
+    }
+}
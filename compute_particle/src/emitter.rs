@@ -0,0 +1,307 @@
+//! 粒子发射器
+//!
+//! 每个发射器有自己的形状、发射速率、初速度的大小和方向分布、寿命和颜色范围，
+//! 多个发射器共用一个粒子缓冲区：每帧 [`Emitters::prepare`] 按各自的速率算出重生名额，
+//! 连同参数一起写进存储缓冲区，死亡的粒子领到的号码落在哪个发射器的区间里，就由哪个发射器重生。
+//! [`GpuEmitter::emit`] 在 CPU 上按同样的算法生成粒子，给[参考实现](crate::simulation::CpuSimulation)用。
+//!
+//! ```ignore
+//! state.emitters_mut().push(
+//!     Emitter::new(EmitterShape::Cone { angle: FRAC_PI_2, spread: 0.5 }, Vec2::new(0.0, -0.9))
+//!         .with_rate(200.0)
+//!         .with_speed(0.8, 1.2)
+//!         .with_colors(Vec4::new(1.0, 0.8, 0.2, 1.0), Vec4::new(1.0, 0.2, 0.0, 1.0)),
+//! )?;
+//! ```
+use std::f32::consts::TAU;
+
+use anyhow::{Result, bail};
+use glam::{Vec2, Vec4};
+
+use crate::{
+    Particle,
+    lifecycle::SpawnBudget,
+    wgsl::{mix, pcg_hash},
+};
+
+/// 发射器缓冲区的容量
+pub const MAX_EMITTERS: u32 = 16;
+
+/// 粒子出生的位置分布，坐标相对发射器的位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitterShape {
+    Point,
+    /// 圆或圆环：在 `inner_radius..radius` 之间按面积均匀分布，`inner_radius` 为 0 时是实心圆
+    Circle {
+        radius: f32,
+        inner_radius: f32,
+    },
+    Box {
+        half_size: Vec2,
+    },
+    /// 锥形：从顶点发射，方向在 `angle` 两侧各张开 `spread` 的一半（弧度），忽略 [`Direction`]
+    Cone {
+        angle: f32,
+        spread: f32,
+    },
+    /// 线段：从发射器位置到 `position + end`
+    Line {
+        end: Vec2,
+    },
+}
+
+/// 初速度的方向分布
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// 各个方向均匀
+    Any,
+    /// 从中心向外（圆环、矩形）或沿法线（线段），两侧各张开 `spread` 的一半
+    Outward { spread: f32 },
+    /// 朝 `angle` 方向，两侧各张开 `spread` 的一半
+    Angle { angle: f32, spread: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emitter {
+    pub shape: EmitterShape,
+    pub position: Vec2,
+    pub direction: Direction,
+    /// 每秒发射多少个粒子
+    pub rate: f32,
+    /// 初速度大小的范围
+    pub speed: Vec2,
+    /// 寿命范围（秒）
+    pub lifetime: Vec2,
+    /// 颜色在两者之间随机
+    pub colors: [Vec4; 2],
+}
+
+impl Emitter {
+    pub fn new(shape: EmitterShape, position: Vec2) -> Self {
+        Self {
+            shape,
+            position,
+            direction: Direction::Any,
+            rate: 100.0,
+            speed: Vec2::new(0.2, 0.6),
+            lifetime: Vec2::new(2.0, 4.0),
+            colors: [Vec4::ONE; 2],
+        }
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = Vec2::new(min, max);
+        self
+    }
+
+    pub fn with_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = Vec2::new(min, max);
+        self
+    }
+
+    pub fn with_colors(mut self, start: Vec4, end: Vec4) -> Self {
+        self.colors = [start, end];
+        self
+    }
+
+    pub fn mean_lifetime(&self) -> f32 {
+        (self.lifetime.x + self.lifetime.y) * 0.5
+    }
+
+    /// 稳定之后这个发射器平均有多少个活着的粒子
+    pub fn steady_count(&self) -> f32 {
+        self.rate * self.mean_lifetime()
+    }
+
+    /// 写进发射器缓冲区的数据
+    pub fn to_gpu(&self, spawn_count: u32) -> GpuEmitter {
+        let (shape_kind, shape) = match self.shape {
+            EmitterShape::Point => (SHAPE_POINT, Vec2::ZERO),
+            EmitterShape::Circle {
+                radius,
+                inner_radius,
+            } => (SHAPE_CIRCLE, Vec2::new(radius, inner_radius)),
+            EmitterShape::Box { half_size } => (SHAPE_BOX, half_size),
+            EmitterShape::Cone { angle, spread } => (SHAPE_CONE, Vec2::new(angle, spread)),
+            EmitterShape::Line { end } => (SHAPE_LINE, end),
+        };
+        let (direction_kind, direction) = match self.direction {
+            Direction::Any => (DIRECTION_ANY, Vec2::ZERO),
+            Direction::Outward { spread } => (DIRECTION_OUTWARD, Vec2::new(0.0, spread)),
+            Direction::Angle { angle, spread } => (DIRECTION_ANGLE, Vec2::new(angle, spread)),
+        };
+        GpuEmitter {
+            color_start: self.colors[0],
+            color_end: self.colors[1],
+            position: self.position,
+            shape,
+            direction,
+            speed: self.speed,
+            lifetime: self.lifetime,
+            shape_kind,
+            direction_kind,
+            spawn_count,
+            _padding: [0; 3],
+        }
+    }
+}
+
+// 和 emitter.wgsl 里的常量对应
+const SHAPE_POINT: u32 = 0;
+const SHAPE_CIRCLE: u32 = 1;
+const SHAPE_BOX: u32 = 2;
+const SHAPE_CONE: u32 = 3;
+const SHAPE_LINE: u32 = 4;
+const DIRECTION_ANY: u32 = 0;
+const DIRECTION_OUTWARD: u32 = 1;
+const DIRECTION_ANGLE: u32 = 2;
+
+/// 和 emitter.wgsl 里的 `Emitter` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuEmitter {
+    pub color_start: Vec4,
+    pub color_end: Vec4,
+    pub position: Vec2,
+    pub shape: Vec2,
+    pub direction: Vec2,
+    pub speed: Vec2,
+    pub lifetime: Vec2,
+    pub shape_kind: u32,
+    pub direction_kind: u32,
+    /// 这一帧分到的重生名额
+    pub spawn_count: u32,
+    // 结构体按 vec4 的 16 字节对齐，大小要补齐到 96
+    _padding: [u32; 3],
+}
+
+impl GpuEmitter {
+    /// 在 CPU 上按发射器的形状和分布生成一个新粒子，和 emitter.wgsl 里的 `emit` 一致
+    pub fn emit(&self, seed: u32) -> Particle {
+        let random = |salt: u32| pcg_hash(seed.wrapping_add(salt.wrapping_mul(2654435761)));
+        let r0 = random(0);
+        let r1 = random(1);
+
+        let mut offset = Vec2::ZERO;
+        let mut outward = Vec2::new((r0 * TAU).cos(), (r0 * TAU).sin());
+        match self.shape_kind {
+            SHAPE_CIRCLE => {
+                let (outer, inner) = (self.shape.x, self.shape.y);
+                offset = outward * mix(inner * inner, outer * outer, r1).sqrt();
+            }
+            SHAPE_BOX => {
+                offset = (Vec2::new(r0, r1) * 2.0 - 1.0) * self.shape;
+                if offset.dot(offset) > 0.0 {
+                    outward = offset / offset.length();
+                }
+            }
+            SHAPE_LINE => {
+                offset = self.shape * r0;
+                if self.shape.dot(self.shape) > 0.0 {
+                    let along = self.shape / self.shape.length();
+                    outward = Vec2::new(-along.y, along.x) * if r1 < 0.5 { 1.0 } else { -1.0 };
+                }
+            }
+            _ => {}
+        }
+
+        let r2 = random(2);
+        let jitter = r2 - 0.5;
+        let angle = if self.shape_kind == SHAPE_CONE {
+            self.shape.x + jitter * self.shape.y
+        } else if self.direction_kind == DIRECTION_OUTWARD {
+            outward.y.atan2(outward.x) + jitter * self.direction.y
+        } else if self.direction_kind == DIRECTION_ANGLE {
+            self.direction.x + jitter * self.direction.y
+        } else {
+            r2 * TAU
+        };
+        let speed = mix(self.speed.x, self.speed.y, random(3));
+        let max_life = mix(self.lifetime.x, self.lifetime.y, random(4));
+        let t = random(5);
+        Particle::new(
+            self.position + offset,
+            Vec2::new(angle.cos(), angle.sin()) * speed,
+            self.color_start * (1.0 - t) + self.color_end * t,
+            max_life,
+        )
+    }
+}
+
+/// 共用一个粒子缓冲区的一组发射器
+#[derive(Debug, Clone, Default)]
+pub struct Emitters {
+    emitters: Vec<(Emitter, SpawnBudget)>,
+}
+
+impl Emitters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个发射器，返回它的下标；超过 [`MAX_EMITTERS`] 时返回错误
+    pub fn push(&mut self, emitter: Emitter) -> Result<usize> {
+        if self.emitters.len() >= MAX_EMITTERS as usize {
+            bail!("最多只能有 {MAX_EMITTERS} 个发射器");
+        }
+        let budget = SpawnBudget::new(emitter.rate);
+        self.emitters.push((emitter, budget));
+        Ok(self.emitters.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Emitter {
+        self.emitters.remove(index).0
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+    }
+
+    /// 修改之后的参数下一帧生效
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Emitter> {
+        self.emitters.get_mut(index).map(|(emitter, _)| emitter)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Emitter> {
+        self.emitters.iter().map(|(emitter, _)| emitter)
+    }
+
+    pub fn len(&self) -> usize {
+        self.emitters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emitters.is_empty()
+    }
+
+    /// 所有发射器每秒一共发射多少个粒子
+    pub fn total_rate(&self) -> f32 {
+        self.iter().map(|emitter| emitter.rate).sum()
+    }
+
+    /// 稳定之后一共有多少个活着的粒子，超过粒子缓冲区的大小时发射器会抢不到名额
+    pub fn steady_count(&self) -> f32 {
+        self.iter().map(Emitter::steady_count).sum()
+    }
+
+    /// 经过 `delta_time` 秒之后，每个发射器这一帧的数据和名额
+    pub fn prepare(&mut self, delta_time: f32) -> Vec<GpuEmitter> {
+        self.emitters
+            .iter_mut()
+            .map(|(emitter, budget)| {
+                budget.set_rate(emitter.rate);
+                emitter.to_gpu(budget.take(delta_time))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,288 @@
+//! 作用在粒子上的力场
+//!
+//! 力场列表每帧写进存储缓冲区，计算着色器按顺序把每个力场作用到粒子的速度上，
+//! 运行时可以随时增删和修改，下一帧生效。
+//! [`GpuForceField::apply`] 是 CPU 上同样的实现，给[参考实现](crate::simulation::CpuSimulation)用。
+//!
+//! 每个力场都有强度和衰减：[`ForceField::radius`] 大于 0 时，强度按粒子到 [`ForceField::position`]
+//! 的距离以 [`Falloff`] 的方式衰减，重力和阻力也一样，可以用来做局部的重力区、减速区。
+//!
+//! ```ignore
+//! state.forces_mut().push(
+//!     ForceField::vortex(Vec2::ZERO, 0.5).with_falloff(Falloff::Linear, 0.6),
+//! )?;
+//! ```
+use std::f32::consts::TAU;
+
+use anyhow::{Result, bail};
+use glam::Vec2;
+
+use crate::wgsl::{mix, pcg_hash};
+
+/// 力场缓冲区的容量
+pub const MAX_FORCE_FIELDS: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForceKind {
+    /// 朝 `direction` 的恒定加速度
+    Gravity { direction: Vec2 },
+    /// 阻力加速度是 `-(linear + quadratic * |v|) * v`，再乘上强度
+    Drag { linear: f32, quadratic: f32 },
+    /// 朝中心吸引，强度为负时是排斥
+    Attractor,
+    /// 绕中心逆时针旋转，强度为负时顺时针
+    Vortex,
+    /// 旋度噪声湍流：`scale` 是空间频率，`speed` 是噪声随时间变化的速度
+    CurlNoise { scale: f32, speed: f32 },
+}
+
+/// 强度随距离衰减的方式，`d` 是距离除以半径
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Falloff {
+    /// 半径以内不变，半径以外为 0
+    Constant,
+    /// `1 - d`，到半径处减为 0
+    Linear,
+    /// `1 / (1 + d²)`，没有边界，半径越大衰减越慢
+    InverseSquare,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceField {
+    pub kind: ForceKind,
+    pub position: Vec2,
+    pub strength: f32,
+    /// 衰减半径，小于等于 0 时处处相同
+    pub radius: f32,
+    pub falloff: Falloff,
+    /// 关掉的力场不写进缓冲区
+    pub enabled: bool,
+}
+
+impl ForceField {
+    pub fn new(kind: ForceKind, strength: f32) -> Self {
+        Self {
+            kind,
+            position: Vec2::ZERO,
+            strength,
+            radius: 0.0,
+            falloff: Falloff::Constant,
+            enabled: true,
+        }
+    }
+
+    pub fn gravity(direction: Vec2, strength: f32) -> Self {
+        Self::new(
+            ForceKind::Gravity {
+                direction: direction.normalize_or_zero(),
+            },
+            strength,
+        )
+    }
+
+    pub fn drag(linear: f32, quadratic: f32) -> Self {
+        Self::new(ForceKind::Drag { linear, quadratic }, 1.0)
+    }
+
+    pub fn attractor(position: Vec2, strength: f32) -> Self {
+        Self::new(ForceKind::Attractor, strength).with_position(position)
+    }
+
+    /// 强度取反的吸引子
+    pub fn repulsor(position: Vec2, strength: f32) -> Self {
+        Self::attractor(position, -strength)
+    }
+
+    pub fn vortex(position: Vec2, strength: f32) -> Self {
+        Self::new(ForceKind::Vortex, strength).with_position(position)
+    }
+
+    pub fn curl_noise(scale: f32, speed: f32, strength: f32) -> Self {
+        Self::new(ForceKind::CurlNoise { scale, speed }, strength)
+    }
+
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff, radius: f32) -> Self {
+        self.falloff = falloff;
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// 写进力场缓冲区的数据
+    pub fn to_gpu(&self) -> GpuForceField {
+        let (kind, vector) = match self.kind {
+            ForceKind::Gravity { direction } => (FORCE_GRAVITY, direction),
+            ForceKind::Drag { linear, quadratic } => (FORCE_DRAG, Vec2::new(linear, quadratic)),
+            ForceKind::Attractor => (FORCE_ATTRACTOR, Vec2::ZERO),
+            ForceKind::Vortex => (FORCE_VORTEX, Vec2::ZERO),
+            ForceKind::CurlNoise { scale, speed } => (FORCE_CURL_NOISE, Vec2::new(scale, speed)),
+        };
+        let falloff = match self.falloff {
+            Falloff::Constant => FALLOFF_CONSTANT,
+            Falloff::Linear => FALLOFF_LINEAR,
+            Falloff::InverseSquare => FALLOFF_INVERSE_SQUARE,
+        };
+        GpuForceField {
+            position: self.position,
+            vector,
+            strength: self.strength,
+            radius: self.radius,
+            kind,
+            falloff,
+        }
+    }
+}
+
+// 和 forces.wgsl 里的常量对应
+const FORCE_GRAVITY: u32 = 0;
+const FORCE_DRAG: u32 = 1;
+const FORCE_ATTRACTOR: u32 = 2;
+const FORCE_VORTEX: u32 = 3;
+const FORCE_CURL_NOISE: u32 = 4;
+const FALLOFF_CONSTANT: u32 = 0;
+const FALLOFF_LINEAR: u32 = 1;
+const FALLOFF_INVERSE_SQUARE: u32 = 2;
+
+/// 和 forces.wgsl 里的 `ForceField` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuForceField {
+    pub position: Vec2,
+    pub vector: Vec2,
+    pub strength: f32,
+    pub radius: f32,
+    pub kind: u32,
+    pub falloff: u32,
+}
+
+impl GpuForceField {
+    /// 在 CPU 上把力场作用 `dt` 秒，返回新的速度，和 forces.wgsl 里的 `apply_force` 一致
+    pub fn apply(&self, pos: Vec2, vel: Vec2, time: f32, dt: f32) -> Vec2 {
+        let to_center = self.position - pos;
+        let distance = to_center.length();
+        let strength = self.strength * self.falloff_weight(distance);
+        match self.kind {
+            FORCE_GRAVITY => vel + self.vector * strength * dt,
+            FORCE_DRAG => {
+                let k = (self.vector.x + self.vector.y * vel.length()) * strength;
+                vel / (1.0 + k.max(0.0) * dt)
+            }
+            FORCE_ATTRACTOR if distance >= 1e-4 => vel + to_center / distance * strength * dt,
+            FORCE_VORTEX if distance >= 1e-4 => {
+                let tangent = Vec2::new(to_center.y, -to_center.x) / distance;
+                vel + tangent * strength * dt
+            }
+            FORCE_CURL_NOISE => {
+                let p = pos * self.vector.x + Vec2::splat(time * self.vector.y);
+                vel + curl_noise(p) * strength * dt
+            }
+            _ => vel,
+        }
+    }
+
+    fn falloff_weight(&self, distance: f32) -> f32 {
+        if self.radius <= 0.0 {
+            return 1.0;
+        }
+        let d = distance / self.radius;
+        match self.falloff {
+            FALLOFF_LINEAR => (1.0 - d).max(0.0),
+            FALLOFF_INVERSE_SQUARE => 1.0 / (1.0 + d * d),
+            _ => {
+                if d <= 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+fn lattice_gradient(cell: Vec2) -> Vec2 {
+    let h =
+        pcg_hash(cell.x.to_bits().wrapping_mul(73856093) ^ cell.y.to_bits().wrapping_mul(19349663));
+    let angle = h * TAU;
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+fn gradient_noise(p: Vec2) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = lattice_gradient(cell).dot(f);
+    let b = lattice_gradient(cell + Vec2::X).dot(f - Vec2::X);
+    let c = lattice_gradient(cell + Vec2::Y).dot(f - Vec2::Y);
+    let d = lattice_gradient(cell + Vec2::ONE).dot(f - Vec2::ONE);
+    mix(mix(a, b, u.x), mix(c, d, u.x), u.y)
+}
+
+fn curl_noise(p: Vec2) -> Vec2 {
+    let e = 0.01;
+    let dx = gradient_noise(p + Vec2::new(e, 0.0)) - gradient_noise(p - Vec2::new(e, 0.0));
+    let dy = gradient_noise(p + Vec2::new(0.0, e)) - gradient_noise(p - Vec2::new(0.0, e));
+    Vec2::new(dy, -dx) / (2.0 * e)
+}
+
+/// 按顺序作用的一组力场
+#[derive(Debug, Clone, Default)]
+pub struct ForceFields {
+    fields: Vec<ForceField>,
+}
+
+impl ForceFields {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个力场，返回它的下标；超过 [`MAX_FORCE_FIELDS`] 时返回错误
+    pub fn push(&mut self, field: ForceField) -> Result<usize> {
+        if self.fields.len() >= MAX_FORCE_FIELDS as usize {
+            bail!("最多只能有 {MAX_FORCE_FIELDS} 个力场");
+        }
+        self.fields.push(field);
+        Ok(self.fields.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) -> ForceField {
+        self.fields.remove(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    /// 修改之后的参数下一帧生效
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ForceField> {
+        self.fields.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ForceField> {
+        self.fields.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// 写进力场缓冲区的数据，跳过关掉的力场
+    pub fn to_gpu(&self) -> Vec<GpuForceField> {
+        self.iter()
+            .filter(|field| field.enabled)
+            .map(ForceField::to_gpu)
+            .collect()
+    }
+}
@@ -0,0 +1,240 @@
+//! 均匀网格空间哈希，鸟群和 SPH 流体共用
+//!
+//! 每一步先统计每个格子里有几个粒子，前缀和得到每个格子的起始位置，再把粒子的下标散射到对应的
+//! 格子里（计数排序），之后每个粒子只需要检查周围 3x3 个格子。格子边长不小于搜索半径，所以不会漏掉邻居。
+//! 散射的顺序取决于原子操作的先后，每个格子再按下标排一次序，累加邻居的顺序就是固定的。
+//!
+//! 着色器一侧是 grid.wgsl：网格的缓冲区放在第 1 组，包含它的着色器提供第一步的入口，
+//! 对每个粒子调用 `grid_insert`，剩下的三步由 [`GridPipelines::record`] 录制。[`CellList`] 是 CPU 上
+//! 同样顺序的实现，给各个模拟的 CPU 参考实现用。
+use anyhow::Result;
+use glam::{IVec2, UVec2, Vec2};
+use gpu::{StorageBuffer, UniformBuffer};
+
+use crate::create_compute_pipeline;
+
+/// 网格缓冲区的容量，窗口太大时放大格子
+pub const MAX_GRID_CELLS: u32 = 1 << 16;
+
+/// 和 grid.wgsl 里的 `GridParams` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GridParams {
+    pub bounds: Vec2,
+    pub size: UVec2,
+    pub cell_size: f32,
+    pub count: u32,
+}
+
+impl GridParams {
+    /// 覆盖 ±`bounds`、格子边长不小于 `radius` 的网格；格子太多时放大格子，保证网格装得进缓冲区
+    pub fn fit(bounds: Vec2, radius: f32, count: u32) -> Self {
+        let extent = bounds * 2.0;
+        let mut cell_size = radius
+            .max((extent.x * extent.y / MAX_GRID_CELLS as f32).sqrt())
+            .max(1e-4);
+        let mut size = grid_dims(extent, cell_size);
+        while size.x * size.y > MAX_GRID_CELLS {
+            cell_size *= 1.05;
+            size = grid_dims(extent, cell_size);
+        }
+        Self {
+            bounds,
+            size,
+            cell_size,
+            count,
+        }
+    }
+
+    pub fn cell_count(&self) -> u32 {
+        self.size.x * self.size.y
+    }
+
+    pub fn cell_coord(&self, pos: Vec2) -> IVec2 {
+        let cell = ((pos + self.bounds) / self.cell_size).floor().as_ivec2();
+        cell.clamp(IVec2::ZERO, self.size.as_ivec2() - 1)
+    }
+
+    fn cell_index(&self, cell: IVec2) -> usize {
+        cell.y as usize * self.size.x as usize + cell.x as usize
+    }
+}
+
+fn grid_dims(extent: Vec2, cell_size: f32) -> UVec2 {
+    (extent / cell_size).ceil().as_uvec2().max(UVec2::ONE)
+}
+
+/// CPU 上建好的网格，和 GPU 上排好序之后的 `cell_start`、`sorted` 一样
+pub struct CellList {
+    params: GridParams,
+    cell_start: Vec<usize>,
+    sorted: Vec<usize>,
+}
+
+impl CellList {
+    pub fn new(params: GridParams, positions: impl IntoIterator<Item = Vec2>) -> Self {
+        let cells: Vec<usize> = positions
+            .into_iter()
+            .map(|pos| params.cell_index(params.cell_coord(pos)))
+            .collect();
+        let mut cell_start = vec![0usize; params.cell_count() as usize + 1];
+        for &cell in &cells {
+            cell_start[cell + 1] += 1;
+        }
+        for c in 0..params.cell_count() as usize {
+            cell_start[c + 1] += cell_start[c];
+        }
+        // 按下标顺序散射，每个格子里自然是升序
+        let mut cursor = cell_start.clone();
+        let mut sorted = vec![0usize; cells.len()];
+        for (i, &cell) in cells.iter().enumerate() {
+            sorted[cursor[cell]] = i;
+            cursor[cell] += 1;
+        }
+        Self {
+            params,
+            cell_start,
+            sorted,
+        }
+    }
+
+    /// `pos` 周围 3x3 个格子里的粒子下标，按格子从下到上、从左到右，格子里按下标升序，包括自己
+    pub fn neighbors(&self, pos: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let center = self.params.cell_coord(pos);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| center + IVec2::new(dx, dy)))
+            .filter(|cell| {
+                cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.params.size.as_ivec2()).all()
+            })
+            .flat_map(|cell| {
+                let index = self.params.cell_index(cell);
+                &self.sorted[self.cell_start[index]..self.cell_start[index + 1]]
+            })
+            .copied()
+    }
+}
+
+/// GPU 上的网格：参数和四个缓冲区，绑定在第 1 组
+pub struct SpatialGrid {
+    params: GridParams,
+    params_buffer: UniformBuffer<GridParams>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl SpatialGrid {
+    /// 能装下 `count` 个粒子的网格，参数在 [`SpatialGrid::update`] 之前全是 0
+    pub fn new(device: &wgpu::Device, count: u32) -> Result<Self> {
+        let params = GridParams::default();
+        let params_buffer = UniformBuffer::new(device, "Grid Params Buffer", &params)?;
+        let cell_counts = StorageBuffer::<u32>::zeroed(
+            device,
+            "Cell Count Buffer",
+            MAX_GRID_CELLS,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let cell_start = StorageBuffer::<u32>::zeroed(
+            device,
+            "Cell Start Buffer",
+            MAX_GRID_CELLS + 1,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let particle_cells = StorageBuffer::<u32>::zeroed(
+            device,
+            "Particle Cell Buffer",
+            count,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let sorted = StorageBuffer::<u32>::zeroed(
+            device,
+            "Sorted Particle Buffer",
+            count,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Grid Bind Group Layout"),
+            entries: &[
+                params_buffer.layout_entry(0, wgpu::ShaderStages::COMPUTE),
+                cell_counts.layout_entry(1, wgpu::ShaderStages::COMPUTE, false),
+                cell_start.layout_entry(2, wgpu::ShaderStages::COMPUTE, false),
+                particle_cells.layout_entry(3, wgpu::ShaderStages::COMPUTE, false),
+                sorted.layout_entry(4, wgpu::ShaderStages::COMPUTE, false),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grid Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                params_buffer.bind_group_entry(0),
+                cell_counts.bind_group_entry(1),
+                cell_start.bind_group_entry(2),
+                particle_cells.bind_group_entry(3),
+                sorted.bind_group_entry(4),
+            ],
+        });
+        Ok(Self {
+            params,
+            params_buffer,
+            bind_group_layout,
+            bind_group,
+        })
+    }
+
+    /// 每帧模拟之前调用，边界跟着窗口变化
+    pub fn update(&mut self, queue: &wgpu::Queue, params: GridParams) {
+        self.params = params;
+        self.params_buffer.update(queue, &params);
+    }
+
+    pub fn params(&self) -> &GridParams {
+        &self.params
+    }
+
+    /// 放在使用网格的管线布局的第 1 组
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+/// 包含 grid.wgsl 的着色器里建网格的后三步
+pub struct GridPipelines {
+    prefix_sum: wgpu::ComputePipeline,
+    scatter: wgpu::ComputePipeline,
+    sort_cells: wgpu::ComputePipeline,
+}
+
+impl GridPipelines {
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        let pipeline = |entry_point, label| {
+            create_compute_pipeline(device, layout, shader, entry_point, label, cache)
+        };
+        Ok(Self {
+            prefix_sum: pipeline("prefix_sum", "Grid Prefix Sum Pipeline")?,
+            scatter: pipeline("scatter", "Grid Scatter Pipeline")?,
+            sort_cells: pipeline("sort_cells", "Grid Sort Cells Pipeline")?,
+        })
+    }
+
+    /// 在调用了 `grid_insert` 的那一步之后录制，网格的绑定组要已经设好
+    pub fn record(&self, pass: &mut wgpu::ComputePass, grid: &SpatialGrid, workgroup_size: u32) {
+        let particle_groups = grid.params.count.div_ceil(workgroup_size);
+        let cell_groups = grid.params.cell_count().div_ceil(workgroup_size);
+        for (pipeline, groups) in [
+            (&self.prefix_sum, 1),
+            (&self.scatter, particle_groups),
+            (&self.sort_cells, cell_groups),
+        ] {
+            pass.set_pipeline(pipeline);
+            pass.dispatch_workgroups(groups, 1, 1);
+        }
+    }
+}
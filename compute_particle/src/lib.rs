@@ -1,120 +1,213 @@
-use std::time::Duration;
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::anyhow;
+use boids::{Flock, FlockSettings, spawn_flock};
+use emitter::{Direction, Emitter, EmitterShape, Emitters};
+use forces::{Falloff, ForceField, ForceFields};
 use glam::{Vec2, Vec4};
-use wgpu::{SurfaceError, TextureFormat};
-use winit::window::Window;
+use gpu::{
+    ContextOptions, Defines, GpuContext, GpuProfiler, HotShader, Lesson, UniformBuffer,
+    shader_source, validation,
+};
+use lifecycle::CounterReadback;
+use nbody::{Energy, NBody, NBodySettings, spawn_disk};
+use readback::Readback;
+use render_params::{BlendMode, RenderParams, RenderSettings, pixel_to_world, world_bounds};
+use simulation::{ParticleSimulation, SimConfig, compute_shader, init_shader};
+use sort::ParticleSorter;
+use sph::{Fluid, SphSettings, spawn_block};
+use wgpu::SurfaceError;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+    window::Window,
+};
+
+pub mod boids;
+pub mod emitter;
+pub mod forces;
+pub mod grid;
+pub mod lifecycle;
+pub mod nbody;
+pub mod ping_pong;
+pub mod readback;
+pub mod render_params;
+pub mod simulation;
+pub mod sort;
+pub mod sph;
+pub mod wgsl;
 
 const PARTICLE_COUNT: u32 = 1024;
+/// 鸟群模式下鸟的数量
+const BOID_COUNT: u32 = 100_000;
+/// N 体模式下粒子的数量，计算量随它的平方增长
+const NBODY_COUNT: u32 = 4096;
+/// 流体模式下粒子的数量
+const FLUID_COUNT: u32 = 8192;
+/// 计算着色器的工作组大小，通过 `WORKGROUP_SIZE` 宏注入着色器
+const WORKGROUP_SIZE: u32 = 64;
+/// 鼠标吸引子的强度，按住左键吸引、右键排斥
+const MOUSE_STRENGTH: f32 = 3.0;
+/// 每隔多久打印一次计时结果
+const PROFILE_INTERVAL: Duration = Duration::from_secs(2);
 
-pub struct State<'window> {
-    surface: wgpu::Surface<'window>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    render_bind_group: wgpu::BindGroup,
+pub struct State {
+    gpu: GpuContext,
+    /// 粒子模式的模拟，粒子状态每帧从最新的缓冲区读、往另一个写
+    simulation: ParticleSimulation,
+    /// 成对的绑定组，按粒子缓冲区的 `latest_index()` 选用
+    render_bind_groups: [wgpu::BindGroup; 2],
     render_pipeline: wgpu::RenderPipeline,
-    compute_bind_group: wgpu::BindGroup,
-    compute_pipeline: wgpu::ComputePipeline,
-    time_buffer: wgpu::Buffer,
+    render_settings: RenderSettings,
+    render_params_buffer: UniformBuffer<RenderParams>,
+    emitters: Emitters,
+    forces: ForceFields,
+    /// 跟随鼠标的吸引子在 `forces` 里的下标
+    mouse_force: usize,
+    counter_readback: CounterReadback,
+    /// 混合结果和顺序有关时，按年龄排序生成绘制用的索引缓冲区
+    sorter: ParticleSorter,
+    /// F 键切换鸟群模式，N 键切换 N 体模式，W 键切换流体模式
+    mode: Mode,
+    flock: Flock,
+    flock_render_bind_groups: [wgpu::BindGroup; 2],
+    flock_render_pipeline: wgpu::RenderPipeline,
+    nbody: NBody,
+    /// N 体和粒子用同一个渲染管线
+    nbody_render_bind_group: wgpu::BindGroup,
+    energy_readback: Readback<Energy>,
+    /// 第一次读回的总能量，用来算积分的漂移；改参数之后重新记录
+    initial_energy: Option<Energy>,
+    fluid: Fluid,
+    /// 流体也用粒子的渲染管线，颜色在模拟时按密度写好
+    fluid_render_bind_group: wgpu::BindGroup,
+    /// 热重载时用同样的布局重建管线
+    render_pipeline_layout: wgpu::PipelineLayout,
+    shaders: Shaders,
+    profiler: GpuProfiler,
+    /// 上次打印计时结果的时刻和当时的帧号
+    last_report: (Instant, u64),
 }
 
-impl State<'_> {
-    pub async fn new(window: &Window) -> anyhow::Result<Self> {
-        let (instance, _backend) = create_wgpu_instance().await?;
-        let surface = unsafe {
-            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(&window)?)
-        }?;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                label: Some("Device"),
-                memory_hints: wgpu::MemoryHints::default(),
-                trace: wgpu::Trace::Off,
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            })
-            .await?;
-        let caps = surface.get_capabilities(&adapter);
-        let format = TextureFormat::Rgba8Unorm;
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: if caps.formats.contains(&format) {
-                format
-            } else {
-                caps.formats[0]
-            },
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&device, &config);
+struct Shaders {
+    render: HotShader,
+    init: HotShader,
+    compute: HotShader,
+    sort: HotShader,
+    flock: HotShader,
+    flock_render: HotShader,
+    nbody: HotShader,
+    fluid: HotShader,
+}
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("./wgsls/shader.wgsl").into()),
-        });
+/// 当前模拟和渲染的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Particles,
+    Flock,
+    NBody,
+    Fluid,
+}
 
-        let compute_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Bind Group Layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: false },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    {
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::COMPUTE,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }
-                    },
-                ],
-            });
+impl State {
+    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+        let gpu = GpuContext::windowed(
+            window,
+            &ContextOptions {
+                optional_features: wgpu::Features::TIMESTAMP_QUERY,
+                ..Default::default()
+            },
+        )
+        .await?;
+        let device = &gpu.device;
+        let queue = &gpu.queue;
+        let config = gpu.config();
+        let profiler = GpuProfiler::new(device, queue, 3);
 
-        let compute_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Compute Pipeline Layout"),
-                bind_group_layouts: &[&compute_bind_group_layout],
-                immediate_size: 0,
-            });
+        let compute_defines = Defines::new().set("WORKGROUP_SIZE", WORKGROUP_SIZE);
+        let shaders = Shaders {
+            render: HotShader::new(
+                device,
+                shader_source!(
+                    "src/wgsls/shader.wgsl",
+                    includes: ["src/wgsls/particle.wgsl", "src/wgsls/render_params.wgsl"],
+                ),
+            )?,
+            init: init_shader(device, compute_defines.clone())?,
+            compute: compute_shader(device, compute_defines.clone())?,
+            sort: HotShader::with_defines(
+                device,
+                shader_source!("src/wgsls/sort.wgsl", includes: ["src/wgsls/particle.wgsl"]),
+                compute_defines.clone(),
+            )?,
+            flock: HotShader::with_defines(
+                device,
+                shader_source!(
+                    "src/wgsls/boids.wgsl",
+                    includes: ["src/wgsls/particle.wgsl", "src/wgsls/grid.wgsl"],
+                ),
+                compute_defines.clone(),
+            )?,
+            flock_render: HotShader::new(
+                device,
+                shader_source!(
+                    "src/wgsls/boids_render.wgsl",
+                    includes: ["src/wgsls/particle.wgsl", "src/wgsls/render_params.wgsl"],
+                ),
+            )?,
+            nbody: HotShader::with_defines(
+                device,
+                shader_source!("src/wgsls/nbody.wgsl", includes: ["src/wgsls/particle.wgsl"]),
+                nbody::shader_defines(WORKGROUP_SIZE, WORKGROUP_SIZE),
+            )?,
+            fluid: HotShader::with_defines(
+                device,
+                shader_source!(
+                    "src/wgsls/sph.wgsl",
+                    includes: ["src/wgsls/particle.wgsl", "src/wgsls/grid.wgsl"],
+                ),
+                compute_defines,
+            )?,
+        };
+
+        let bounds = world_bounds(config.width, config.height);
+        let simulation = ParticleSimulation::new(
+            device,
+            queue,
+            &SimConfig::new(PARTICLE_COUNT, 0).with_bounds(bounds),
+            shaders.compute.module(),
+            shaders.init.module(),
+            WORKGROUP_SIZE,
+            gpu.pipeline_cache(),
+        )?;
+        let particles = simulation.particles();
+        let render_settings = RenderSettings::default();
+        let render_params_buffer = UniformBuffer::new(
+            device,
+            "Render Params Buffer",
+            &render_settings.to_gpu(config.width, config.height),
+        )?;
+        let mut forces = demo_forces()?;
+        let mouse_force = forces.push(
+            ForceField::attractor(Vec2::ZERO, MOUSE_STRENGTH)
+                .with_falloff(Falloff::InverseSquare, 0.3)
+                .with_enabled(false),
+        )?;
 
         let render_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Render Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
+                entries: &[
+                    particles
+                        .latest()
+                        .layout_entry(0, wgpu::ShaderStages::VERTEX, true),
+                    // 片元着色器按混合方式决定是否预乘
+                    render_params_buffer.layout_entry(1, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                ],
             });
 
         let render_pipeline_layout =
@@ -124,239 +217,783 @@ impl State<'_> {
                 immediate_size: 0,
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                compilation_options: Default::default(),
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            // 图元，描述了如何将顶点数据转换为图元
-            primitive: wgpu::PrimitiveState {
-                // topology: wgpu::PrimitiveTopology::PointList,
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview_mask: None,
-            cache: None,
-        });
+        let render_pipeline = create_render_pipeline(
+            device,
+            &render_pipeline_layout,
+            shaders.render.module(),
+            config.format,
+            render_settings.blend,
+            gpu.pipeline_cache(),
+        )?;
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Init Shader Module"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("./wgsls/compute_init.wgsl").into()),
+        // 渲染读的是计算刚写完的那个缓冲区
+        let render_bind_groups = particles.pair(|source, _| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Render Bind Group"),
+                layout: &render_bind_group_layout,
+                entries: &[
+                    source.bind_group_entry(0),
+                    render_params_buffer.bind_group_entry(1),
+                ],
+            })
         });
 
-        let init_compute_pipeline =
-            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                label: Some("Init Compute Pipeline"),
-                layout: Some(&compute_pipeline_layout),
-                module: &shader,
-                entry_point: Some("main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                cache: None,
-            });
-
-        let particle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Particle Buffer"),
-            size: std::mem::size_of::<Particle>() as u64 * PARTICLE_COUNT as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
-            mapped_at_creation: false,
-        });
+        let counter_readback = CounterReadback::new(device, "计数器读回缓冲区");
+        let sorter = ParticleSorter::new(
+            device,
+            queue,
+            particles,
+            shaders.sort.module(),
+            WORKGROUP_SIZE,
+            gpu.pipeline_cache(),
+        )?;
 
-        let time_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Random Number Buffer"),
-            size: std::mem::size_of::<f32>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        let flock = Flock::new(
+            device,
+            &spawn_flock(BOID_COUNT, 7, bounds),
+            FlockSettings::default(),
+            shaders.flock.module(),
+            WORKGROUP_SIZE,
+            gpu.pipeline_cache(),
+        )?;
+        // 鸟群和粒子共用渲染参数，布局也一样
+        let flock_render_bind_groups = flock.boids().pair(|source, _| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Flock Render Bind Group"),
+                layout: &render_bind_group_layout,
+                entries: &[
+                    source.bind_group_entry(0),
+                    render_params_buffer.bind_group_entry(1),
+                ],
+            })
         });
+        let flock_render_pipeline = create_render_pipeline(
+            device,
+            &render_pipeline_layout,
+            shaders.flock_render.module(),
+            config.format,
+            render_settings.blend,
+            gpu.pipeline_cache(),
+        )?;
 
-        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Compute Bind Group"),
-            layout: &compute_bind_group_layout,
+        let nbody_settings = NBodySettings::default();
+        let nbody = NBody::new(
+            device,
+            queue,
+            &spawn_disk(NBODY_COUNT, 7, &nbody_settings),
+            nbody_settings,
+            shaders.nbody.module(),
+            WORKGROUP_SIZE,
+            gpu.pipeline_cache(),
+        )?;
+        let nbody_render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("NBody Render Bind Group"),
+            layout: &render_bind_group_layout,
             entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: particle_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: time_buffer.as_entire_binding(),
-                },
+                nbody.bodies().bind_group_entry(0),
+                render_params_buffer.bind_group_entry(1),
             ],
         });
+        let energy_readback = Readback::new(device, "能量读回缓冲区");
 
-        // 绑定同一个buffer
-        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Render Bind Group"),
+        let fluid_settings = SphSettings::default();
+        let fluid = Fluid::new(
+            device,
+            &spawn_block(FLUID_COUNT, 7, &fluid_settings, bounds),
+            fluid_settings,
+            shaders.fluid.module(),
+            WORKGROUP_SIZE,
+            gpu.pipeline_cache(),
+        )?;
+        let fluid_render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Fluid Render Bind Group"),
             layout: &render_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: particle_buffer.as_entire_binding(),
-            }],
-        });
-
-        let mut init_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Computer Encoder"),
-        });
-        {
-            let mut compute_pass = init_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Init Computer Pass"),
-                timestamp_writes: None,
-            });
-            compute_pass.set_pipeline(&init_compute_pipeline);
-            compute_pass.set_bind_group(0, &compute_bind_group, &[]);
-            compute_pass.dispatch_workgroups(PARTICLE_COUNT.div_ceil(64), 1, 1);
-        }
-        queue.submit(Some(init_encoder.finish()));
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Compute Shader Module"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("./wgsls/compute.wgsl").into()),
-        });
-
-        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &shader,
-            entry_point: Some("main"),
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
+            entries: &[
+                fluid.particles().bind_group_entry(0),
+                render_params_buffer.bind_group_entry(1),
+            ],
         });
 
         Ok(Self {
-            surface,
-            device,
-            queue,
-            config,
-            render_bind_group,
+            gpu,
+            simulation,
+            render_bind_groups,
             render_pipeline,
-            compute_bind_group,
-            compute_pipeline,
-            time_buffer,
+            render_settings,
+            render_params_buffer,
+            emitters: demo_emitters()?,
+            forces,
+            mouse_force,
+            counter_readback,
+            sorter,
+            mode: Mode::Particles,
+            flock,
+            flock_render_bind_groups,
+            flock_render_pipeline,
+            nbody,
+            nbody_render_bind_group,
+            energy_readback,
+            initial_energy: None,
+            fluid,
+            fluid_render_bind_group,
+            render_pipeline_layout,
+            shaders,
+            profiler,
+            last_report: (Instant::now(), 0),
         })
     }
     pub fn render(&mut self) -> Result<(), SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let output = self.gpu.surface().get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self
+            .gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
+        match self.mode {
+            Mode::Particles => self.record_particles(&mut encoder, &view),
+            Mode::Flock => self.record_flock(&mut encoder, &view),
+            Mode::NBody => self.record_nbody(&mut encoder, &view),
+            Mode::Fluid => self.record_fluid(&mut encoder, &view),
+        }
+        self.profiler.resolve(&mut encoder);
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        self.counter_readback.after_submit(&self.gpu.device);
+        self.energy_readback.after_submit(&self.gpu.device);
+        self.profiler.end_frame(&self.gpu.device, &self.gpu.queue);
+        self.report_timings();
+        Ok(())
+    }
+
+    /// 粒子模式：模拟、按需排序，再画所有粒子
+    fn record_particles(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
         {
-            // 物理模拟
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Compute Pass"),
-                timestamp_writes: None,
-            });
-            compute_pass.set_pipeline(&self.compute_pipeline);
-            compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
-            compute_pass.dispatch_workgroups(PARTICLE_COUNT.div_ceil(64), 1, 1);
+            // 物理模拟，之后刚写入的缓冲区变成最新的，排序和渲染都读它
+            let mut compute_pass = self.profiler.begin_compute_pass(encoder, "粒子更新");
+            self.simulation.record(&mut compute_pass);
+        }
+        self.counter_readback
+            .copy(encoder, self.simulation.counters().buffer());
+        let sorted = self.render_settings.blend.needs_sorting();
+        if sorted {
+            // 按年龄排序，老的粒子先画
+            let mut sort_pass = self.profiler.begin_compute_pass(encoder, "粒子排序");
+            self.sorter
+                .record(&mut sort_pass, self.simulation.particles());
         }
 
         {
             // 渲染
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    depth_slice: None,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                ..Default::default()
-            });
+            let mut render_pass = begin_render_pass(encoder, view, &mut self.profiler, "粒子渲染");
             render_pass.set_pipeline(&self.render_pipeline);
-            // 复用bind_group 它们绑定了同一个buffer
-            render_pass.set_bind_group(0, &self.render_bind_group, &[]);
-            // 画 1024 个点
-            // 画 N 个点，每个点一个顶点
-            // render_pass.draw(0..PARTICLE_COUNT, 0..1);
-            // 0..6 画一个正方形需要的顶点数（两个三角形：0，1，2，和 2，1，3）
-            // 0..n: 就是 instance 的 数量
-            render_pass.draw(0..6, 0..PARTICLE_COUNT);
+            let latest = self.simulation.particles().latest_index();
+            render_pass.set_bind_group(0, &self.render_bind_groups[latest], &[]);
+            // 每个粒子 6 个顶点，画一个正方形（两个三角形：0，1，2，和 2，1，3）
+            // 需要排序时顶点编号来自索引缓冲区，否则按粒子在缓冲区里的顺序画
+            if sorted {
+                render_pass.set_index_buffer(
+                    self.sorter.index_buffer().slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                render_pass.draw_indexed(0..self.sorter.index_count(), 0, 0..1);
+            } else {
+                render_pass.draw(0..PARTICLE_COUNT * 6, 0..1);
+            }
+        }
+    }
+
+    /// 鸟群模式：重建网格、更新每只鸟，再画成三角形
+    fn record_flock(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        {
+            let mut compute_pass = self.profiler.begin_compute_pass(encoder, "鸟群更新");
+            self.flock.record(&mut compute_pass);
+        }
+        let mut render_pass = begin_render_pass(encoder, view, &mut self.profiler, "鸟群渲染");
+        render_pass.set_pipeline(&self.flock_render_pipeline);
+        let latest = self.flock.boids().latest_index();
+        render_pass.set_bind_group(0, &self.flock_render_bind_groups[latest], &[]);
+        // 每只鸟 3 个顶点
+        render_pass.draw(0..self.flock.len() * 3, 0..1);
+    }
+
+    /// N 体模式：跳蛙法积分，上一次能量读完之后再统计一次，粒子按缓冲区顺序画
+    fn record_nbody(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        {
+            let mut compute_pass = self.profiler.begin_compute_pass(encoder, "引力更新");
+            self.nbody.record(&mut compute_pass);
+        }
+        // 能量统计和一小步一样是 O(N²)，没必要每帧都算
+        if self.energy_readback.is_idle() {
+            {
+                let mut energy_pass = self.profiler.begin_compute_pass(encoder, "能量统计");
+                self.nbody.record_energy(&mut energy_pass);
+            }
+            self.energy_readback
+                .copy(encoder, self.nbody.energy_buffer());
+        }
+        let mut render_pass = begin_render_pass(encoder, view, &mut self.profiler, "引力渲染");
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.nbody_render_bind_group, &[]);
+        render_pass.draw(0..self.nbody.len() * 6, 0..1);
+    }
+
+    /// 流体模式：每一小步重建网格、算密度和受力再积分，粒子按密度着色
+    fn record_fluid(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        {
+            let mut compute_pass = self.profiler.begin_compute_pass(encoder, "流体更新");
+            self.fluid.record(&mut compute_pass);
+        }
+        let mut render_pass = begin_render_pass(encoder, view, &mut self.profiler, "流体渲染");
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.fluid_render_bind_group, &[]);
+        render_pass.draw(0..self.fluid.len() * 6, 0..1);
+    }
+
+    /// 定期记录每个通道的耗时和粒子更新的吞吐量
+    fn report_timings(&mut self) {
+        let (last_time, last_frame) = self.last_report;
+        if last_time.elapsed() < PROFILE_INTERVAL {
+            return;
+        }
+        self.last_report = (Instant::now(), self.profiler.frame());
+        if let Some(counters) = self.counter_readback.latest() {
+            tracing::info!(
+                "存活 {} / {} 个粒子，{} 个发射器每秒发射 {:.0} 个",
+                counters.live,
+                PARTICLE_COUNT,
+                self.emitters.len(),
+                self.emitters.total_rate()
+            );
+        }
+        if self.mode == Mode::NBody
+            && let Some(energy) = self.energy_readback.latest()
+        {
+            let initial = *self.initial_energy.get_or_insert(energy);
+            tracing::info!(
+                "总能量 {:.6}（动能 {:.6}，势能 {:.6}），相对漂移 {:.2e}",
+                energy.total(),
+                energy.kinetic,
+                energy.potential,
+                energy.drift(&initial)
+            );
+        }
+        for summary in self.profiler.summary_since(last_frame) {
+            let count = match summary.name.as_str() {
+                "粒子更新" => Some(PARTICLE_COUNT),
+                "鸟群更新" => Some(self.flock.len()),
+                "引力更新" => Some(self.nbody.len()),
+                "流体更新" => Some(self.fluid.len()),
+                _ => None,
+            };
+            if let Some(count) = count
+                && summary.mean_ms > 0.0
+            {
+                let per_second = f64::from(count) / (summary.mean_ms / 1000.0);
+                tracing::info!(
+                    pass = summary.name,
+                    "平均 {:.3} ms，{:.1} 百万粒子/秒",
+                    summary.mean_ms,
+                    per_second / 1e6
+                );
+            } else {
+                tracing::info!(pass = summary.name, "平均 {:.3} ms", summary.mean_ms);
+            }
         }
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-        Ok(())
     }
     pub fn update(&mut self, delta_time: Duration) {
-        // 更新 delta_time
-        // bytemuck::bytes_of(): 将一个值转换为字节数组&[u8]，必须是一个单一的变量引用（比如 &f32, &MyStruct）。
-        // bytemuck::cast_slice(&slice): 将一个切片（一堆）数据转换为字节数组&[u8]，必须是一个切片（Slice），比如 &[f32], &[Particle], 或者 &Vec<T>
-        self.queue.write_buffer(
-            &self.time_buffer,
-            0,
-            bytemuck::bytes_of(&delta_time.as_secs_f32()),
+        self.reload_shaders();
+        let delta_time = delta_time.as_secs_f32();
+        self.simulation.prepare(
+            &self.gpu.queue,
+            &mut self.emitters,
+            &self.forces,
+            delta_time,
         );
+        let bounds = self.simulation.params().bounds;
+        match self.mode {
+            Mode::Flock => self.flock.update(&self.gpu.queue, bounds, delta_time),
+            Mode::NBody => self.nbody.update(&self.gpu.queue),
+            Mode::Fluid => self.fluid.update(&self.gpu.queue, bounds),
+            Mode::Particles => {}
+        }
+    }
+    pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
+        self.gpu.resize(physical_size);
+        self.write_render_params();
     }
-    pub fn resize(&mut self, physical_size: winit::dpi::PhysicalSize<u32>) {
-        if physical_size.width > 0 && physical_size.height > 0 {
-            self.config.width = physical_size.width;
-            self.config.height = physical_size.height;
-            self.surface.configure(&self.device, &self.config);
+
+    /// 粒子大小、大小曲线和混合方式，立即生效
+    pub fn set_render_settings(&mut self, settings: RenderSettings) {
+        let blend_changed = settings.blend != self.render_settings.blend;
+        self.render_settings = settings;
+        self.write_render_params();
+        if blend_changed {
+            self.rebuild_render_pipeline();
         }
     }
-}
 
-async fn create_wgpu_instance() -> anyhow::Result<(wgpu::Instance, wgpu::Backends)> {
-    for backend in wgpu::Backends::all() {
-        if let Some(instance) = try_wgpu_backend(backend).await {
-            return Ok((instance, backend));
+    /// 按 alpha、预乘 alpha、加法的顺序切换混合方式
+    pub fn cycle_blend_mode(&mut self) -> BlendMode {
+        let blend = self.render_settings.blend.next();
+        self.set_render_settings(RenderSettings {
+            blend,
+            ..self.render_settings
+        });
+        tracing::info!("混合方式：{blend:?}");
+        blend
+    }
+
+    /// 着色器或混合方式变化之后重建粒子和鸟群的渲染管线，失败时保留旧管线
+    fn rebuild_render_pipeline(&mut self) {
+        for (shader, pipeline) in [
+            (&self.shaders.render, &mut self.render_pipeline),
+            (&self.shaders.flock_render, &mut self.flock_render_pipeline),
+        ] {
+            match create_render_pipeline(
+                &self.gpu.device,
+                &self.render_pipeline_layout,
+                shader.module(),
+                self.gpu.surface_format(),
+                self.render_settings.blend,
+                self.gpu.pipeline_cache(),
+            ) {
+                Ok(rebuilt) => *pipeline = rebuilt,
+                Err(e) => tracing::warn!("重建渲染管线失败，保留旧管线：{e}"),
+            }
+        }
+    }
+
+    /// 按当前的窗口大小更新渲染参数和模拟边界
+    fn write_render_params(&mut self) {
+        let config = self.gpu.config();
+        let (width, height) = (config.width, config.height);
+        self.render_params_buffer
+            .update(&self.gpu.queue, &self.render_settings.to_gpu(width, height));
+        self.simulation.set_bounds(world_bounds(width, height));
+    }
+
+    /// 共用粒子缓冲区的发射器，修改之后下一帧生效
+    pub fn emitters_mut(&mut self) -> &mut Emitters {
+        &mut self.emitters
+    }
+
+    /// 作用在所有粒子上的力场，修改之后下一帧生效
+    pub fn forces_mut(&mut self) -> &mut ForceFields {
+        &mut self.forces
+    }
+
+    /// 鸟群的分离、对齐、聚集等参数，修改之后下一帧生效
+    pub fn flock_settings_mut(&mut self) -> &mut FlockSettings {
+        self.flock.settings_mut()
+    }
+
+    /// 引力常数、软化长度和步长，修改之后下一帧生效，能量漂移从新的读数开始算
+    pub fn nbody_settings_mut(&mut self) -> &mut NBodySettings {
+        self.initial_energy = None;
+        self.nbody.settings_mut()
+    }
+
+    /// 流体的黏性、压力系数、重力等参数，修改之后下一帧生效
+    pub fn fluid_settings_mut(&mut self) -> &mut SphSettings {
+        self.fluid.settings_mut()
+    }
+
+    /// 在粒子和鸟群之间切换，返回切换之后是不是鸟群模式；切走的那一边暂停模拟
+    pub fn toggle_flock(&mut self) -> bool {
+        self.toggle_mode(Mode::Flock)
+    }
+
+    /// 在粒子和 N 体之间切换，返回切换之后是不是 N 体模式
+    pub fn toggle_nbody(&mut self) -> bool {
+        self.toggle_mode(Mode::NBody)
+    }
+
+    /// 在粒子和流体之间切换，返回切换之后是不是流体模式
+    pub fn toggle_fluid(&mut self) -> bool {
+        self.toggle_mode(Mode::Fluid)
+    }
+
+    fn toggle_mode(&mut self, mode: Mode) -> bool {
+        self.mode = if self.mode == mode {
+            Mode::Particles
+        } else {
+            mode
+        };
+        tracing::info!("模式：{:?}", self.mode);
+        self.mode == mode
+    }
+
+    /// 鼠标吸引子：跟随光标，按住左键吸引、右键排斥；B 键切换混合方式，F 键切换鸟群模式，
+    /// N 键切换 N 体模式，W 键切换流体模式
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        if let WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(code),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                },
+            ..
+        } = event
+        {
+            match code {
+                KeyCode::KeyB => {
+                    self.cycle_blend_mode();
+                    return true;
+                }
+                KeyCode::KeyF => {
+                    self.toggle_flock();
+                    return true;
+                }
+                KeyCode::KeyN => {
+                    self.toggle_nbody();
+                    return true;
+                }
+                KeyCode::KeyW => {
+                    self.toggle_fluid();
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        let Some(mouse) = self.forces.get_mut(self.mouse_force) else {
+            return false;
+        };
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let config = self.gpu.config();
+                let pixel = Vec2::new(position.x as f32, position.y as f32);
+                mouse.position = pixel_to_world(pixel, config.width, config.height);
+            }
+            WindowEvent::CursorLeft { .. } => mouse.enabled = false,
+            WindowEvent::MouseInput { state, button, .. } => {
+                let strength = match button {
+                    MouseButton::Left => MOUSE_STRENGTH,
+                    MouseButton::Right => -MOUSE_STRENGTH,
+                    _ => return false,
+                };
+                mouse.strength = strength;
+                mouse.enabled = *state == ElementState::Pressed;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// 开发模式下检查各个着色器文件，有变化就重建对应的管线，重建失败时保留旧管线
+    fn reload_shaders(&mut self) {
+        // 两个都要检查，不能短路
+        let render_changed = self.shaders.render.poll(&self.gpu.device);
+        if self.shaders.flock_render.poll(&self.gpu.device) | render_changed {
+            self.rebuild_render_pipeline();
+        }
+        let device = &self.gpu.device;
+        if self.shaders.compute.poll(device)
+            && let Err(e) = self.simulation.rebuild(
+                device,
+                self.shaders.compute.module(),
+                self.gpu.pipeline_cache(),
+            )
+        {
+            tracing::warn!("重建计算管线失败，保留旧管线：{e}");
+        }
+        // 初始化着色器只在开始时跑一次，改动后重新初始化所有粒子
+        if self.shaders.init.poll(device)
+            && let Err(e) = self.simulation.reset(
+                device,
+                &self.gpu.queue,
+                self.shaders.init.module(),
+                self.gpu.pipeline_cache(),
+            )
+        {
+            tracing::warn!("重建初始化管线失败：{e}");
+        }
+        if self.shaders.sort.poll(device)
+            && let Err(e) = self.sorter.rebuild(
+                device,
+                self.shaders.sort.module(),
+                self.gpu.pipeline_cache(),
+            )
+        {
+            tracing::warn!("重建排序管线失败，保留旧管线：{e}");
+        }
+        if self.shaders.flock.poll(device)
+            && let Err(e) = self.flock.rebuild(
+                device,
+                self.shaders.flock.module(),
+                self.gpu.pipeline_cache(),
+            )
+        {
+            tracing::warn!("重建鸟群管线失败，保留旧管线：{e}");
+        }
+        if self.shaders.nbody.poll(device)
+            && let Err(e) = self.nbody.rebuild(
+                device,
+                self.shaders.nbody.module(),
+                self.gpu.pipeline_cache(),
+            )
+        {
+            tracing::warn!("重建 N 体管线失败，保留旧管线：{e}");
+        }
+        if self.shaders.fluid.poll(device)
+            && let Err(e) = self.fluid.rebuild(
+                device,
+                self.shaders.fluid.module(),
+                self.gpu.pipeline_cache(),
+            )
+        {
+            tracing::warn!("重建流体管线失败，保留旧管线：{e}");
         }
     }
-    Err(anyhow!("没有找到可用渲染后端"))
 }
-async fn try_wgpu_backend(backend: wgpu::Backends) -> Option<wgpu::Instance> {
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        backends: backend,
-        flags: wgpu::InstanceFlags::default().with_env(),
+
+/// 演示用的力场：重力、阻力、中间的漩涡和一点湍流
+fn demo_forces() -> anyhow::Result<ForceFields> {
+    let mut forces = ForceFields::new();
+    forces.push(ForceField::gravity(Vec2::NEG_Y, 0.4))?;
+    forces.push(ForceField::drag(0.3, 0.2))?;
+    forces.push(ForceField::vortex(Vec2::ZERO, 0.8).with_falloff(Falloff::Linear, 0.5))?;
+    forces.push(ForceField::curl_noise(3.0, 0.2, 0.15))?;
+    Ok(forces)
+}
+
+/// 演示用的发射器：每种形状一个，稳定之后大约占满粒子缓冲区
+fn demo_emitters() -> anyhow::Result<Emitters> {
+    let mut emitters = Emitters::new();
+    // 底部的喷泉
+    emitters.push(
+        Emitter::new(
+            EmitterShape::Cone {
+                angle: FRAC_PI_2,
+                spread: 0.4,
+            },
+            Vec2::new(0.0, -0.95),
+        )
+        .with_rate(80.0)
+        .with_speed(0.9, 1.3)
+        .with_lifetime(1.5, 2.5)
+        .with_colors(Vec4::new(1.0, 0.8, 0.2, 1.0), Vec4::new(1.0, 0.3, 0.0, 1.0)),
+    )?;
+    // 中间向外扩散的圆环
+    emitters.push(
+        Emitter::new(
+            EmitterShape::Circle {
+                radius: 0.25,
+                inner_radius: 0.2,
+            },
+            Vec2::ZERO,
+        )
+        .with_direction(Direction::Outward { spread: 0.3 })
+        .with_rate(80.0)
+        .with_speed(0.1, 0.3)
+        .with_lifetime(1.0, 2.0)
+        .with_colors(Vec4::new(0.2, 0.6, 1.0, 1.0), Vec4::new(0.4, 1.0, 1.0, 1.0)),
+    )?;
+    // 顶部落下的雨
+    emitters.push(
+        Emitter::new(
+            EmitterShape::Line {
+                end: Vec2::new(1.8, 0.0),
+            },
+            Vec2::new(-0.9, 0.95),
+        )
+        .with_direction(Direction::Angle {
+            angle: -FRAC_PI_2,
+            spread: 0.1,
+        })
+        .with_rate(60.0)
+        .with_speed(0.5, 0.8)
+        .with_lifetime(2.0, 3.0)
+        .with_colors(Vec4::new(0.6, 0.6, 0.9, 1.0), Vec4::new(0.8, 0.8, 1.0, 1.0)),
+    )?;
+    // 左右两侧的火花
+    emitters.push(
+        Emitter::new(
+            EmitterShape::Box {
+                half_size: Vec2::new(0.1, 0.1),
+            },
+            Vec2::new(-0.6, 0.0),
+        )
+        .with_rate(40.0)
+        .with_speed(0.05, 0.2)
+        .with_lifetime(1.0, 3.0)
+        .with_colors(Vec4::new(0.2, 1.0, 0.3, 1.0), Vec4::new(0.9, 1.0, 0.2, 1.0)),
+    )?;
+    emitters.push(
+        Emitter::new(EmitterShape::Point, Vec2::new(0.6, 0.0))
+            .with_direction(Direction::Angle {
+                angle: PI,
+                spread: PI,
+            })
+            .with_rate(40.0)
+            .with_speed(0.2, 0.5)
+            .with_lifetime(1.0, 2.0)
+            .with_colors(Vec4::new(1.0, 0.2, 0.8, 1.0), Vec4::new(1.0, 1.0, 1.0, 1.0)),
+    )?;
+    Ok(emitters)
+}
+
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    blend: BlendMode,
+    cache: Option<&wgpu::PipelineCache>,
+) -> anyhow::Result<wgpu::RenderPipeline> {
+    validation::create_render_pipeline(
+        device,
+        &wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                compilation_options: Default::default(),
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // 粒子临死前淡出，需要混合；混合方式和片元着色器的输出对应
+                    blend: Some(blend.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            // 图元，描述了如何将顶点数据转换为图元
+            primitive: wgpu::PrimitiveState {
+                // topology: wgpu::PrimitiveTopology::PointList,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview_mask: None,
+            cache,
+        },
+    )
+}
+
+pub(crate) fn create_compute_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    entry_point: &str,
+    label: &str,
+    cache: Option<&wgpu::PipelineCache>,
+) -> anyhow::Result<wgpu::ComputePipeline> {
+    validation::create_compute_pipeline(
+        device,
+        &wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            module: shader,
+            entry_point: Some(entry_point),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache,
+        },
+    )
+}
+
+/// 清屏并开始渲染通道，`name` 是计时结果里的通道名
+fn begin_render_pass<'encoder>(
+    encoder: &'encoder mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+    profiler: &mut GpuProfiler,
+    name: &str,
+) -> wgpu::RenderPass<'encoder> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            depth_slice: None,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        timestamp_writes: profiler.render_timestamp_writes(name),
         ..Default::default()
-    });
-    if instance.enumerate_adapters(backend).await.is_empty() {
-        None
-    } else {
-        Some(instance)
+    })
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        self.profiler.write_reports_from_env("compute_particle");
+    }
+}
+
+impl Lesson for State {
+    fn update(&mut self, delta_time: Duration) {
+        State::update(self, delta_time);
+    }
+
+    fn render(&mut self) -> Result<(), SurfaceError> {
+        State::render(self)
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        State::resize(self, size);
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        State::input(self, event)
     }
 }
 
 #[repr(C)] // 保证结构体的内存布局和C语言一致，用于和C语言交互，共享数据
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct Particle {
-    pos: Vec2,
-    vel: Vec2,
-    color: Vec4,
-    life: f32,
-    _padding: [f32; 3],
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Particle {
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub color: Vec4,
+    /// 剩余寿命（秒），小于等于 0 表示已经死亡
+    pub life: f32,
+    /// 出生时的寿命
+    pub max_life: f32,
+    /// 质量，只有 N 体引力模拟用到
+    pub mass: f32,
+    // WGSL 里 vec4 按 16 字节对齐，结构体大小要补齐到 48
+    _padding: f32,
+}
+
+impl Particle {
+    /// 刚出生的粒子，能活 `lifetime` 秒，质量为 1
+    pub fn new(pos: Vec2, vel: Vec2, color: Vec4, lifetime: f32) -> Self {
+        Self {
+            pos,
+            vel,
+            color,
+            life: lifetime,
+            max_life: lifetime,
+            mass: 1.0,
+            _padding: 0.0,
+        }
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = mass;
+        self
+    }
 }
 /// 🎨 标准 sRGB 转 Linear RGB 转换器
 ///
@@ -0,0 +1,78 @@
+//! 粒子的寿命和重生
+//!
+//! 每个粒子出生时在寿命范围里随机取一个寿命，每帧减去经过的时间，
+//! 渲染时按剩余寿命的比例缩小、淡出。寿命用完的粒子每帧按下标顺序领一个号码（总数是 [`Counters::dead`]），
+//! 号码小于这一帧的重生名额（[`SimParams::spawn_count`]）就由对应的[发射器](crate::emitter)重生，
+//! 名额由 [`SpawnBudget`] 按每秒的重生速率换算，粒子就能源源不断地喷出来，而不是一次性炸开。
+//!
+//! 活着的粒子数用原子操作在着色器里累加，[`CounterReadback`] 把计数器异步读回 CPU。
+use glam::Vec2;
+
+use crate::readback::Readback;
+
+/// 计算着色器每帧读取的模拟参数，和 sim.wgsl 里的 `SimParams` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SimParams {
+    pub delta_time: f32,
+    /// 这一帧所有发射器的重生名额之和
+    pub spawn_count: u32,
+    /// 每帧变化的随机数种子
+    pub seed: u32,
+    /// 发射器缓冲区里有效的发射器个数
+    pub emitter_count: u32,
+    /// 模拟开始以来的秒数，驱动随时间变化的力场
+    pub time: f32,
+    /// 力场缓冲区里有效的力场个数
+    pub force_count: u32,
+    /// 世界坐标的边界，见 [`world_bounds`](crate::render_params::world_bounds)
+    pub bounds: Vec2,
+}
+
+/// 着色器里用原子操作累加的计数器，和 sim.wgsl 里的 `Counters` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Counters {
+    /// 这一帧结束时还活着的粒子
+    pub live: u32,
+    /// 这一帧开始时已经死亡的粒子
+    pub dead: u32,
+}
+
+/// 把每秒的重生速率换算成每帧的名额，不足一个的部分留到下一帧
+#[derive(Debug, Clone, Default)]
+pub struct SpawnBudget {
+    rate: f32,
+    carry: f32,
+}
+
+impl SpawnBudget {
+    /// 每秒重生 `rate` 个
+    pub fn new(rate: f32) -> Self {
+        Self { rate, carry: 0.0 }
+    }
+
+    /// 粒子总数保持在 `count` 左右需要的速率：平均每个粒子活 `mean_lifetime` 秒
+    pub fn steady(count: u32, mean_lifetime: f32) -> Self {
+        Self::new(count as f32 / mean_lifetime.max(f32::EPSILON))
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate.max(0.0);
+    }
+
+    /// 经过 `delta_time` 秒之后这一帧的名额
+    pub fn take(&mut self, delta_time: f32) -> u32 {
+        let total = self.carry + self.rate * delta_time;
+        let count = total.floor();
+        self.carry = total - count;
+        count as u32
+    }
+}
+
+/// 把计数器缓冲区异步读回 CPU
+pub type CounterReadback = Readback<Counters>;
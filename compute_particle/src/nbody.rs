@@ -0,0 +1,384 @@
+//! N 体引力模拟
+//!
+//! 每个粒子受到其他所有粒子的引力，计算量是 O(N²)。着色器按工作组分块：整个工作组先把一块粒子的
+//! 位置和质量读进共享内存，每个线程再和这一块里的所有粒子逐一计算，全局内存的读取次数减少到
+//! 原来的 1/工作组大小。两个粒子靠得很近时引力会发散，距离的平方加上软化长度的平方来避免。
+//!
+//! 积分用跳蛙法（kick-drift-kick）：速度推进半步、位置推进一步、用新位置的加速度再把速度推进半步。
+//! 跳蛙法是辛积分，总能量不会像显式欧拉那样一直漂移，[`NBody::record_energy`] 在 GPU 上算出
+//! 总的动能和势能（先在工作组里归约，再把各组的部分和归约成一个），用来检查积分的误差。
+//!
+//! ```ignore
+//! let bodies = spawn_disk(4096, 7, &NBodySettings::default());
+//! let nbody = NBody::new(device, queue, &bodies, NBodySettings::default(), shader, 64, None)?;
+//! nbody.record(&mut compute_pass);
+//! ```
+use std::f32::consts::TAU;
+
+use anyhow::{Result, bail};
+use glam::{Vec2, Vec4};
+use gpu::{Defines, StorageBuffer, UniformBuffer};
+
+use crate::{Particle, create_compute_pipeline, wgsl::pcg_hash};
+
+/// nbody.wgsl 的宏
+///
+/// 着色器按 `TILE_SIZE` 分块读粒子、从 `TILE_SIZE / 2` 开始折半归约，共享内存数组的长度却是
+/// `WORKGROUP_SIZE`，两者不相等或者不是 2 的幂时归约会越界或者漏加。
+pub fn shader_defines(workgroup_size: u32, tile_size: u32) -> Defines {
+    assert_eq!(
+        tile_size, workgroup_size,
+        "N 体模拟的分块大小 {tile_size} 必须等于工作组大小 {workgroup_size}"
+    );
+    assert!(
+        workgroup_size.is_power_of_two(),
+        "工作组大小 {workgroup_size} 不是 2 的幂，没法做归约"
+    );
+    Defines::new()
+        .set("WORKGROUP_SIZE", workgroup_size)
+        .set("TILE_SIZE", tile_size)
+}
+
+/// 可以在运行时修改的模拟参数，下一帧生效
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NBodySettings {
+    /// 引力常数
+    pub gravity: f32,
+    /// 软化长度
+    pub softening: f32,
+    /// 每一小步的时间，固定步长能量守恒得更好
+    pub time_step: f32,
+    /// 每帧走几小步
+    pub substeps: u32,
+}
+
+impl Default for NBodySettings {
+    fn default() -> Self {
+        Self {
+            gravity: 0.2,
+            softening: 0.02,
+            time_step: 1.0 / 240.0,
+            substeps: 4,
+        }
+    }
+}
+
+impl NBodySettings {
+    pub fn to_gpu(&self, count: u32) -> NBodyParams {
+        NBodyParams {
+            count,
+            delta_time: self.time_step,
+            gravity: self.gravity,
+            softening: self.softening,
+        }
+    }
+}
+
+/// 和 nbody.wgsl 里的 `NBodyParams` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct NBodyParams {
+    pub count: u32,
+    pub delta_time: f32,
+    pub gravity: f32,
+    pub softening: f32,
+}
+
+/// 总的动能和势能，和 nbody.wgsl 里的 `Energy` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Energy {
+    pub kinetic: f32,
+    pub potential: f32,
+}
+
+impl Energy {
+    pub fn total(&self) -> f32 {
+        self.kinetic + self.potential
+    }
+
+    /// 相对 `initial` 的漂移比例
+    pub fn drift(&self, initial: &Energy) -> f32 {
+        (self.total() - initial.total()) / initial.total().abs().max(f32::EPSILON)
+    }
+}
+
+/// 绕中心旋转的圆盘：中间一个和整个圆盘一样重的天体，其余的粒子在半径 0.8 以内按圆轨道运动
+pub fn spawn_disk(count: u32, seed: u32, settings: &NBodySettings) -> Vec<Particle> {
+    const RADIUS: f32 = 0.8;
+    let disk_mass = 1.0;
+    let body_mass = disk_mass / count.saturating_sub(1).max(1) as f32;
+    let mut bodies = vec![
+        Particle::new(Vec2::ZERO, Vec2::ZERO, Vec4::new(1.0, 0.9, 0.6, 1.0), 1.0)
+            .with_mass(disk_mass),
+    ];
+    bodies.extend((1..count).map(|i| {
+        let random = |salt: u32| pcg_hash(i.wrapping_mul(2).wrapping_add(salt) ^ seed);
+        // 面积均匀分布，离中心太近的轨道太快，留出一个空洞
+        let r = RADIUS * (0.05 + 0.95 * random(0)).sqrt();
+        let angle = random(1) * TAU;
+        let direction = Vec2::from_angle(angle);
+        // 半径以内的质量：中心天体加上按面积算的那部分圆盘
+        let enclosed = disk_mass * (1.0 + (r / RADIUS).powi(2));
+        let speed = (settings.gravity * enclosed / r).sqrt();
+        let t = r / RADIUS;
+        let color = Vec4::new(1.0 - 0.6 * t, 0.6 + 0.2 * t, 0.4 + 0.6 * t, 1.0);
+        Particle::new(direction * r, direction.perp() * speed, color, 1.0).with_mass(body_mass)
+    }));
+    bodies
+}
+
+/// 第 `i` 个粒子受到的加速度和所在位置的引力势，和 nbody.wgsl 里的 `interact` 一致
+pub fn interact(params: &NBodyParams, bodies: &[Particle], i: usize) -> (Vec2, f32) {
+    let softening_sq = params.softening * params.softening;
+    let pos = bodies[i].pos;
+    let mut acceleration = Vec2::ZERO;
+    let mut potential = 0.0;
+    for (j, other) in bodies.iter().enumerate() {
+        if j == i || other.mass == 0.0 {
+            continue;
+        }
+        let offset = other.pos - pos;
+        let inverse = 1.0 / (offset.dot(offset) + softening_sq).sqrt();
+        acceleration += other.mass * inverse * inverse * inverse * offset;
+        potential -= other.mass * inverse;
+    }
+    (acceleration * params.gravity, potential * params.gravity)
+}
+
+/// 在 CPU 上走一小步，`accelerations` 是上一步结束时的加速度，走完之后更新
+pub fn cpu_step(params: &NBodyParams, bodies: &mut [Particle], accelerations: &mut [Vec2]) {
+    let dt = params.delta_time;
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations.iter()) {
+        body.vel += *acceleration * (0.5 * dt);
+        body.pos += body.vel * dt;
+    }
+    for (i, acceleration) in accelerations.iter_mut().enumerate() {
+        *acceleration = interact(params, bodies, i).0;
+    }
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations.iter()) {
+        body.vel += *acceleration * (0.5 * dt);
+    }
+}
+
+/// 在 CPU 上算总能量，每一对的势能只算一次
+pub fn cpu_energy(params: &NBodyParams, bodies: &[Particle]) -> Energy {
+    bodies
+        .iter()
+        .enumerate()
+        .fold(Energy::default(), |energy, (i, body)| Energy {
+            kinetic: energy.kinetic + 0.5 * body.mass * body.vel.length_squared(),
+            potential: energy.potential + 0.5 * body.mass * interact(params, bodies, i).1,
+        })
+}
+
+/// GPU 上的 N 体模拟：粒子和加速度缓冲区、能量归约的缓冲区和四个计算管线
+pub struct NBody {
+    bodies: StorageBuffer<Particle>,
+    total_energy: StorageBuffer<Energy>,
+    settings: NBodySettings,
+    params_buffer: UniformBuffer<NBodyParams>,
+    workgroup_size: u32,
+    bind_group: wgpu::BindGroup,
+    /// 热重载时用同样的布局重建管线
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: NBodyPipelines,
+}
+
+struct NBodyPipelines {
+    drift: wgpu::ComputePipeline,
+    accelerate: wgpu::ComputePipeline,
+    energy: wgpu::ComputePipeline,
+    reduce_energy: wgpu::ComputePipeline,
+}
+
+impl NBody {
+    /// `shader` 是按 `workgroup_size` 编译的 nbody.wgsl，`workgroup_size` 必须是 2 的幂
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bodies: &[Particle],
+        settings: NBodySettings,
+        shader: &wgpu::ShaderModule,
+        workgroup_size: u32,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        if bodies.is_empty() {
+            bail!("N 体模拟至少要有一个粒子");
+        }
+        if !workgroup_size.is_power_of_two() {
+            bail!("工作组大小 {workgroup_size} 不是 2 的幂，没法做归约");
+        }
+        let count = bodies.len() as u32;
+        let body_buffer =
+            StorageBuffer::from_slice(device, "Body Buffer", bodies, wgpu::BufferUsages::empty())?;
+        let accelerations = StorageBuffer::<Vec2>::zeroed(
+            device,
+            "Acceleration Buffer",
+            count,
+            wgpu::BufferUsages::empty(),
+        )?;
+        // 先用 0 步长算一次加速度，跳蛙法的第一个半步要用
+        let params_buffer = UniformBuffer::new(
+            device,
+            "NBody Params Buffer",
+            &NBodyParams {
+                delta_time: 0.0,
+                ..settings.to_gpu(count)
+            },
+        )?;
+        let partial_energy = StorageBuffer::<Energy>::zeroed(
+            device,
+            "Partial Energy Buffer",
+            count.div_ceil(workgroup_size),
+            wgpu::BufferUsages::empty(),
+        )?;
+        let total_energy = StorageBuffer::<Energy>::zeroed(
+            device,
+            "Total Energy Buffer",
+            1,
+            wgpu::BufferUsages::empty(),
+        )?;
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("NBody Bind Group Layout"),
+            entries: &[
+                body_buffer.layout_entry(0, wgpu::ShaderStages::COMPUTE, false),
+                accelerations.layout_entry(1, wgpu::ShaderStages::COMPUTE, false),
+                params_buffer.layout_entry(2, wgpu::ShaderStages::COMPUTE),
+                partial_energy.layout_entry(3, wgpu::ShaderStages::COMPUTE, false),
+                total_energy.layout_entry(4, wgpu::ShaderStages::COMPUTE, false),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("NBody Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                body_buffer.bind_group_entry(0),
+                accelerations.bind_group_entry(1),
+                params_buffer.bind_group_entry(2),
+                partial_energy.bind_group_entry(3),
+                total_energy.bind_group_entry(4),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("NBody Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipelines = NBodyPipelines::new(device, &pipeline_layout, shader, cache)?;
+
+        let nbody = Self {
+            bodies: body_buffer,
+            total_energy,
+            settings,
+            params_buffer,
+            workgroup_size,
+            bind_group,
+            pipeline_layout,
+            pipelines,
+        };
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("NBody Init Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("NBody Init Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, &nbody.bind_group, &[]);
+            pass.set_pipeline(&nbody.pipelines.accelerate);
+            pass.dispatch_workgroups(nbody.groups(), 1, 1);
+        }
+        queue.submit(Some(encoder.finish()));
+        nbody.update(queue);
+        Ok(nbody)
+    }
+
+    pub fn settings(&self) -> &NBodySettings {
+        &self.settings
+    }
+
+    /// 修改之后下一次 [`NBody::update`] 生效
+    pub fn settings_mut(&mut self) -> &mut NBodySettings {
+        &mut self.settings
+    }
+
+    /// 每帧模拟之前调用，写入最新的参数；步长固定，和帧时间无关
+    pub fn update(&self, queue: &wgpu::Queue) {
+        self.params_buffer
+            .update(queue, &self.settings.to_gpu(self.len()));
+    }
+
+    /// 着色器改动之后重建管线，失败时保留旧管线
+    pub fn rebuild(
+        &mut self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<()> {
+        self.pipelines = NBodyPipelines::new(device, &self.pipeline_layout, shader, cache)?;
+        Ok(())
+    }
+
+    /// 在计算通道里录制这一帧的 `substeps` 小步
+    pub fn record(&self, pass: &mut wgpu::ComputePass) {
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        for _ in 0..self.settings.substeps {
+            pass.set_pipeline(&self.pipelines.drift);
+            pass.dispatch_workgroups(self.groups(), 1, 1);
+            pass.set_pipeline(&self.pipelines.accelerate);
+            pass.dispatch_workgroups(self.groups(), 1, 1);
+        }
+    }
+
+    /// 录制能量的计算和归约，结果在 [`NBody::energy_buffer`] 里
+    pub fn record_energy(&self, pass: &mut wgpu::ComputePass) {
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_pipeline(&self.pipelines.energy);
+        pass.dispatch_workgroups(self.groups(), 1, 1);
+        pass.set_pipeline(&self.pipelines.reduce_energy);
+        pass.dispatch_workgroups(1, 1, 1);
+    }
+
+    /// 一个 [`Energy`]
+    pub fn energy_buffer(&self) -> &wgpu::Buffer {
+        self.total_energy.buffer()
+    }
+
+    /// 粒子的状态，渲染时只读绑定
+    pub fn bodies(&self) -> &StorageBuffer<Particle> {
+        &self.bodies
+    }
+
+    pub fn len(&self) -> u32 {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn groups(&self) -> u32 {
+        self.len().div_ceil(self.workgroup_size)
+    }
+}
+
+impl NBodyPipelines {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        let pipeline = |entry_point, label| {
+            create_compute_pipeline(device, layout, shader, entry_point, label, cache)
+        };
+        Ok(Self {
+            drift: pipeline("drift", "NBody Drift Pipeline")?,
+            accelerate: pipeline("accelerate", "NBody Accelerate Pipeline")?,
+            energy: pipeline("energy", "NBody Energy Pipeline")?,
+            reduce_energy: pipeline("reduce_energy", "NBody Reduce Energy Pipeline")?,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+//! 交替读写的一对状态缓冲区
+//!
+//! 粒子之间有相互作用时，原地更新是错的：有的邻居已经是这一帧的新状态，有的还是上一帧的。
+//! 这里准备两个缓冲区，每一步从最新的那个读、往另一个写，写完之后交换，所有粒子读到的都是上一步的状态。
+//! 绑定组也成对创建，[`PingPong::pair`] 的第 `i` 个从缓冲区 `i` 读、往另一个写，
+//! 按 [`PingPong::latest_index`] 选用；渲染和读回用 [`PingPong::latest`]。
+//!
+//! ```ignore
+//! let particles = PingPong::<Particle>::zeroed(device, "Particle Buffer", 1024, usage)?;
+//! let bind_groups = particles.pair(|source, destination| create_bind_group(source, destination));
+//! pass.set_bind_group(0, &bind_groups[particles.latest_index()], &[]);
+//! pass.dispatch_workgroups(groups, 1, 1);
+//! particles.swap();
+//! ```
+use anyhow::Result;
+use bytemuck::Pod;
+use gpu::StorageBuffer;
+
+pub struct PingPong<T> {
+    buffers: [StorageBuffer<T>; 2],
+    /// 最近写入的缓冲区
+    latest: usize,
+}
+
+impl<T: Pod> PingPong<T> {
+    /// 两个缓冲区都清零，`usage` 是 `STORAGE | COPY_DST | COPY_SRC` 之外的额外用途
+    pub fn zeroed(
+        device: &wgpu::Device,
+        label: &str,
+        len: u32,
+        usage: wgpu::BufferUsages,
+    ) -> Result<Self> {
+        Ok(Self {
+            buffers: [
+                StorageBuffer::zeroed(device, &format!("{label} A"), len, usage)?,
+                StorageBuffer::zeroed(device, &format!("{label} B"), len, usage)?,
+            ],
+            latest: 0,
+        })
+    }
+
+    /// 两个缓冲区都用 `data` 初始化
+    pub fn from_slice(
+        device: &wgpu::Device,
+        label: &str,
+        data: &[T],
+        usage: wgpu::BufferUsages,
+    ) -> Result<Self> {
+        Ok(Self {
+            buffers: [
+                StorageBuffer::from_slice(device, &format!("{label} A"), data, usage)?,
+                StorageBuffer::from_slice(device, &format!("{label} B"), data, usage)?,
+            ],
+            latest: 0,
+        })
+    }
+
+    /// 按顺序为两个方向各创建一份资源，第 `i` 个的参数是（缓冲区 `i`，另一个缓冲区）
+    pub fn pair<U>(&self, mut f: impl FnMut(&StorageBuffer<T>, &StorageBuffer<T>) -> U) -> [U; 2] {
+        let [a, b] = &self.buffers;
+        [f(a, b), f(b, a)]
+    }
+
+    /// 最近写入的缓冲区，下一步从它读；渲染和读回也用它
+    pub fn latest(&self) -> &StorageBuffer<T> {
+        &self.buffers[self.latest]
+    }
+
+    /// 用来从 [`PingPong::pair`] 创建的资源里选这一步用的那个
+    pub fn latest_index(&self) -> usize {
+        self.latest
+    }
+
+    /// 录制完一步之后调用，刚写入的缓冲区变成最新的
+    pub fn swap(&mut self) {
+        self.latest = 1 - self.latest;
+    }
+
+    pub fn len(&self) -> u32 {
+        self.buffers[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
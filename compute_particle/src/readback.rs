@@ -0,0 +1,120 @@
+//! 把着色器算出来的小块结果（计数器、能量）异步读回 CPU
+//!
+//! 测试和无窗口运行时用 [`read_buffer`] 阻塞地读回整个缓冲区。
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Result, anyhow};
+use bytemuck::Pod;
+use gpu::StorageBuffer;
+
+/// 异步读回一个 `T`，上一次没读完之前不会再复制，不阻塞渲染
+pub struct Readback<T> {
+    buffer: wgpu::Buffer,
+    state: ReadbackState,
+    latest: Option<T>,
+}
+
+enum ReadbackState {
+    Idle,
+    /// 复制命令已经录进编码器，等待提交
+    Copied,
+    /// 提交之后开始映射，映射完成时写入结果
+    Mapping(Arc<OnceLock<bool>>),
+}
+
+impl<T: Pod> Readback<T> {
+    pub fn new(device: &wgpu::Device, label: &str) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size_of::<T>() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            state: ReadbackState::Idle,
+            latest: None,
+        }
+    }
+
+    /// 上一次已经读完，这一帧录制的复制会生效
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, ReadbackState::Idle)
+    }
+
+    /// 在计算之后录制复制命令，上一次还没读完时什么也不做
+    pub fn copy(&mut self, encoder: &mut wgpu::CommandEncoder, source: &wgpu::Buffer) {
+        if self.is_idle() {
+            encoder.copy_buffer_to_buffer(source, 0, &self.buffer, 0, self.buffer.size());
+            self.state = ReadbackState::Copied;
+        }
+    }
+
+    /// 提交之后调用：开始映射，并收集已经完成的结果
+    pub fn after_submit(&mut self, device: &wgpu::Device) {
+        if matches!(self.state, ReadbackState::Copied) {
+            let done = Arc::new(OnceLock::new());
+            let slot = done.clone();
+            self.buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = slot.set(result.is_ok());
+                });
+            self.state = ReadbackState::Mapping(done);
+        }
+        let _ = device.poll(wgpu::PollType::Poll);
+        if let ReadbackState::Mapping(done) = &self.state
+            && let Some(&ok) = done.get()
+        {
+            if ok {
+                let bytes = self.buffer.slice(..).get_mapped_range();
+                self.latest = Some(*bytemuck::from_bytes(&bytes));
+                drop(bytes);
+                self.buffer.unmap();
+            }
+            self.state = ReadbackState::Idle;
+        }
+    }
+
+    /// 最近一次读回的结果，还没有读回过时返回 `None`
+    pub fn latest(&self) -> Option<T> {
+        self.latest
+    }
+}
+
+/// 把 `source` 复制到临时缓冲区，等 GPU 做完之前提交的所有工作之后读回来
+pub fn read_buffer<T: Pod>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    source: &StorageBuffer<T>,
+) -> Result<Vec<T>> {
+    let size = source.buffer().size();
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("读回缓冲区"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(source.buffer(), 0, &staging, 0, size);
+    queue.submit(Some(encoder.finish()));
+
+    let done = Arc::new(OnceLock::new());
+    let slot = done.clone();
+    staging
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |result| {
+            let _ = slot.set(result);
+        });
+    device.poll(wgpu::PollType::wait_indefinitely())?;
+    match done.get() {
+        Some(Ok(())) => {}
+        Some(Err(e)) => return Err(anyhow!("映射读回缓冲区失败：{e}")),
+        None => return Err(anyhow!("等待之后读回缓冲区还没有映射")),
+    }
+    let data = bytemuck::cast_slice(&staging.slice(..).get_mapped_range()).to_vec();
+    staging.unmap();
+    Ok(data)
+}
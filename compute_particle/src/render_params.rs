@@ -0,0 +1,169 @@
+//! 粒子的渲染参数
+//!
+//! 模拟在世界坐标里进行：y 的范围是 [-1, 1]，x 的范围随窗口的长宽比变化，是 [-aspect, aspect]，
+//! 粒子反弹的边界就是真正的窗口边缘。渲染时 x 再除以长宽比回到裁剪空间，窗口不是正方形时粒子也是圆的。
+//!
+//! 粒子大小可以按像素（窗口缩放时大小不变）或者按世界单位（跟着窗口一起缩放）给出，
+//! 还可以用 [`SizeCurve`] 让大小随年龄变化。窗口大小变化时 `State::resize` 重新写入 [`RenderParams`]。
+//!
+//! 粒子之间怎么叠加由 [`BlendMode`] 决定：加法混合和顺序无关，两种 alpha 混合都要从后往前画，
+//! 这时先用[排序](crate::sort)生成的索引缓冲区调整绘制顺序。
+use glam::{Vec2, Vec4};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParticleSize {
+    /// 半径的像素数
+    Pixels(f32),
+    /// 世界坐标里的半径，窗口高度是 2
+    World(f32),
+}
+
+/// 大小随年龄变化的曲线：出生、1/3、2/3 和死亡时的倍数，中间线性插值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeCurve(pub [f32; 4]);
+
+impl SizeCurve {
+    /// 大小不变
+    pub const CONSTANT: Self = Self([1.0; 4]);
+
+    /// `age` 是已经活过的比例，0 是刚出生，1 是寿命用完；和 shader.wgsl 里的 `size_over_life` 一致
+    pub fn sample(&self, age: f32) -> f32 {
+        let x = age.clamp(0.0, 1.0) * 3.0;
+        let i = (x as usize).min(2);
+        let t = x - i as f32;
+        self.0[i] + (self.0[i + 1] - self.0[i]) * t
+    }
+}
+
+/// 粒子颜色和背景的混合方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// 普通的 alpha 混合，片元输出未预乘的颜色
+    #[default]
+    Alpha,
+    /// 预乘 alpha：片元输出 `rgb * a`，半透明边缘不会发暗
+    Premultiplied,
+    /// 颜色直接相加，越叠越亮，适合火焰和火花
+    Additive,
+}
+
+impl BlendMode {
+    pub const ALL: [Self; 3] = [Self::Alpha, Self::Premultiplied, Self::Additive];
+
+    /// 按 [`BlendMode::ALL`] 的顺序切换到下一种
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// 渲染管线用的混合状态，和片元着色器的输出对应
+    pub fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            Self::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            Self::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            Self::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            },
+        }
+    }
+
+    /// 混合结果和绘制顺序有关，需要先排序
+    pub fn needs_sorting(self) -> bool {
+        !matches!(self, Self::Additive)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    pub size: ParticleSize,
+    /// `None` 时大小不随年龄变化
+    pub size_over_life: Option<SizeCurve>,
+    /// 改变混合方式要重建渲染管线
+    pub blend: BlendMode,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            size: ParticleSize::Pixels(8.0),
+            // 临死前逐渐缩小到 0
+            size_over_life: Some(SizeCurve([1.0, 0.8, 0.6, 0.0])),
+            blend: BlendMode::Alpha,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// `width` × `height` 像素的窗口用的渲染参数
+    pub fn to_gpu(&self, width: u32, height: u32) -> RenderParams {
+        let (size, size_unit) = match self.size {
+            ParticleSize::Pixels(radius) => (radius, SIZE_PIXELS),
+            ParticleSize::World(radius) => (radius, SIZE_WORLD),
+        };
+        RenderParams {
+            size_curve: Vec4::from_array(self.size_over_life.unwrap_or(SizeCurve::CONSTANT).0),
+            viewport: Vec2::new(width.max(1) as f32, height.max(1) as f32),
+            aspect: aspect(width, height),
+            size,
+            size_unit,
+            blend_mode: match self.blend {
+                BlendMode::Alpha => BLEND_ALPHA,
+                BlendMode::Premultiplied => BLEND_PREMULTIPLIED,
+                BlendMode::Additive => BLEND_ADDITIVE,
+            },
+            _padding: [0; 2],
+        }
+    }
+}
+
+// 和 render_params.wgsl 里的常量对应
+const SIZE_PIXELS: u32 = 0;
+const SIZE_WORLD: u32 = 1;
+const BLEND_ALPHA: u32 = 0;
+const BLEND_PREMULTIPLIED: u32 = 1;
+const BLEND_ADDITIVE: u32 = 2;
+
+/// 和 render_params.wgsl 里的 `RenderParams` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RenderParams {
+    pub size_curve: Vec4,
+    /// 窗口的像素大小
+    pub viewport: Vec2,
+    /// 宽除以高
+    pub aspect: f32,
+    pub size: f32,
+    pub size_unit: u32,
+    /// 片元着色器按混合方式决定输出是否预乘
+    pub blend_mode: u32,
+    // 结构体按 vec4 的 16 字节对齐，大小要补齐到 48
+    _padding: [u32; 2],
+}
+
+/// 窗口的长宽比，最小化时窗口大小为 0，按正方形处理
+pub fn aspect(width: u32, height: u32) -> f32 {
+    if width == 0 || height == 0 {
+        1.0
+    } else {
+        width as f32 / height as f32
+    }
+}
+
+/// 世界坐标的边界：粒子的 x 在 ±`bounds.x` 之间，y 在 ±`bounds.y` 之间
+pub fn world_bounds(width: u32, height: u32) -> Vec2 {
+    Vec2::new(aspect(width, height), 1.0)
+}
+
+/// 窗口里的像素坐标转成世界坐标，y 轴朝上
+pub fn pixel_to_world(pixel: Vec2, width: u32, height: u32) -> Vec2 {
+    let ndc = Vec2::new(
+        pixel.x / width.max(1) as f32 * 2.0 - 1.0,
+        1.0 - pixel.y / height.max(1) as f32 * 2.0,
+    );
+    ndc * world_bounds(width, height)
+}
@@ -0,0 +1,490 @@
+//! 发射器和力场驱动的粒子模拟，以及按同样步骤算的 CPU 参考实现
+//!
+//! 每一帧分两步（compute.wgsl）：先用一个工作组的前缀和给这一帧开始时已经死亡的粒子按下标顺序发号码，
+//! 再逐个粒子重生、叠加力场、积分、处理边界和寿命。号码不取决于原子操作的先后，
+//! 同样的种子、发射器、力场和步长每次跑出来的结果都一样。
+//!
+//! [`ParticleSimulation::headless`] 不需要窗口：用 [`ParticleSimulation::step`] 按固定的步长一帧一帧推进，
+//! 再用 [`ParticleSimulation::read_particles`] 读回粒子。[`CpuSimulation`] 在 CPU 上按同样的顺序算一遍，
+//! 用来检查着色器。
+//!
+//! ```ignore
+//! let config = SimConfig::new(1024, 7);
+//! let mut gpu_sim = ParticleSimulation::headless(device, queue, &config)?;
+//! let mut cpu_sim = CpuSimulation::new(&config);
+//! for _ in 0..60 {
+//!     gpu_sim.step(device, queue, &mut gpu_emitters, &forces, 1.0 / 60.0);
+//!     cpu_sim.step(&mut cpu_emitters, &forces, 1.0 / 60.0);
+//! }
+//! let particles = gpu_sim.read_particles(device, queue)?;
+//! ```
+use anyhow::Result;
+use bytemuck::Zeroable;
+use glam::Vec2;
+use gpu::{Defines, HotShader, StorageBuffer, UniformBuffer, shader_source};
+
+use crate::{
+    Particle, WORKGROUP_SIZE, create_compute_pipeline,
+    emitter::{Emitters, GpuEmitter, MAX_EMITTERS},
+    forces::{ForceFields, GpuForceField, MAX_FORCE_FIELDS},
+    lifecycle::{Counters, SimParams},
+    ping_pong::PingPong,
+    readback::read_buffer,
+    wgsl::sign,
+};
+
+/// 粒子数、第一帧的随机数种子和世界坐标的边界
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimConfig {
+    pub count: u32,
+    /// 每帧先加 1 再用
+    pub seed: u32,
+    /// 见 [`world_bounds`](crate::render_params::world_bounds)
+    pub bounds: Vec2,
+}
+
+impl SimConfig {
+    /// 边界是 ±1 的正方形
+    pub fn new(count: u32, seed: u32) -> Self {
+        Self {
+            count,
+            seed,
+            bounds: Vec2::ONE,
+        }
+    }
+
+    pub fn with_bounds(mut self, bounds: Vec2) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    fn params(&self) -> SimParams {
+        SimParams {
+            seed: self.seed,
+            bounds: self.bounds,
+            ..Default::default()
+        }
+    }
+}
+
+/// 前进一帧：发射器按 `delta_time` 分配名额，时间和种子跟着更新，返回这一帧写进缓冲区的发射器和力场
+fn next_frame(
+    params: &mut SimParams,
+    emitters: &mut Emitters,
+    forces: &ForceFields,
+    delta_time: f32,
+) -> (Vec<GpuEmitter>, Vec<GpuForceField>) {
+    let emitters = emitters.prepare(delta_time);
+    let forces = forces.to_gpu();
+    params.delta_time = delta_time;
+    params.spawn_count = emitters.iter().map(|emitter| emitter.spawn_count).sum();
+    params.emitter_count = emitters.len() as u32;
+    params.force_count = forces.len() as u32;
+    params.time += delta_time;
+    params.seed = params.seed.wrapping_add(1);
+    (emitters, forces)
+}
+
+/// compute.wgsl，窗口模式下热重载
+pub(crate) fn compute_shader(device: &wgpu::Device, defines: Defines) -> Result<HotShader> {
+    HotShader::with_defines(
+        device,
+        shader_source!(
+            "src/wgsls/compute.wgsl",
+            includes: [
+                "src/wgsls/particle.wgsl",
+                "src/wgsls/sim.wgsl",
+                "src/wgsls/emitter.wgsl",
+                "src/wgsls/forces.wgsl",
+            ],
+        ),
+        defines,
+    )
+}
+
+/// compute_init.wgsl，和 compute.wgsl 共用绑定组布局
+pub(crate) fn init_shader(device: &wgpu::Device, defines: Defines) -> Result<HotShader> {
+    HotShader::with_defines(
+        device,
+        shader_source!("src/wgsls/compute_init.wgsl", includes: ["src/wgsls/particle.wgsl"]),
+        defines,
+    )
+}
+
+/// GPU 上的粒子模拟：交替读写的粒子缓冲区、每帧的参数、发射器、力场和计数器
+pub struct ParticleSimulation {
+    particles: PingPong<Particle>,
+    params: SimParams,
+    params_buffer: UniformBuffer<SimParams>,
+    counters: StorageBuffer<Counters>,
+    emitter_buffer: StorageBuffer<GpuEmitter>,
+    force_buffer: StorageBuffer<GpuForceField>,
+    /// 成对的绑定组，按 `particles.latest_index()` 选用
+    bind_groups: [wgpu::BindGroup; 2],
+    /// 热重载时用同样的布局重建管线
+    pipeline_layout: wgpu::PipelineLayout,
+    assign_tickets: wgpu::ComputePipeline,
+    update: wgpu::ComputePipeline,
+    workgroup_size: u32,
+}
+
+impl ParticleSimulation {
+    /// 所有粒子一开始都是死的，由发射器按各自的速率陆续发射
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &SimConfig,
+        shader: &wgpu::ShaderModule,
+        init_shader: &wgpu::ShaderModule,
+        workgroup_size: u32,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        let particles = PingPong::<Particle>::zeroed(
+            device,
+            "Particle Buffer",
+            config.count,
+            wgpu::BufferUsages::VERTEX,
+        )?;
+        let params = config.params();
+        let params_buffer = UniformBuffer::new(device, "Sim Params Buffer", &params)?;
+        let counters = StorageBuffer::<Counters>::zeroed(
+            device,
+            "Counter Buffer",
+            1,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let emitter_buffer = StorageBuffer::<GpuEmitter>::zeroed(
+            device,
+            "Emitter Buffer",
+            MAX_EMITTERS,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let force_buffer = StorageBuffer::<GpuForceField>::zeroed(
+            device,
+            "Force Field Buffer",
+            MAX_FORCE_FIELDS,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let tickets = StorageBuffer::<u32>::zeroed(
+            device,
+            "Ticket Buffer",
+            config.count,
+            wgpu::BufferUsages::empty(),
+        )?;
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bind Group Layout"),
+            entries: &[
+                particles
+                    .latest()
+                    .layout_entry(0, wgpu::ShaderStages::COMPUTE, true),
+                params_buffer.layout_entry(1, wgpu::ShaderStages::COMPUTE),
+                counters.layout_entry(2, wgpu::ShaderStages::COMPUTE, false),
+                emitter_buffer.layout_entry(3, wgpu::ShaderStages::COMPUTE, true),
+                force_buffer.layout_entry(4, wgpu::ShaderStages::COMPUTE, true),
+                particles
+                    .latest()
+                    .layout_entry(5, wgpu::ShaderStages::COMPUTE, false),
+                tickets.layout_entry(6, wgpu::ShaderStages::COMPUTE, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compute Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+        let bind_groups = particles.pair(|source, destination| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Compute Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    source.bind_group_entry(0),
+                    params_buffer.bind_group_entry(1),
+                    counters.bind_group_entry(2),
+                    emitter_buffer.bind_group_entry(3),
+                    force_buffer.bind_group_entry(4),
+                    destination.bind_group_entry(5),
+                    tickets.bind_group_entry(6),
+                ],
+            })
+        });
+
+        let pipeline = |entry_point, label| {
+            create_compute_pipeline(device, &pipeline_layout, shader, entry_point, label, cache)
+        };
+        let assign_tickets = pipeline("assign_tickets", "Assign Tickets Pipeline")?;
+        let update = pipeline("main", "Compute Pipeline")?;
+        let simulation = Self {
+            particles,
+            params,
+            params_buffer,
+            counters,
+            emitter_buffer,
+            force_buffer,
+            bind_groups,
+            pipeline_layout,
+            assign_tickets,
+            update,
+            workgroup_size,
+        };
+        simulation.reset(device, queue, init_shader, cache)?;
+        Ok(simulation)
+    }
+
+    /// 不需要窗口的版本，着色器在这里编译，不用管线缓存
+    pub fn headless(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &SimConfig,
+    ) -> Result<Self> {
+        let defines = Defines::new().set("WORKGROUP_SIZE", WORKGROUP_SIZE);
+        let shader = compute_shader(device, defines.clone())?;
+        let init = init_shader(device, defines)?;
+        Self::new(
+            device,
+            queue,
+            config,
+            shader.module(),
+            init.module(),
+            WORKGROUP_SIZE,
+            None,
+        )
+    }
+
+    /// 着色器热重载之后重建管线，失败时保留旧管线
+    pub fn rebuild(
+        &mut self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<()> {
+        let pipeline = |entry_point, label| {
+            create_compute_pipeline(
+                device,
+                &self.pipeline_layout,
+                shader,
+                entry_point,
+                label,
+                cache,
+            )
+        };
+        let assign_tickets = pipeline("assign_tickets", "Assign Tickets Pipeline")?;
+        let update = pipeline("main", "Compute Pipeline")?;
+        self.assign_tickets = assign_tickets;
+        self.update = update;
+        Ok(())
+    }
+
+    /// 用初始化着色器给所有粒子赋初值，两个方向各跑一次，两个缓冲区都清空
+    pub fn reset(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        init_shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<()> {
+        let pipeline = create_compute_pipeline(
+            device,
+            &self.pipeline_layout,
+            init_shader,
+            "main",
+            "Init Compute Pipeline",
+            cache,
+        )?;
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Computer Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Init Computer Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&pipeline);
+            for bind_group in &self.bind_groups {
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(self.len().div_ceil(self.workgroup_size), 1, 1);
+            }
+        }
+        queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+
+    /// 每帧模拟之前调用：发射器按 `delta_time` 分配名额，时间和种子前进一帧，写进缓冲区
+    pub fn prepare(
+        &mut self,
+        queue: &wgpu::Queue,
+        emitters: &mut Emitters,
+        forces: &ForceFields,
+        delta_time: f32,
+    ) {
+        let (emitters, forces) = next_frame(&mut self.params, emitters, forces, delta_time);
+        self.emitter_buffer.write(queue, 0, &emitters);
+        self.force_buffer.write(queue, 0, &forces);
+        self.params_buffer.update(queue, &self.params);
+    }
+
+    /// 录制一帧：发号码、更新所有粒子，之后刚写入的缓冲区变成最新的
+    pub fn record(&mut self, pass: &mut wgpu::ComputePass) {
+        pass.set_bind_group(0, &self.bind_groups[self.particles.latest_index()], &[]);
+        pass.set_pipeline(&self.assign_tickets);
+        pass.dispatch_workgroups(1, 1, 1);
+        pass.set_pipeline(&self.update);
+        pass.dispatch_workgroups(self.len().div_ceil(self.workgroup_size), 1, 1);
+        self.particles.swap();
+    }
+
+    /// 准备、录制并提交一帧，不需要窗口
+    pub fn step(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        emitters: &mut Emitters,
+        forces: &ForceFields,
+        delta_time: f32,
+    ) {
+        self.prepare(queue, emitters, forces, delta_time);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Simulation Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Simulation Pass"),
+                timestamp_writes: None,
+            });
+            self.record(&mut pass);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+
+    /// 阻塞地读回最新的粒子
+    pub fn read_particles(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<Particle>> {
+        read_buffer(device, queue, self.particles.latest())
+    }
+
+    /// 阻塞地读回最近一帧的计数器
+    pub fn read_counters(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Counters> {
+        Ok(read_buffer(device, queue, &self.counters)?[0])
+    }
+
+    /// 渲染和排序读 `latest()`
+    pub fn particles(&self) -> &PingPong<Particle> {
+        &self.particles
+    }
+
+    /// 计数器缓冲区，用来异步读回
+    pub fn counters(&self) -> &StorageBuffer<Counters> {
+        &self.counters
+    }
+
+    pub fn params(&self) -> &SimParams {
+        &self.params
+    }
+
+    /// 边界跟着窗口变化，下一帧生效
+    pub fn set_bounds(&mut self, bounds: Vec2) {
+        self.params.bounds = bounds;
+    }
+
+    pub fn len(&self) -> u32 {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+}
+
+/// CPU 上的同一个模拟，用法和 [`ParticleSimulation`] 一样
+pub struct CpuSimulation {
+    params: SimParams,
+    particles: Vec<Particle>,
+    counters: Counters,
+}
+
+impl CpuSimulation {
+    pub fn new(config: &SimConfig) -> Self {
+        Self {
+            params: config.params(),
+            particles: vec![Particle::zeroed(); config.count as usize],
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn step(&mut self, emitters: &mut Emitters, forces: &ForceFields, delta_time: f32) {
+        let (emitters, forces) = next_frame(&mut self.params, emitters, forces, delta_time);
+        let (particles, counters) = cpu_step(&self.params, &emitters, &forces, &self.particles);
+        self.particles = particles;
+        self.counters = counters;
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// 最近一帧的计数器
+    pub fn counters(&self) -> Counters {
+        self.counters
+    }
+
+    pub fn params(&self) -> &SimParams {
+        &self.params
+    }
+}
+
+/// 号码按发射器的顺序分段，和 compute.wgsl 里的 `find_emitter` 一致
+fn find_emitter(emitters: &[GpuEmitter], ticket: u32) -> usize {
+    let mut rest = ticket;
+    for (i, emitter) in emitters.iter().enumerate() {
+        if rest < emitter.spawn_count {
+            return i;
+        }
+        rest -= emitter.spawn_count;
+    }
+    emitters.len() - 1
+}
+
+/// 在 CPU 上按 compute.wgsl 的顺序模拟一帧，返回新的粒子和计数器
+pub fn cpu_step(
+    params: &SimParams,
+    emitters: &[GpuEmitter],
+    forces: &[GpuForceField],
+    particles: &[Particle],
+) -> (Vec<Particle>, Counters) {
+    let mut counters = Counters::default();
+    let mut next = Vec::with_capacity(particles.len());
+    for (index, &particle) in particles.iter().enumerate() {
+        let mut particle = particle;
+        if particle.life <= 0.0 {
+            // 号码按下标顺序发
+            let ticket = counters.dead;
+            counters.dead += 1;
+            if ticket >= params.spawn_count {
+                next.push(particle);
+                continue;
+            }
+            let seed = (index as u32)
+                .wrapping_mul(1664525)
+                .wrapping_add(params.seed.wrapping_mul(1013904223));
+            particle = emitters[find_emitter(emitters, ticket)].emit(seed);
+        }
+
+        for force in forces {
+            particle.vel = force.apply(particle.pos, particle.vel, params.time, params.delta_time);
+        }
+        particle.pos += particle.vel * params.delta_time;
+        let outside = particle.pos.abs().cmpgt(params.bounds);
+        let outward = (sign(particle.pos) * particle.vel).cmpgt(Vec2::ZERO);
+        particle.vel = Vec2::select(outside & outward, -particle.vel, particle.vel);
+
+        particle.life -= params.delta_time;
+        if particle.life > 0.0 {
+            counters.live += 1;
+        }
+        next.push(particle);
+    }
+    (next, counters)
+}
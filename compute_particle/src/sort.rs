@@ -0,0 +1,237 @@
+//! 在 GPU 上按年龄给粒子排序
+//!
+//! alpha 混合的结果和绘制顺序有关，粒子要从后往前画。2D 粒子没有深度，这里按年龄排：
+//! 老的粒子先画，刚出生的粒子盖在上面。排序用双调排序网络，每一步（[`SortStage`]）一次调度，
+//! 所有步骤的参数提前写进一个动态偏移的 uniform 缓冲区，每帧只需要换偏移。
+//!
+//! 排序分三个入口：`write_keys` 写入键和粒子下标，`bitonic_step` 执行一步比较交换，
+//! `write_indices` 把排好的下标展开成每个粒子 6 个顶点的索引缓冲区，渲染时用 `draw_indexed` 绘制。
+use anyhow::Result;
+use gpu::{StorageBuffer, UniformBuffer};
+
+use crate::{Particle, create_compute_pipeline, ping_pong::PingPong};
+
+/// 和 sort.wgsl 里的 `SortEntry` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SortEntry {
+    pub key: f32,
+    pub index: u32,
+}
+
+/// 双调排序的一步，和 sort.wgsl 里的 `SortStage` 对应
+///
+/// 下标为 `i` 的条目和 `i ^ j` 比较，`i & k` 为 0 的块升序、否则降序。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SortStage {
+    pub k: u32,
+    pub j: u32,
+}
+
+// 和 sort.wgsl 里的常量对应
+const DEAD_KEY: f32 = 1e30;
+
+/// 双调排序要求条目数是 2 的幂，不够的部分用排在最后的条目补齐
+pub fn sort_len(count: u32) -> u32 {
+    count.max(1).next_power_of_two()
+}
+
+/// 给 `len` 个条目排序的所有步骤，`len` 必须是 2 的幂
+pub fn bitonic_stages(len: u32) -> Vec<SortStage> {
+    assert!(len.is_power_of_two(), "排序的条目数 {len} 不是 2 的幂");
+    let mut stages = Vec::new();
+    let mut k = 2;
+    while k <= len {
+        let mut j = k / 2;
+        while j > 0 {
+            stages.push(SortStage { k, j });
+            j /= 2;
+        }
+        k *= 2;
+    }
+    stages
+}
+
+/// 粒子的排序键，和 sort.wgsl 里的 `write_keys` 一致：活过的时间越长越小，死亡的粒子排在最后
+pub fn sort_key(particle: &Particle) -> f32 {
+    if particle.life > 0.0 {
+        particle.life - particle.max_life
+    } else {
+        DEAD_KEY
+    }
+}
+
+/// 粒子索引缓冲区和生成它的三个计算管线
+pub struct ParticleSorter {
+    indices: StorageBuffer<u32>,
+    stages: UniformBuffer<SortStage>,
+    stage_count: u32,
+    particle_count: u32,
+    /// 补齐到 2 的幂之后的条目数
+    len: u32,
+    workgroup_size: u32,
+    /// 两个粒子缓冲区各一个，按哪个是最新的选用
+    bind_groups: [wgpu::BindGroup; 2],
+    /// 热重载时用同样的布局重建管线
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: SortPipelines,
+}
+
+struct SortPipelines {
+    write_keys: wgpu::ComputePipeline,
+    bitonic_step: wgpu::ComputePipeline,
+    write_indices: wgpu::ComputePipeline,
+}
+
+impl ParticleSorter {
+    /// `shader` 是按 `workgroup_size` 编译的 sort.wgsl
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        particles: &PingPong<Particle>,
+        shader: &wgpu::ShaderModule,
+        workgroup_size: u32,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        let particle_count = particles.len();
+        let len = sort_len(particle_count);
+        let entries = StorageBuffer::<SortEntry>::zeroed(
+            device,
+            "Sort Entry Buffer",
+            len,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let indices = StorageBuffer::<u32>::zeroed(
+            device,
+            "Particle Index Buffer",
+            particle_count * 6,
+            wgpu::BufferUsages::INDEX,
+        )?;
+        let stage_list = bitonic_stages(len);
+        let stages = UniformBuffer::<SortStage>::new_dynamic(
+            device,
+            "Sort Stage Buffer",
+            stage_list.len() as u32,
+        )?;
+        // 排序步骤只和条目数有关，创建时写一次
+        stages.update_all(queue, &stage_list);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sort Bind Group Layout"),
+            entries: &[
+                particles
+                    .latest()
+                    .layout_entry(0, wgpu::ShaderStages::COMPUTE, true),
+                entries.layout_entry(1, wgpu::ShaderStages::COMPUTE, false),
+                indices.layout_entry(2, wgpu::ShaderStages::COMPUTE, false),
+                stages.layout_entry(3, wgpu::ShaderStages::COMPUTE),
+            ],
+        });
+        let bind_groups = particles.pair(|source, _| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Sort Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    source.bind_group_entry(0),
+                    entries.bind_group_entry(1),
+                    indices.bind_group_entry(2),
+                    stages.bind_group_entry(3),
+                ],
+            })
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sort Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipelines = SortPipelines::new(device, &pipeline_layout, shader, cache)?;
+
+        Ok(Self {
+            indices,
+            stages,
+            stage_count: stage_list.len() as u32,
+            particle_count,
+            len,
+            workgroup_size,
+            bind_groups,
+            pipeline_layout,
+            pipelines,
+        })
+    }
+
+    /// 着色器改动之后重建管线，失败时保留旧管线
+    pub fn rebuild(
+        &mut self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<()> {
+        self.pipelines = SortPipelines::new(device, &self.pipeline_layout, shader, cache)?;
+        Ok(())
+    }
+
+    /// 在计算通道里录制整个排序，按 `particles` 里最新的状态排，
+    /// 之后 [`ParticleSorter::index_buffer`] 就是排好的绘制顺序
+    pub fn record(&self, pass: &mut wgpu::ComputePass, particles: &PingPong<Particle>) {
+        let entry_groups = self.len.div_ceil(self.workgroup_size);
+        let bind_group = &self.bind_groups[particles.latest_index()];
+        pass.set_bind_group(0, bind_group, &[self.stages.offset(0)]);
+        pass.set_pipeline(&self.pipelines.write_keys);
+        pass.dispatch_workgroups(entry_groups, 1, 1);
+
+        pass.set_pipeline(&self.pipelines.bitonic_step);
+        for stage in 0..self.stage_count {
+            pass.set_bind_group(0, bind_group, &[self.stages.offset(stage)]);
+            pass.dispatch_workgroups(entry_groups, 1, 1);
+        }
+
+        pass.set_pipeline(&self.pipelines.write_indices);
+        pass.dispatch_workgroups(self.particle_count.div_ceil(self.workgroup_size), 1, 1);
+    }
+
+    /// `u32` 索引，每个粒子 6 个
+    pub fn index_buffer(&self) -> &wgpu::Buffer {
+        self.indices.buffer()
+    }
+
+    pub fn index_count(&self) -> u32 {
+        self.particle_count * 6
+    }
+}
+
+impl SortPipelines {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        Ok(Self {
+            write_keys: create_compute_pipeline(
+                device,
+                layout,
+                shader,
+                "write_keys",
+                "Sort Keys Pipeline",
+                cache,
+            )?,
+            bitonic_step: create_compute_pipeline(
+                device,
+                layout,
+                shader,
+                "bitonic_step",
+                "Bitonic Sort Pipeline",
+                cache,
+            )?,
+            write_indices: create_compute_pipeline(
+                device,
+                layout,
+                shader,
+                "write_indices",
+                "Sort Indices Pipeline",
+                cache,
+            )?,
+        })
+    }
+}
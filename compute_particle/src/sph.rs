@@ -0,0 +1,428 @@
+//! SPH（光滑粒子流体动力学）流体
+//!
+//! 每个粒子代表一小团流体。密度是周围粒子的质量按核函数加权求和，压力和密度偏离静止密度的程度成正比，
+//! 压力梯度把挤在一起的粒子推开，黏性力让相邻粒子的速度趋于一致。邻居和鸟群一样用 [`crate::grid`]
+//! 里的均匀网格查找，格子边长不小于光滑核半径。
+//!
+//! 每一小步分成建网格、算密度、算压力和黏性力、积分四个阶段，每个阶段都等上一个阶段全部算完，
+//! 邻居读到的都是这一小步开始时的状态，结果和粒子的计算顺序无关。[`cpu_step`] 按同样的顺序在 CPU 上模拟。
+//!
+//! ```ignore
+//! let settings = SphSettings::default();
+//! let particles = spawn_block(8192, 7, &settings, bounds);
+//! let mut fluid = Fluid::new(device, &particles, settings, shader, 64, None)?;
+//! fluid.update(queue, bounds);
+//! fluid.record(&mut compute_pass);
+//! ```
+use std::f32::consts::PI;
+
+use anyhow::{Result, bail};
+use glam::{Vec2, Vec4};
+use gpu::{StorageBuffer, UniformBuffer};
+
+use crate::{
+    Particle, create_compute_pipeline,
+    grid::{CellList, GridParams, GridPipelines, SpatialGrid},
+    wgsl::pcg_hash,
+};
+
+/// 可以在运行时修改的流体参数，下一帧生效
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SphSettings {
+    /// 光滑核半径，初始时相邻粒子的间距是它的一半
+    pub smoothing_radius: f32,
+    pub rest_density: f32,
+    /// 压力 = stiffness * (密度 - 静止密度)，越大越不可压缩，步长也要越小
+    pub stiffness: f32,
+    pub viscosity: f32,
+    pub gravity: Vec2,
+    /// 撞墙之后法向速度保留的比例
+    pub restitution: f32,
+    /// 每一小步的时间，固定步长才稳定
+    pub time_step: f32,
+    /// 每帧走几小步
+    pub substeps: u32,
+}
+
+impl Default for SphSettings {
+    /// 适合八千个粒子的溃坝
+    fn default() -> Self {
+        Self {
+            smoothing_radius: 0.03,
+            rest_density: 1000.0,
+            stiffness: 150.0,
+            viscosity: 8.0,
+            gravity: Vec2::new(0.0, -3.0),
+            restitution: 0.3,
+            time_step: 1.0 / 960.0,
+            substeps: 16,
+        }
+    }
+}
+
+impl SphSettings {
+    /// 初始时相邻粒子的间距
+    pub fn spacing(&self) -> f32 {
+        self.smoothing_radius * 0.5
+    }
+
+    /// 让按 [`SphSettings::spacing`] 排成方阵的粒子刚好是静止密度的质量
+    pub fn particle_mass(&self) -> f32 {
+        let params = self.to_gpu(Vec2::ONE, 1);
+        let reach = (self.smoothing_radius / self.spacing()).ceil() as i32;
+        let mut sum = 0.0;
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                let offset = Vec2::new(x as f32, y as f32) * self.spacing();
+                sum += params.poly6(offset.length_squared());
+            }
+        }
+        self.rest_density / sum
+    }
+
+    /// 容器是 ±`bounds`、共 `count` 个粒子时的模拟参数
+    pub fn to_gpu(&self, bounds: Vec2, count: u32) -> SphParams {
+        let h = self.smoothing_radius;
+        SphParams {
+            bounds,
+            gravity: self.gravity,
+            count,
+            delta_time: self.time_step,
+            smoothing_radius: h,
+            rest_density: self.rest_density,
+            stiffness: self.stiffness,
+            viscosity: self.viscosity,
+            restitution: self.restitution,
+            poly6: 4.0 / (PI * h.powi(8)),
+            spiky: 30.0 / (PI * h.powi(5)),
+            viscosity_laplacian: 40.0 / (PI * h.powi(5)),
+            _padding: Vec2::ZERO,
+        }
+    }
+}
+
+/// 和 sph.wgsl 里的 `SphParams` 对应
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SphParams {
+    pub bounds: Vec2,
+    pub gravity: Vec2,
+    pub count: u32,
+    pub delta_time: f32,
+    pub smoothing_radius: f32,
+    pub rest_density: f32,
+    pub stiffness: f32,
+    pub viscosity: f32,
+    pub restitution: f32,
+    /// 二维 poly6 核的系数 4 / (π h⁸)
+    pub poly6: f32,
+    /// 二维 spiky 核梯度的系数 30 / (π h⁵)
+    pub spiky: f32,
+    /// 二维黏性核拉普拉斯的系数 40 / (π h⁵)
+    pub viscosity_laplacian: f32,
+    _padding: Vec2,
+}
+
+impl SphParams {
+    /// 建网格用的参数，格子边长不小于光滑核半径
+    pub fn grid(&self) -> GridParams {
+        GridParams::fit(self.bounds, self.smoothing_radius, self.count)
+    }
+
+    /// 距离的平方为 `distance_sq` 时的 poly6 核，超出光滑核半径时为 0
+    pub fn poly6(&self, distance_sq: f32) -> f32 {
+        let radius_sq = self.smoothing_radius * self.smoothing_radius;
+        if distance_sq >= radius_sq {
+            return 0.0;
+        }
+        let w = radius_sq - distance_sq;
+        self.poly6 * w * w * w
+    }
+
+    fn pressure(&self, density: f32) -> f32 {
+        self.stiffness * (density - self.rest_density)
+    }
+}
+
+/// 和 sph.wgsl 里的 `density_color` 一样
+pub fn density_color(params: &SphParams, density: f32) -> Vec4 {
+    const SPARSE_COLOR: Vec4 = Vec4::new(0.75, 0.9, 1.0, 1.0);
+    const DENSE_COLOR: Vec4 = Vec4::new(0.05, 0.25, 0.8, 1.0);
+    let t = ((density / params.rest_density - 0.5) * 2.0).clamp(0.0, 1.0);
+    SPARSE_COLOR.lerp(DENSE_COLOR, t)
+}
+
+/// 溃坝：左下角按 [`SphSettings::spacing`] 排成方阵，位置带一点随机扰动打破对称
+pub fn spawn_block(count: u32, seed: u32, settings: &SphSettings, bounds: Vec2) -> Vec<Particle> {
+    let spacing = settings.spacing();
+    let mass = settings.particle_mass();
+    let columns = (count as f32).sqrt().ceil().max(1.0) as u32;
+    let corner = -bounds + spacing;
+    let params = settings.to_gpu(bounds, count);
+    let color = density_color(&params, settings.rest_density);
+    (0..count)
+        .map(|i| {
+            let random = |salt: u32| pcg_hash(i.wrapping_mul(2).wrapping_add(salt) ^ seed);
+            let lattice = Vec2::new((i % columns) as f32, (i / columns) as f32);
+            let jitter = Vec2::new(random(0), random(1)) - 0.5;
+            let pos = corner + (lattice + jitter * 0.02) * spacing;
+            Particle::new(pos.min(bounds), Vec2::ZERO, color, 1.0).with_mass(mass)
+        })
+        .collect()
+}
+
+/// 每个粒子的密度，和 sph.wgsl 里的 `density` 一致
+pub fn cpu_densities(params: &SphParams, particles: &[Particle]) -> Vec<f32> {
+    let cells = CellList::new(params.grid(), particles.iter().map(|p| p.pos));
+    particles
+        .iter()
+        .map(|particle| {
+            cells
+                .neighbors(particle.pos)
+                .map(|j| {
+                    let other = &particles[j];
+                    other.mass * params.poly6(other.pos.distance_squared(particle.pos))
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// 在 CPU 上按 GPU 的顺序模拟一小步
+pub fn cpu_step(params: &SphParams, particles: &[Particle]) -> Vec<Particle> {
+    let cells = CellList::new(params.grid(), particles.iter().map(|p| p.pos));
+    let densities = cpu_densities(params, particles);
+    let h = params.smoothing_radius;
+    particles
+        .iter()
+        .enumerate()
+        .map(|(i, &particle)| {
+            let density = densities[i];
+            let pressure = params.pressure(density);
+            let mut pressure_sum = Vec2::ZERO;
+            let mut viscosity_sum = Vec2::ZERO;
+            for j in cells.neighbors(particle.pos) {
+                if j == i {
+                    continue;
+                }
+                let other = &particles[j];
+                let offset = particle.pos - other.pos;
+                let distance = offset.length();
+                if distance >= h {
+                    continue;
+                }
+                let other_density = densities[j];
+                let falloff = h - distance;
+                if distance > 1e-6 {
+                    let shared_pressure =
+                        (pressure + params.pressure(other_density)) / (2.0 * other_density);
+                    pressure_sum += other.mass
+                        * shared_pressure
+                        * params.spiky
+                        * falloff
+                        * falloff
+                        * (offset / distance);
+                }
+                viscosity_sum += other.mass * (other.vel - particle.vel) / other_density
+                    * params.viscosity_laplacian
+                    * falloff;
+            }
+            let acceleration =
+                (pressure_sum + params.viscosity * viscosity_sum) / density + params.gravity;
+
+            let mut next = particle;
+            next.vel += acceleration * params.delta_time;
+            next.pos += next.vel * params.delta_time;
+            let outside = next.pos.abs().cmpgt(params.bounds);
+            next.vel *= Vec2::select(outside, Vec2::splat(-params.restitution), Vec2::ONE);
+            next.pos = next.pos.clamp(-params.bounds, params.bounds);
+            next.color = density_color(params, density);
+            next
+        })
+        .collect()
+}
+
+/// GPU 上的流体：粒子、密度和加速度缓冲区、网格，以及每一小步的四个计算管线
+pub struct Fluid {
+    particles: StorageBuffer<Particle>,
+    settings: SphSettings,
+    params: SphParams,
+    params_buffer: UniformBuffer<SphParams>,
+    grid: SpatialGrid,
+    workgroup_size: u32,
+    bind_group: wgpu::BindGroup,
+    /// 热重载时用同样的布局重建管线
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: FluidPipelines,
+}
+
+struct FluidPipelines {
+    count: wgpu::ComputePipeline,
+    grid: GridPipelines,
+    density: wgpu::ComputePipeline,
+    forces: wgpu::ComputePipeline,
+    integrate: wgpu::ComputePipeline,
+}
+
+impl Fluid {
+    /// `shader` 是按 `workgroup_size` 编译的 sph.wgsl
+    pub fn new(
+        device: &wgpu::Device,
+        particles: &[Particle],
+        settings: SphSettings,
+        shader: &wgpu::ShaderModule,
+        workgroup_size: u32,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        if particles.is_empty() {
+            bail!("流体至少要有一个粒子");
+        }
+        let count = particles.len() as u32;
+        let particle_buffer = StorageBuffer::from_slice(
+            device,
+            "Fluid Particle Buffer",
+            particles,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let params = SphParams::default();
+        let params_buffer = UniformBuffer::new(device, "Sph Params Buffer", &params)?;
+        let densities = StorageBuffer::<f32>::zeroed(
+            device,
+            "Density Buffer",
+            count,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let accelerations = StorageBuffer::<Vec2>::zeroed(
+            device,
+            "Fluid Acceleration Buffer",
+            count,
+            wgpu::BufferUsages::empty(),
+        )?;
+        let grid = SpatialGrid::new(device, count)?;
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Fluid Bind Group Layout"),
+            entries: &[
+                particle_buffer.layout_entry(0, wgpu::ShaderStages::COMPUTE, false),
+                params_buffer.layout_entry(1, wgpu::ShaderStages::COMPUTE),
+                densities.layout_entry(2, wgpu::ShaderStages::COMPUTE, false),
+                accelerations.layout_entry(3, wgpu::ShaderStages::COMPUTE, false),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Fluid Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                particle_buffer.bind_group_entry(0),
+                params_buffer.bind_group_entry(1),
+                densities.bind_group_entry(2),
+                accelerations.bind_group_entry(3),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Fluid Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, grid.bind_group_layout()],
+            immediate_size: 0,
+        });
+        let pipelines = FluidPipelines::new(device, &pipeline_layout, shader, cache)?;
+
+        Ok(Self {
+            particles: particle_buffer,
+            settings,
+            params,
+            params_buffer,
+            grid,
+            workgroup_size,
+            bind_group,
+            pipeline_layout,
+            pipelines,
+        })
+    }
+
+    pub fn settings(&self) -> &SphSettings {
+        &self.settings
+    }
+
+    /// 修改之后下一次 [`Fluid::update`] 生效
+    pub fn settings_mut(&mut self) -> &mut SphSettings {
+        &mut self.settings
+    }
+
+    /// 每帧模拟之前调用，容器跟着窗口变化；步长固定，和帧时间无关
+    pub fn update(&mut self, queue: &wgpu::Queue, bounds: Vec2) {
+        self.params = self.settings.to_gpu(bounds, self.len());
+        self.params_buffer.update(queue, &self.params);
+        self.grid.update(queue, self.params.grid());
+    }
+
+    /// 最近一次 [`Fluid::update`] 写入的参数
+    pub fn params(&self) -> &SphParams {
+        &self.params
+    }
+
+    /// 着色器改动之后重建管线，失败时保留旧管线
+    pub fn rebuild(
+        &mut self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<()> {
+        self.pipelines = FluidPipelines::new(device, &self.pipeline_layout, shader, cache)?;
+        Ok(())
+    }
+
+    /// 在计算通道里录制这一帧的 `substeps` 小步
+    pub fn record(&self, pass: &mut wgpu::ComputePass) {
+        let groups = self.len().div_ceil(self.workgroup_size);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_bind_group(1, self.grid.bind_group(), &[]);
+        for _ in 0..self.settings.substeps {
+            pass.set_pipeline(&self.pipelines.count);
+            pass.dispatch_workgroups(groups, 1, 1);
+            self.pipelines
+                .grid
+                .record(pass, &self.grid, self.workgroup_size);
+            for pipeline in [
+                &self.pipelines.density,
+                &self.pipelines.forces,
+                &self.pipelines.integrate,
+            ] {
+                pass.set_pipeline(pipeline);
+                pass.dispatch_workgroups(groups, 1, 1);
+            }
+        }
+    }
+
+    /// 粒子的状态，渲染时只读绑定
+    pub fn particles(&self) -> &StorageBuffer<Particle> {
+        &self.particles
+    }
+
+    pub fn len(&self) -> u32 {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl FluidPipelines {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> Result<Self> {
+        let pipeline = |entry_point, label| {
+            create_compute_pipeline(device, layout, shader, entry_point, label, cache)
+        };
+        Ok(Self {
+            count: pipeline("count", "Fluid Count Pipeline")?,
+            grid: GridPipelines::new(device, layout, shader, cache)?,
+            density: pipeline("density", "Fluid Density Pipeline")?,
+            forces: pipeline("forces", "Fluid Forces Pipeline")?,
+            integrate: pipeline("integrate", "Fluid Integrate Pipeline")?,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+//! CPU 参考实现用到的着色器函数
+//!
+//! 和 gpu/wgsl/random.wgsl 里的 `pcg_hash`、WGSL 内置的 `mix` 和 `sign` 算法一样，
+//! CPU 上算出来的随机数和插值才能和 GPU 对得上。Rust 的 `f32::signum(0.0)` 是 1，WGSL 的 `sign(0.0)` 是 0。
+use glam::Vec2;
+
+/// 输入一个种子，输出一个 0.0 到 1.0 的随机数
+pub fn pcg_hash(seed: u32) -> f32 {
+    let state = seed.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    ((word >> 22) ^ word) as f32 / u32::MAX as f32
+}
+
+/// `a * (1 - t) + b * t`，和 glam 的 `lerp` 舍入不同
+pub fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+/// 每个分量为正时是 1，为负时是 -1，为 0 时是 0
+pub fn sign(v: Vec2) -> Vec2 {
+    let sign = |x: f32| {
+        if x > 0.0 {
+            1.0
+        } else if x < 0.0 {
+            -1.0
+        } else {
+            0.0
+        }
+    };
+    Vec2::new(sign(v.x), sign(v.y))
+}
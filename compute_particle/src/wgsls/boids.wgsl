@@ -0,0 +1,118 @@
+// 鸟群模拟：每帧用均匀网格重建空间哈希，只在周围 3x3 个格子里找邻居
+// 和 Rust 端的 compute_particle::boids 对应，改这里的时候记得同步，CPU 参考实现也要一起改
+#include "particle.wgsl"
+#include "grid.wgsl"
+
+#ifndef WORKGROUP_SIZE
+#define WORKGROUP_SIZE 64u
+#endif
+
+struct FlockParams {
+    // 世界坐标的边界，鸟的 x 在 ±bounds.x 之间，y 在 ±bounds.y 之间
+    bounds: vec2<f32>,
+    // 网格的格子数和格子边长，GPU 上用 grid.wgsl 里的 GridParams，这里留给 CPU 参考实现
+    grid_size: vec2<u32>,
+    cell_size: f32,
+    delta_time: f32,
+    // 对齐和聚集的感知半径
+    radius: f32,
+    // 分离的半径
+    separation_radius: f32,
+    separation: f32,
+    alignment: f32,
+    cohesion: f32,
+    min_speed: f32,
+    max_speed: f32,
+    // 离边界不到 edge_margin 时往回转
+    edge_margin: f32,
+    edge_turn: f32,
+    count: u32,
+};
+
+// 上一帧的状态，只读；新状态写进 next，两个缓冲区每帧交换，邻居读到的都是上一帧的状态
+@group(0) @binding(0) var<storage, read> boids: array<Particle>;
+@group(0) @binding(1) var<storage, read_write> next: array<Particle>;
+@group(0) @binding(2) var<uniform> params: FlockParams;
+
+// 1. 统计每个格子里有几只鸟，之后是 grid.wgsl 里的前缀和、散射和格子内排序
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn count(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    grid_insert(i, boids[i].pos);
+}
+
+fn limit_speed(vel: vec2<f32>) -> vec2<f32> {
+    let speed = length(vel);
+    if speed > params.max_speed {
+        return vel * (params.max_speed / speed);
+    }
+    if speed < params.min_speed && speed > 0.0 {
+        return vel * (params.min_speed / speed);
+    }
+    return vel;
+}
+
+// 离边界太近时往回转
+fn edge_steer(pos: vec2<f32>) -> vec2<f32> {
+    let inner = params.bounds - params.edge_margin;
+    return -sign(pos) * select(vec2<f32>(0.0), vec2<f32>(params.edge_turn), abs(pos) > inner);
+}
+
+// 5. 分离、对齐、聚集
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn flock(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    var boid = boids[i];
+    let center = cell_coord(boid.pos);
+    let radius_sq = params.radius * params.radius;
+    let separation_sq = params.separation_radius * params.separation_radius;
+
+    var neighbors = 0u;
+    var velocity_sum = vec2<f32>(0.0);
+    var position_sum = vec2<f32>(0.0);
+    var separation = vec2<f32>(0.0);
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let cell = center + vec2<i32>(dx, dy);
+            if !in_grid(cell) {
+                continue;
+            }
+            let index = cell_index(cell);
+            for (var slot = cell_start[index]; slot < cell_start[index + 1u]; slot++) {
+                let j = sorted[slot];
+                if j == i {
+                    continue;
+                }
+                let other = boids[j];
+                let offset = other.pos - boid.pos;
+                let distance_sq = dot(offset, offset);
+                if distance_sq >= radius_sq {
+                    continue;
+                }
+                neighbors += 1u;
+                velocity_sum += other.vel;
+                position_sum += other.pos;
+                if distance_sq < separation_sq {
+                    separation -= offset / max(distance_sq, 1e-8);
+                }
+            }
+        }
+    }
+
+    var acceleration = edge_steer(boid.pos);
+    if neighbors > 0u {
+        let n = f32(neighbors);
+        acceleration += params.alignment * (velocity_sum / n - boid.vel);
+        acceleration += params.cohesion * (position_sum / n - boid.pos);
+        acceleration += params.separation * separation;
+    }
+    boid.vel = limit_speed(boid.vel + acceleration * params.delta_time);
+    boid.pos = clamp(boid.pos + boid.vel * params.delta_time, -params.bounds, params.bounds);
+    next[i] = boid;
+}
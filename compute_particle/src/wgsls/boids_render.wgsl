@@ -0,0 +1,53 @@
+// 把每只鸟画成朝向速度方向的三角形
+#include "particle.wgsl"
+#include "render_params.wgsl"
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@group(0) @binding(0) var<storage, read> boids: array<Particle>;
+@group(0) @binding(1) var<uniform> params: RenderParams;
+
+// 鸟头朝 +x 时三个顶点的位置，逆时针
+const SHAPE = array<vec2<f32>, 3>(
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(-0.6, 0.5),
+    vec2<f32>(-0.6, -0.5),
+);
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // 每只鸟 3 个顶点
+    let boid = boids[index / 3u];
+    let corner = SHAPE[index % 3u];
+    // 速度为 0 时朝右
+    var heading = vec2<f32>(1.0, 0.0);
+    if dot(boid.vel, boid.vel) > 0.0 {
+        heading = normalize(boid.vel);
+    }
+    let offset = vec2<f32>(
+        corner.x * heading.x - corner.y * heading.y,
+        corner.x * heading.y + corner.y * heading.x,
+    );
+    // 大小的单位和粒子一样，不随年龄变化
+    var radius = vec2<f32>(params.size / params.aspect, params.size);
+    if params.size_unit == SIZE_PIXELS {
+        radius = 2.0 * params.size / params.viewport;
+    }
+    let center = boid.pos / vec2<f32>(params.aspect, 1.0);
+
+    var output: VertexOutput;
+    output.clip_position = vec4<f32>(center + offset * radius, 0.0, 1.0);
+    output.color = boid.color;
+    return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if params.blend_mode == BLEND_ALPHA {
+        return in.color;
+    }
+    return vec4<f32>(in.color.rgb * in.color.a, in.color.a);
+}
@@ -1,14 +1,80 @@
-struct Particle {
-    pos: vec2<f32>,
-    vel: vec2<f32>,
-    color: vec4<f32>,
-    life: f32,
-};
+#include "particle.wgsl"
+#include "sim.wgsl"
+#include "emitter.wgsl"
+#include "forces.wgsl"
 
-@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
-@group(0) @binding(1) var<uniform> delta_time: f32;
+#ifndef WORKGROUP_SIZE
+#define WORKGROUP_SIZE 64u
+#endif
 
-@compute @workgroup_size(64)
+// 上一帧的状态，只读；新状态写进 next_particles，两个缓冲区每帧交换
+@group(0) @binding(0) var<storage, read> particles: array<Particle>;
+@group(0) @binding(1) var<uniform> params: SimParams;
+@group(0) @binding(2) var<storage, read_write> counters: Counters;
+@group(0) @binding(3) var<storage, read> emitters: array<Emitter>;
+@group(0) @binding(4) var<storage, read> forces: array<ForceField>;
+@group(0) @binding(5) var<storage, read_write> next_particles: array<Particle>;
+// 死亡的粒子按下标顺序领到的号码，活着的粒子不用
+@group(0) @binding(6) var<storage, read_write> tickets: array<u32>;
+
+// 发号码的前缀和只用一个工作组
+const SCAN_SIZE: u32 = 256u;
+
+var<workgroup> scan: array<u32, SCAN_SIZE>;
+
+// 号码按发射器的顺序分段：前 spawn_count 个号码属于第一个发射器，以此类推
+fn find_emitter(ticket: u32) -> u32 {
+    var rest = ticket;
+    for (var i = 0u; i < params.emitter_count; i++) {
+        let count = emitters[i].spawn_count;
+        if rest < count {
+            return i;
+        }
+        rest -= count;
+    }
+    return params.emitter_count - 1u;
+}
+
+// 1. 按下标顺序给这一帧开始时已经死亡的粒子发号码，顺便重置计数器。
+//    号码不取决于线程执行的先后，同样的输入每次都是同样的粒子重生
+@compute @workgroup_size(SCAN_SIZE)
+fn assign_tickets(@builtin(local_invocation_index) t: u32) {
+    let count = arrayLength(&particles);
+    let chunk = (count + SCAN_SIZE - 1u) / SCAN_SIZE;
+    let begin = min(t * chunk, count);
+    let end = min(begin + chunk, count);
+    var dead = 0u;
+    for (var i = begin; i < end; i++) {
+        if particles[i].life <= 0.0 {
+            dead += 1u;
+        }
+    }
+    scan[t] = dead;
+    workgroupBarrier();
+    for (var offset = 1u; offset < SCAN_SIZE; offset *= 2u) {
+        var value = 0u;
+        if t >= offset {
+            value = scan[t - offset];
+        }
+        workgroupBarrier();
+        scan[t] += value;
+        workgroupBarrier();
+    }
+    var ticket = scan[t] - dead;
+    for (var i = begin; i < end; i++) {
+        if particles[i].life <= 0.0 {
+            tickets[i] = ticket;
+            ticket += 1u;
+        }
+    }
+    if t == SCAN_SIZE - 1u {
+        atomicStore(&counters.live, 0u);
+        atomicStore(&counters.dead, scan[t]);
+    }
+}
+
+// 2. 重生、受力、积分和寿命
+@compute @workgroup_size(WORKGROUP_SIZE)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let index = global_id.x;
     if index >= arrayLength(&particles) {
@@ -17,18 +83,34 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
     // 读取当前粒子
     var particle = particles[index];
-    // 物理更新
-    particle.pos += particle.vel * delta_time;
-    // 碰撞检测,触碰到墙壁反弹
-    if (particle.pos.x > 1.0 || particle.pos.x < -1.0) {
-        particle.vel.x = -particle.vel.x;
+
+    // 死亡的粒子号码在这一帧的名额之内就由对应的发射器重生，否则继续等待
+    if particle.life <= 0.0 {
+        let ticket = tickets[index];
+        if ticket >= params.spawn_count {
+            // 另一个缓冲区里是两帧之前的状态，没轮到重生也要写一遍
+            next_particles[index] = particle;
+            return;
+        }
+        // 粒子下标和帧种子一起决定随机数，同一个粒子每次重生都不一样
+        particle = emit(emitters[find_emitter(ticket)], index * 1664525u + params.seed * 1013904223u);
     }
-    if (particle.pos.y > 1.0 || particle.pos.y < -1.0) {
-        particle.vel.y = -particle.vel.y;
+
+    // 依次叠加每个力场，再用新的速度更新位置（半隐式欧拉）
+    for (var i = 0u; i < params.force_count; i++) {
+        particle.vel = apply_force(forces[i], particle.pos, particle.vel, params.time, params.delta_time);
     }
+    particle.pos += particle.vel * params.delta_time;
+    // 碰撞检测,触碰到窗口边缘反弹；只反弹朝外的速度，窗口缩小后留在外面的粒子会慢慢回来
+    let outside = abs(particle.pos) > params.bounds;
+    let outward = sign(particle.pos) * particle.vel > vec2<f32>(0.0);
+    particle.vel = select(particle.vel, -particle.vel, outside & outward);
 
     // 寿命管理
-    particle.life -= delta_time;
+    particle.life -= params.delta_time;
+    if particle.life > 0.0 {
+        atomicAdd(&counters.live, 1u);
+    }
 
-    particles[index] = particle;
+    next_particles[index] = particle;
 }
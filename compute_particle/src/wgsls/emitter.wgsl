@@ -0,0 +1,96 @@
+// 和 Rust 端的 compute_particle::emitter 一一对应，改这里的时候记得同步
+#include "particle.wgsl"
+#include <random.wgsl>
+
+const TAU: f32 = 6.28318530718;
+
+// 发射器形状
+const SHAPE_POINT: u32 = 0u;
+const SHAPE_CIRCLE: u32 = 1u;
+const SHAPE_BOX: u32 = 2u;
+const SHAPE_CONE: u32 = 3u;
+const SHAPE_LINE: u32 = 4u;
+
+// 初速度的方向分布
+const DIRECTION_ANY: u32 = 0u;
+const DIRECTION_OUTWARD: u32 = 1u;
+const DIRECTION_ANGLE: u32 = 2u;
+
+struct Emitter {
+    // 颜色在两者之间随机
+    color_start: vec4<f32>,
+    color_end: vec4<f32>,
+    position: vec2<f32>,
+    // 形状参数：圆环是（外半径，内半径），矩形是（半宽，半高），
+    // 锥形是（方向，张角），线段是另一端相对 position 的偏移
+    shape: vec2<f32>,
+    // 方向分布的参数：（方向，张角），DIRECTION_OUTWARD 只用张角
+    direction: vec2<f32>,
+    speed: vec2<f32>,
+    lifetime: vec2<f32>,
+    shape_kind: u32,
+    direction_kind: u32,
+    // 这一帧分到的重生名额
+    spawn_count: u32,
+};
+
+// 同一个种子按用途取不同的随机数
+fn emitter_random(seed: u32, salt: u32) -> f32 {
+    return pcg_hash(seed + salt * 2654435761u);
+}
+
+// 按发射器的形状和分布生成一个新粒子
+fn emit(emitter: Emitter, seed: u32) -> Particle {
+    let r0 = emitter_random(seed, 0u);
+    let r1 = emitter_random(seed, 1u);
+
+    // 出生位置相对发射器的偏移，以及“向外”的方向
+    var offset = vec2<f32>(0.0);
+    var outward = vec2<f32>(cos(r0 * TAU), sin(r0 * TAU));
+    switch emitter.shape_kind {
+        case SHAPE_CIRCLE: {
+            // 半径的平方均匀分布，圆环上的点按面积均匀
+            let outer = emitter.shape.x;
+            let inner = emitter.shape.y;
+            offset = outward * sqrt(mix(inner * inner, outer * outer, r1));
+        }
+        case SHAPE_BOX: {
+            offset = (vec2<f32>(r0, r1) * 2.0 - 1.0) * emitter.shape;
+            if dot(offset, offset) > 0.0 {
+                outward = normalize(offset);
+            }
+        }
+        case SHAPE_LINE: {
+            offset = emitter.shape * r0;
+            // 线段的法线，两侧随机
+            if dot(emitter.shape, emitter.shape) > 0.0 {
+                let along = normalize(emitter.shape);
+                outward = vec2<f32>(-along.y, along.x) * select(-1.0, 1.0, r1 < 0.5);
+            }
+        }
+        default: {}
+    }
+
+    // 初速度方向：在基准方向两侧各张开一半的张角
+    let r2 = emitter_random(seed, 2u);
+    let jitter = r2 - 0.5;
+    var angle = r2 * TAU;
+    if emitter.shape_kind == SHAPE_CONE {
+        angle = emitter.shape.x + jitter * emitter.shape.y;
+    } else if emitter.direction_kind == DIRECTION_OUTWARD {
+        angle = atan2(outward.y, outward.x) + jitter * emitter.direction.y;
+    } else if emitter.direction_kind == DIRECTION_ANGLE {
+        angle = emitter.direction.x + jitter * emitter.direction.y;
+    }
+    let speed = mix(emitter.speed.x, emitter.speed.y, emitter_random(seed, 3u));
+    let max_life = mix(emitter.lifetime.x, emitter.lifetime.y, emitter_random(seed, 4u));
+
+    var particle: Particle;
+    particle.pos = emitter.position + offset;
+    particle.vel = vec2<f32>(cos(angle), sin(angle)) * speed;
+    particle.color = mix(emitter.color_start, emitter.color_end, emitter_random(seed, 5u));
+    particle.life = max_life;
+    particle.max_life = max_life;
+    particle.mass = 1.0;
+    return particle;
+}
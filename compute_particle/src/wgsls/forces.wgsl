@@ -0,0 +1,110 @@
+// 和 Rust 端的 compute_particle::forces 一一对应，改这里的时候记得同步
+#include <random.wgsl>
+
+const FORCE_GRAVITY: u32 = 0u;
+const FORCE_DRAG: u32 = 1u;
+const FORCE_ATTRACTOR: u32 = 2u;
+const FORCE_VORTEX: u32 = 3u;
+const FORCE_CURL_NOISE: u32 = 4u;
+
+// 强度随到力场中心的距离衰减的方式
+const FALLOFF_CONSTANT: u32 = 0u;
+const FALLOFF_LINEAR: u32 = 1u;
+const FALLOFF_INVERSE_SQUARE: u32 = 2u;
+
+struct ForceField {
+    // 力场中心：吸引子、漩涡的位置，也是衰减的起点
+    position: vec2<f32>,
+    // 按种类解释：重力是方向，阻力是（线性系数，二次系数），旋度噪声是（空间频率，变化速度）
+    vector: vec2<f32>,
+    // 吸引子为负时是排斥
+    strength: f32,
+    // 衰减半径，小于等于 0 时处处相同
+    radius: f32,
+    kind: u32,
+    falloff: u32,
+};
+
+fn falloff_weight(field: ForceField, distance: f32) -> f32 {
+    if field.radius <= 0.0 {
+        return 1.0;
+    }
+    let d = distance / field.radius;
+    switch field.falloff {
+        case FALLOFF_LINEAR: {
+            return max(1.0 - d, 0.0);
+        }
+        case FALLOFF_INVERSE_SQUARE: {
+            // 半径当作软化长度，中心处不会无穷大
+            return 1.0 / (1.0 + d * d);
+        }
+        default: {
+            return select(0.0, 1.0, d <= 1.0);
+        }
+    }
+}
+
+// 晶格点上的随机单位梯度
+fn lattice_gradient(cell: vec2<f32>) -> vec2<f32> {
+    let h = pcg_hash(bitcast<u32>(cell.x) * 73856093u ^ bitcast<u32>(cell.y) * 19349663u);
+    let angle = h * 6.28318530718;
+    return vec2<f32>(cos(angle), sin(angle));
+}
+
+// 二维梯度噪声，范围大约在 [-0.7, 0.7]
+fn gradient_noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = dot(lattice_gradient(cell), f);
+    let b = dot(lattice_gradient(cell + vec2<f32>(1.0, 0.0)), f - vec2<f32>(1.0, 0.0));
+    let c = dot(lattice_gradient(cell + vec2<f32>(0.0, 1.0)), f - vec2<f32>(0.0, 1.0));
+    let d = dot(lattice_gradient(cell + vec2<f32>(1.0, 1.0)), f - vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// 噪声势函数的旋度 (∂ψ/∂y, -∂ψ/∂x)，散度为 0，粒子打旋但不会聚成一团
+fn curl_noise(p: vec2<f32>) -> vec2<f32> {
+    let e = 0.01;
+    let dx = gradient_noise(p + vec2<f32>(e, 0.0)) - gradient_noise(p - vec2<f32>(e, 0.0));
+    let dy = gradient_noise(p + vec2<f32>(0.0, e)) - gradient_noise(p - vec2<f32>(0.0, e));
+    return vec2<f32>(dy, -dx) / (2.0 * e);
+}
+
+// 一个力场作用 dt 秒之后粒子的速度
+fn apply_force(field: ForceField, pos: vec2<f32>, vel: vec2<f32>, time: f32, dt: f32) -> vec2<f32> {
+    let to_center = field.position - pos;
+    let distance = length(to_center);
+    let strength = field.strength * falloff_weight(field, distance);
+    switch field.kind {
+        case FORCE_GRAVITY: {
+            return vel + field.vector * strength * dt;
+        }
+        case FORCE_DRAG: {
+            // 隐式积分，dt 较大时也不会把速度减成反方向
+            let k = (field.vector.x + field.vector.y * length(vel)) * strength;
+            return vel / (1.0 + max(k, 0.0) * dt);
+        }
+        case FORCE_ATTRACTOR: {
+            if distance < 1e-4 {
+                return vel;
+            }
+            return vel + to_center / distance * strength * dt;
+        }
+        case FORCE_VORTEX: {
+            if distance < 1e-4 {
+                return vel;
+            }
+            // 绕中心逆时针旋转
+            let tangent = vec2<f32>(to_center.y, -to_center.x) / distance;
+            return vel + tangent * strength * dt;
+        }
+        case FORCE_CURL_NOISE: {
+            let p = pos * field.vector.x + vec2<f32>(time * field.vector.y);
+            return vel + curl_noise(p) * strength * dt;
+        }
+        default: {
+            return vel;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// 均匀网格空间哈希：用计数排序把粒子的下标按所在的格子排好，之后只需要在周围 3x3 个格子里找邻居
+// 鸟群和 SPH 流体共用，和 Rust 端的 compute_particle::grid 对应，改这里的时候记得同步
+// 网格的参数和缓冲区都在第 1 组。包含它的着色器自己提供第一步的入口，对每个粒子调用 grid_insert，
+// 之后依次是这里的 prefix_sum、scatter、sort_cells
+#ifndef WORKGROUP_SIZE
+#define WORKGROUP_SIZE 64u
+#endif
+
+// 前缀和只用一个工作组
+const SCAN_SIZE: u32 = 256u;
+
+struct GridParams {
+    // 世界坐标的边界，网格覆盖 x 在 ±bounds.x、y 在 ±bounds.y 之间的区域
+    bounds: vec2<f32>,
+    // 网格的格子数
+    size: vec2<u32>,
+    // 格子边长，不小于邻居的搜索半径，邻居一定在周围 3x3 个格子里
+    cell_size: f32,
+    // 粒子数
+    count: u32,
+};
+
+@group(1) @binding(0) var<uniform> grid: GridParams;
+// 每个格子里有几个粒子；前缀和之后清零，散射时当作写入位置的游标
+@group(1) @binding(1) var<storage, read_write> cell_counts: array<atomic<u32>>;
+// 每个格子在 sorted 里的起始位置，最后多一个元素存总数
+@group(1) @binding(2) var<storage, read_write> cell_start: array<u32>;
+@group(1) @binding(3) var<storage, read_write> particle_cells: array<u32>;
+// 按格子排好的粒子下标，同一个格子里按下标升序
+@group(1) @binding(4) var<storage, read_write> sorted: array<u32>;
+
+var<workgroup> scan: array<u32, SCAN_SIZE>;
+
+fn cell_coord(pos: vec2<f32>) -> vec2<i32> {
+    let cell = vec2<i32>(floor((pos + grid.bounds) / grid.cell_size));
+    return clamp(cell, vec2<i32>(0), vec2<i32>(grid.size) - 1);
+}
+
+fn cell_index(cell: vec2<i32>) -> u32 {
+    return u32(cell.y) * grid.size.x + u32(cell.x);
+}
+
+fn cell_total() -> u32 {
+    return grid.size.x * grid.size.y;
+}
+
+// 找邻居时跳过网格外面的格子
+fn in_grid(cell: vec2<i32>) -> bool {
+    return all(cell >= vec2<i32>(0)) && all(cell < vec2<i32>(grid.size));
+}
+
+// 1. 统计每个格子里有几个粒子，由包含它的着色器对每个粒子调用
+fn grid_insert(i: u32, pos: vec2<f32>) {
+    let cell = cell_index(cell_coord(pos));
+    particle_cells[i] = cell;
+    atomicAdd(&cell_counts[cell], 1u);
+}
+
+// 2. 计数的前缀和：每个线程先顺序累加一段格子，再在工作组里做 Hillis-Steele 扫描
+@compute @workgroup_size(SCAN_SIZE)
+fn prefix_sum(@builtin(local_invocation_index) t: u32) {
+    let cells = cell_total();
+    let chunk = (cells + SCAN_SIZE - 1u) / SCAN_SIZE;
+    let begin = min(t * chunk, cells);
+    let end = min(begin + chunk, cells);
+    var sum = 0u;
+    for (var c = begin; c < end; c++) {
+        sum += atomicLoad(&cell_counts[c]);
+    }
+    scan[t] = sum;
+    workgroupBarrier();
+    for (var offset = 1u; offset < SCAN_SIZE; offset *= 2u) {
+        var value = 0u;
+        if t >= offset {
+            value = scan[t - offset];
+        }
+        workgroupBarrier();
+        scan[t] += value;
+        workgroupBarrier();
+    }
+    var running = scan[t] - sum;
+    for (var c = begin; c < end; c++) {
+        cell_start[c] = running;
+        running += atomicLoad(&cell_counts[c]);
+        atomicStore(&cell_counts[c], 0u);
+    }
+    if t == SCAN_SIZE - 1u {
+        cell_start[cells] = scan[t];
+    }
+}
+
+// 3. 按格子散射，格子里的顺序取决于原子操作的先后
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn scatter(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= grid.count {
+        return;
+    }
+    let cell = particle_cells[i];
+    sorted[cell_start[cell] + atomicAdd(&cell_counts[cell], 1u)] = i;
+}
+
+// 4. 每个格子里按下标插入排序，累加邻居的顺序固定，结果才是确定的；
+//    散射之后计数又变回了每个格子的粒子数，这里顺便清零，下一次从 0 开始统计
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn sort_cells(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let cell = global_id.x;
+    if cell >= cell_total() {
+        return;
+    }
+    atomicStore(&cell_counts[cell], 0u);
+    let begin = cell_start[cell];
+    let end = cell_start[cell + 1u];
+    for (var a = begin + 1u; a < end; a++) {
+        let value = sorted[a];
+        var b = a;
+        while b > begin && sorted[b - 1u] > value {
+            sorted[b] = sorted[b - 1u];
+            b -= 1u;
+        }
+        sorted[b] = value;
+    }
+}
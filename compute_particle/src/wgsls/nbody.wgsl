@@ -0,0 +1,147 @@
+// N 体引力模拟：每个粒子受到其他所有粒子的引力，O(N²)
+// 和 Rust 端的 compute_particle::nbody 对应，改这里的时候记得同步，CPU 参考实现也要一起改
+#include "particle.wgsl"
+
+// 宏从 Rust 端注入时是 `64u` 这样的 u32 字面量，由 nbody::shader_defines 生成
+#ifndef WORKGROUP_SIZE
+#define WORKGROUP_SIZE 64u
+#endif
+// 分块大小：每次读进共享内存的粒子数，也是 reduce_sums 折半的起点。
+// 共享内存数组的长度是 WORKGROUP_SIZE，两者必须相等并且是 2 的幂，Rust 端生成宏时会检查
+#ifndef TILE_SIZE
+#define TILE_SIZE WORKGROUP_SIZE
+#endif
+
+struct NBodyParams {
+    count: u32,
+    // 每一小步的时间，为 0 时只计算加速度
+    delta_time: f32,
+    // 引力常数
+    gravity: f32,
+    // 软化长度：距离的平方加上它的平方，靠得很近时引力不会发散
+    softening: f32,
+};
+
+struct Energy {
+    kinetic: f32,
+    potential: f32,
+};
+
+@group(0) @binding(0) var<storage, read_write> bodies: array<Particle>;
+// 上一次算出来的加速度，跳蛙法的前半步用
+@group(0) @binding(1) var<storage, read_write> accelerations: array<vec2<f32>>;
+@group(0) @binding(2) var<uniform> params: NBodyParams;
+// 每个工作组的能量之和
+@group(0) @binding(3) var<storage, read_write> partial_energy: array<Energy>;
+@group(0) @binding(4) var<storage, read_write> total_energy: Energy;
+
+// 一块粒子的位置和质量，整个工作组一起从全局内存读进来，之后每个线程都从共享内存读
+var<workgroup> tile: array<vec4<f32>, WORKGROUP_SIZE>;
+var<workgroup> sums: array<vec2<f32>, WORKGROUP_SIZE>;
+
+// 第 i 个粒子受到的引力加速度（xy）和所在位置的引力势（z），不算自己
+// 所有线程都要调用，包括超出粒子数的线程，否则 workgroupBarrier 不在统一控制流里
+fn interact(i: u32, pos: vec2<f32>, local: u32) -> vec3<f32> {
+    let softening_sq = params.softening * params.softening;
+    var acceleration = vec2<f32>(0.0);
+    var potential = 0.0;
+    for (var start = 0u; start < params.count; start += TILE_SIZE) {
+        let j = start + local;
+        if j < params.count {
+            // 只读位置和质量：accelerate 里别的线程同时在写 bodies[j].vel，整个结构体一起读会有数据竞争
+            tile[local] = vec4<f32>(bodies[j].pos, bodies[j].mass, 0.0);
+        } else {
+            tile[local] = vec4<f32>(0.0);
+        }
+        workgroupBarrier();
+        for (var k = 0u; k < TILE_SIZE; k++) {
+            let other = tile[k];
+            if start + k == i || other.z == 0.0 {
+                continue;
+            }
+            let offset = other.xy - pos;
+            let inverse = inverseSqrt(dot(offset, offset) + softening_sq);
+            acceleration += other.z * inverse * inverse * inverse * offset;
+            potential -= other.z * inverse;
+        }
+        workgroupBarrier();
+    }
+    return vec3<f32>(acceleration, potential) * params.gravity;
+}
+
+// 跳蛙法（kick-drift-kick）的前半步：用上一次的加速度把速度推进半步，再把位置推进一整步
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn drift(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    var body = bodies[i];
+    body.vel += accelerations[i] * (0.5 * params.delta_time);
+    body.pos += body.vel * params.delta_time;
+    bodies[i] = body;
+}
+
+// 后半步：在新位置上算加速度，再把速度推进半步
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn accelerate(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local: u32,
+) {
+    let i = global_id.x;
+    let index = min(i, params.count - 1u);
+    let acceleration = interact(i, bodies[index].pos, local).xy;
+    if i >= params.count {
+        return;
+    }
+    accelerations[i] = acceleration;
+    bodies[i].vel += acceleration * (0.5 * params.delta_time);
+}
+
+// 把工作组里每个线程的值加起来，结果在 sums[0]
+fn reduce_sums(local: u32) {
+    for (var stride = TILE_SIZE / 2u; stride > 0u; stride /= 2u) {
+        if local < stride {
+            sums[local] += sums[local + stride];
+        }
+        workgroupBarrier();
+    }
+}
+
+// 每个粒子的动能和一半的势能（每一对算了两次），按工作组求和
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn energy(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    let i = global_id.x;
+    let body = bodies[min(i, params.count - 1u)];
+    let potential = interact(i, body.pos, local).z;
+    var value = vec2<f32>(0.0);
+    if i < params.count {
+        value = body.mass * vec2<f32>(0.5 * dot(body.vel, body.vel), 0.5 * potential);
+    }
+    sums[local] = value;
+    workgroupBarrier();
+    reduce_sums(local);
+    if local == 0u {
+        partial_energy[group.x] = Energy(sums[0].x, sums[0].y);
+    }
+}
+
+// 只用一个工作组，把各个工作组的部分和再加起来
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn reduce_energy(@builtin(local_invocation_index) local: u32) {
+    let groups = (params.count + TILE_SIZE - 1u) / TILE_SIZE;
+    var value = vec2<f32>(0.0);
+    for (var g = local; g < groups; g += TILE_SIZE) {
+        value += vec2<f32>(partial_energy[g].kinetic, partial_energy[g].potential);
+    }
+    sums[local] = value;
+    workgroupBarrier();
+    reduce_sums(local);
+    if local == 0u {
+        total_energy = Energy(sums[0].x, sums[0].y);
+    }
+}
@@ -0,0 +1,12 @@
+// 和 Rust 端的 compute_particle::Particle 一一对应，改这里的时候记得同步
+struct Particle {
+    pos: vec2<f32>,
+    vel: vec2<f32>,
+    color: vec4<f32>,
+    // 剩余寿命（秒），小于等于 0 表示已经死亡，等待重生
+    life: f32,
+    // 出生时的寿命，用来计算淡出和缩小的比例
+    max_life: f32,
+    // 质量，只有 N 体引力模拟用到
+    mass: f32,
+};
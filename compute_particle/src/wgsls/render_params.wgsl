@@ -0,0 +1,21 @@
+// 两个渲染着色器共用的渲染参数
+// 和 Rust 端的 compute_particle::render_params 对应，改这里的时候记得同步
+const SIZE_PIXELS: u32 = 0u;
+const SIZE_WORLD: u32 = 1u;
+const BLEND_ALPHA: u32 = 0u;
+const BLEND_PREMULTIPLIED: u32 = 1u;
+const BLEND_ADDITIVE: u32 = 2u;
+
+struct RenderParams {
+    // 出生、1/3、2/3 和死亡时的大小倍数
+    size_curve: vec4<f32>,
+    // 窗口的像素大小
+    viewport: vec2<f32>,
+    // 宽除以高，世界坐标的 x 范围是 [-aspect, aspect]
+    aspect: f32,
+    // 粒子半径，单位由 size_unit 决定
+    size: f32,
+    size_unit: u32,
+    // 除了普通的 alpha 混合，其他混合方式都要输出预乘过的颜色
+    blend_mode: u32,
+};
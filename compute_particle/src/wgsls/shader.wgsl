@@ -1,9 +1,6 @@
-struct Particle {
-    pos: vec2<f32>,
-    vel: vec2<f32>,
-    color: vec4<f32>,
-    life: f32,
-};
+#include "particle.wgsl"
+
+#include "render_params.wgsl"
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
@@ -14,18 +11,23 @@ struct VertexOutput {
 
 // 这里是只读的，因为渲染时不需要修改数据
 @group(0) @binding(0) var<storage, read> particles: array<Particle>;
+@group(0) @binding(1) var<uniform> params: RenderParams;
+
+// age 是已经活过的比例，在曲线的四个点之间线性插值
+fn size_over_life(age: f32) -> f32 {
+    let x = clamp(age, 0.0, 1.0) * 3.0;
+    let i = min(u32(x), 2u);
+    return mix(params.size_curve[i], params.size_curve[i + 1u], x - f32(i));
+}
 
 @vertex
-fn vs_main(
-    @builtin(vertex_index) v_index: u32,
-    @builtin(instance_index) i_index: u32
-) -> VertexOutput {
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
     var output: VertexOutput;
     // 1. 获取对应的粒子
-    // output.clip_position = vec4<f32>(particles[i_index].pos, 0.0, 1.0);
-    // output.color = particles[i_index].color;
-    //
-    let particle = particles[i_index];
+    // 每个粒子 6 个顶点：不排序时顶点编号就是 0..6N，排序时由索引缓冲区给出，
+    // 除以 6 是第几个粒子，余数是正方形的第几个顶点
+    let particle = particles[index / 6u];
+    let v_index = index % 6u;
 
     // 2. 凭空定义一个正方形的 4 个角 (偏移量)
     // 顺序是：左下, 右下, 左上, 右上
@@ -48,16 +50,24 @@ fn vs_main(
     // 根据 vertex_index 获取对应的角的坐标
     let offset = corners[corner_indices[v_index]];
     // 3. 设定粒子大小（半径）
-    let radius = 0.05; // 可以调节，uniform传递
+    // 寿命快用完时按曲线缩放、逐渐变透明；死亡的粒子半径为 0，两个三角形退化成点，不会被光栅化
+    let remaining = clamp(particle.life / max(particle.max_life, 1e-6), 0.0, 1.0);
+    let alive = select(0.0, 1.0, particle.life > 0.0);
+    let scale = params.size * size_over_life(1.0 - remaining) * alive;
+    // 裁剪空间里的半径：x 和 y 分别换算，窗口不是正方形时粒子也是圆的
+    var radius = vec2<f32>(scale / params.aspect, scale);
+    if params.size_unit == SIZE_PIXELS {
+        // 裁剪空间的宽高都是 2
+        radius = 2.0 * scale / params.viewport;
+    }
 
     // 4. 计算最终位置
-    // 粒子中心 + 偏移量 * 半径（大小）
-    // 注意：这里没有处理长宽比，如果窗口不是正方形，粒子会变扁。
-    // 如果要完美圆形，需要传入窗口长宽比进行修正。
-    let final_pos = particle.pos + offset * radius;
+    // 世界坐标的 x 除以长宽比回到裁剪空间，再加上偏移量 * 半径（大小）
+    let center = particle.pos / vec2<f32>(params.aspect, 1.0);
+    let final_pos = center + offset * radius;
     // 计算顶点位置
     output.clip_position = vec4<f32>(final_pos, 0.0, 1.0);
-    output.color = particle.color;
+    output.color = vec4<f32>(particle.color.rgb, particle.color.a * remaining);
 
     // 5. 传递uv（把 -1.0~1.0 的 offset 当作UV用，方便画圆）
     output.uv = offset;
@@ -101,5 +111,9 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // 圆心区域：1.0 - 0.0 = 1.0 (完全不透明，实心的)。
     // 边缘区域：1.0 - (0.0~1.0) = 1.0 慢慢变到 0.0 (慢慢变透明)。
     let alpha = 1.0 - smoothstep(0.8, 1.0, sqrt(dist_sq));
-    return vec4<f32>(in.color.rgb, in.color.a * alpha);
+    let a = in.color.a * alpha;
+    if params.blend_mode == BLEND_ALPHA {
+        return vec4<f32>(in.color.rgb, a);
+    }
+    return vec4<f32>(in.color.rgb * a, a);
 }
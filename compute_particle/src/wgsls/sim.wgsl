@@ -0,0 +1,26 @@
+// 和 Rust 端的 compute_particle::lifecycle 一一对应，改这里的时候记得同步
+
+// 每帧更新的模拟参数
+struct SimParams {
+    delta_time: f32,
+    // 这一帧所有发射器的重生名额之和
+    spawn_count: u32,
+    // 每帧变化的随机数种子
+    seed: u32,
+    // 发射器缓冲区里有效的发射器个数
+    emitter_count: u32,
+    // 模拟开始以来的秒数，驱动随时间变化的力场
+    time: f32,
+    // 力场缓冲区里有效的力场个数
+    force_count: u32,
+    // 世界坐标的边界：x 在 ±bounds.x 之间，y 在 ±bounds.y 之间，随窗口的长宽比变化
+    bounds: vec2<f32>,
+};
+
+// 计数器，每帧发号码时重置，存活数再用原子操作累加
+struct Counters {
+    // 这一帧结束时还活着的粒子
+    live: atomic<u32>,
+    // 这一帧开始时已经死亡的粒子，也就是发出去的号码数
+    dead: atomic<u32>,
+};
@@ -0,0 +1,73 @@
+// 按年龄给粒子排序，生成绘制用的索引缓冲区
+// 和 Rust 端的 compute_particle::sort 一一对应，改这里的时候记得同步
+#include "particle.wgsl"
+
+#ifndef WORKGROUP_SIZE
+#define WORKGROUP_SIZE 64u
+#endif
+
+// 死亡的粒子排在活着的后面，补齐用的条目排在最后
+const DEAD_KEY: f32 = 1e30;
+const PADDING_KEY: f32 = 3e38;
+
+struct SortEntry {
+    key: f32,
+    index: u32,
+};
+
+// 双调排序的一步：和下标相差 j 的条目比较，所在的 k 大小的块决定升序还是降序
+struct SortStage {
+    k: u32,
+    j: u32,
+};
+
+@group(0) @binding(0) var<storage, read> particles: array<Particle>;
+@group(0) @binding(1) var<storage, read_write> entries: array<SortEntry>;
+@group(0) @binding(2) var<storage, read_write> indices: array<u32>;
+@group(0) @binding(3) var<uniform> stage: SortStage;
+
+// 排序键：年龄越大越靠前，先画老的粒子，新粒子盖在上面
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn write_keys(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= arrayLength(&entries) {
+        return;
+    }
+    var entry = SortEntry(PADDING_KEY, 0u);
+    if i < arrayLength(&particles) {
+        let particle = particles[i];
+        entry.index = i;
+        entry.key = select(DEAD_KEY, particle.life - particle.max_life, particle.life > 0.0);
+    }
+    entries[i] = entry;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn bitonic_step(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    let partner = i ^ stage.j;
+    // 每一对只由下标小的那个线程处理
+    if i >= arrayLength(&entries) || partner <= i {
+        return;
+    }
+    let a = entries[i];
+    let b = entries[partner];
+    let ascending = (i & stage.k) == 0u;
+    if (a.key > b.key) == ascending {
+        entries[i] = b;
+        entries[partner] = a;
+    }
+}
+
+// 每个粒子 6 个顶点，按排好的顺序写进索引缓冲区
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn write_indices(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= arrayLength(&particles) {
+        return;
+    }
+    let first = entries[i].index * 6u;
+    for (var corner = 0u; corner < 6u; corner++) {
+        indices[i * 6u + corner] = first + corner;
+    }
+}
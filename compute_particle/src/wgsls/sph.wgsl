@@ -0,0 +1,150 @@
+// SPH（光滑粒子流体动力学）流体：每一小步先重建网格，再依次算密度、压力和黏性力，最后积分并处理边界
+// 和 Rust 端的 compute_particle::sph 对应，改这里的时候记得同步，CPU 参考实现也要一起改
+#include "particle.wgsl"
+#include "grid.wgsl"
+
+struct SphParams {
+    // 世界坐标的边界，也是容器的墙
+    bounds: vec2<f32>,
+    gravity: vec2<f32>,
+    count: u32,
+    delta_time: f32,
+    // 光滑核半径，只有比它近的粒子才互相影响
+    smoothing_radius: f32,
+    rest_density: f32,
+    // 压力 = stiffness * (密度 - 静止密度)
+    stiffness: f32,
+    viscosity: f32,
+    // 撞墙之后法向速度保留的比例
+    restitution: f32,
+    // 二维核函数的归一化系数，在 CPU 上按 smoothing_radius 算好
+    poly6: f32,
+    spiky: f32,
+    viscosity_laplacian: f32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<uniform> params: SphParams;
+@group(0) @binding(2) var<storage, read_write> densities: array<f32>;
+// 压力和黏性力产生的加速度，全部算完之后再积分，邻居读到的都是这一小步开始时的状态
+@group(0) @binding(3) var<storage, read_write> accelerations: array<vec2<f32>>;
+
+// 密度的颜色：静止密度附近是深蓝，越稀越浅，表面的粒子接近白色
+const SPARSE_COLOR = vec4<f32>(0.75, 0.9, 1.0, 1.0);
+const DENSE_COLOR = vec4<f32>(0.05, 0.25, 0.8, 1.0);
+
+fn density_color(density: f32) -> vec4<f32> {
+    let t = clamp((density / params.rest_density - 0.5) * 2.0, 0.0, 1.0);
+    return mix(SPARSE_COLOR, DENSE_COLOR, t);
+}
+
+// 1. 统计每个格子里有几个粒子，之后是 grid.wgsl 里的前缀和、散射和格子内排序
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn count(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    grid_insert(i, particles[i].pos);
+}
+
+// 5. 密度：周围粒子的质量按 poly6 核加权求和，包括自己
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn density(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    let pos = particles[i].pos;
+    let center = cell_coord(pos);
+    let radius_sq = params.smoothing_radius * params.smoothing_radius;
+    var sum = 0.0;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let cell = center + vec2<i32>(dx, dy);
+            if !in_grid(cell) {
+                continue;
+            }
+            let index = cell_index(cell);
+            for (var slot = cell_start[index]; slot < cell_start[index + 1u]; slot++) {
+                let other = particles[sorted[slot]];
+                let offset = other.pos - pos;
+                let distance_sq = dot(offset, offset);
+                if distance_sq >= radius_sq {
+                    continue;
+                }
+                let w = radius_sq - distance_sq;
+                sum += other.mass * params.poly6 * w * w * w;
+            }
+        }
+    }
+    densities[i] = sum;
+}
+
+// 6. 压力和黏性力：压力梯度用 spiky 核，两个粒子的压力取平均保证作用力对称；黏性力用黏性核的拉普拉斯
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn forces(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    let particle = particles[i];
+    let density = densities[i];
+    let pressure = params.stiffness * (density - params.rest_density);
+    let center = cell_coord(particle.pos);
+    let h = params.smoothing_radius;
+    var pressure_sum = vec2<f32>(0.0);
+    var viscosity_sum = vec2<f32>(0.0);
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let cell = center + vec2<i32>(dx, dy);
+            if !in_grid(cell) {
+                continue;
+            }
+            let index = cell_index(cell);
+            for (var slot = cell_start[index]; slot < cell_start[index + 1u]; slot++) {
+                let j = sorted[slot];
+                if j == i {
+                    continue;
+                }
+                let other = particles[j];
+                let offset = particle.pos - other.pos;
+                let distance = length(offset);
+                if distance >= h {
+                    continue;
+                }
+                let other_density = densities[j];
+                let other_pressure = params.stiffness * (other_density - params.rest_density);
+                let falloff = h - distance;
+                // 两个粒子重合时没有方向，只算黏性
+                if distance > 1e-6 {
+                    let shared_pressure = (pressure + other_pressure) / (2.0 * other_density);
+                    pressure_sum += other.mass * shared_pressure * params.spiky * falloff * falloff
+                        * (offset / distance);
+                }
+                viscosity_sum += other.mass * (other.vel - particle.vel) / other_density
+                    * params.viscosity_laplacian * falloff;
+            }
+        }
+    }
+    accelerations[i] = (pressure_sum + params.viscosity * viscosity_sum) / density + params.gravity;
+}
+
+// 7. 半隐式欧拉积分，撞到墙时把粒子放回墙上、法向速度反向并衰减，颜色按密度更新
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn integrate(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= params.count {
+        return;
+    }
+    var particle = particles[i];
+    particle.vel += accelerations[i] * params.delta_time;
+    particle.pos += particle.vel * params.delta_time;
+    let outside = abs(particle.pos) > params.bounds;
+    let bounce = select(vec2<f32>(1.0), vec2<f32>(-params.restitution), outside);
+    particle.vel *= bounce;
+    particle.pos = clamp(particle.pos, -params.bounds, params.bounds);
+    particle.color = density_color(densities[i]);
+    particles[i] = particle;
+}
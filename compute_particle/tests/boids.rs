@@ -0,0 +1,126 @@
+use compute_particle::{
+    Particle,
+    boids::{Flock, FlockSettings, MAX_GRID_CELLS, cpu_step, spawn_flock},
+};
+use glam::Vec2;
+use gpu::{ContextOptions, Defines, GpuContext, HotShader, shader_source};
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+#[test]
+fn grid_covers_the_world_and_fits_the_buffer() {
+    let settings = FlockSettings::default();
+    let bounds = Vec2::new(16.0 / 9.0, 1.0);
+    let params = settings.to_gpu(bounds, 100_000, DELTA_TIME);
+    assert!(params.cell_size >= settings.radius);
+    assert!(params.cell_count() <= MAX_GRID_CELLS);
+    assert!(
+        (params.grid_size.as_vec2() * params.cell_size)
+            .cmpge(bounds * 2.0)
+            .all()
+    );
+
+    // 世界太大时放大格子，而不是超出缓冲区
+    let huge = settings.to_gpu(Vec2::new(20.0, 10.0), 10, DELTA_TIME);
+    assert!(huge.cell_size > settings.radius);
+    assert!(huge.cell_count() <= MAX_GRID_CELLS);
+}
+
+#[test]
+fn cpu_step_keeps_boids_in_bounds_and_speed_limits() {
+    let settings = FlockSettings::default();
+    let bounds = Vec2::new(0.3, 0.2);
+    let params = settings.to_gpu(bounds, 400, DELTA_TIME);
+    let mut boids = spawn_flock(400, 3, bounds);
+    assert_eq!(boids, spawn_flock(400, 3, bounds));
+    for _ in 0..30 {
+        boids = cpu_step(&params, &boids);
+    }
+    for boid in &boids {
+        assert!(boid.pos.abs().cmple(bounds).all());
+        let speed = boid.vel.length();
+        assert!(speed >= settings.min_speed * 0.999 && speed <= settings.max_speed * 1.001);
+    }
+}
+
+fn read_boids(device: &wgpu::Device, queue: &wgpu::Queue, flock: &Flock) -> Vec<Particle> {
+    let buffer = flock.boids().latest().buffer();
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+    staging
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+    bytemuck::cast_slice(&staging.slice(..).get_mapped_range()).to_vec()
+}
+
+#[test]
+fn gpu_flock_is_deterministic_and_matches_the_cpu_reference() {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let shader = HotShader::with_defines(
+        device,
+        shader_source!(
+            "src/wgsls/boids.wgsl",
+            includes: ["src/wgsls/particle.wgsl", "src/wgsls/grid.wgsl"],
+        ),
+        Defines::new().set("WORKGROUP_SIZE", 64),
+    )
+    .unwrap();
+    // 小范围里挤 512 只鸟，每只都有不少邻居
+    let bounds = Vec2::new(0.3, 0.2);
+    let start = spawn_flock(512, 11, bounds);
+    let settings = FlockSettings::default();
+    let steps = 10;
+
+    let run = || {
+        let mut flock = Flock::new(device, &start, settings, shader.module(), 64, None).unwrap();
+        for _ in 0..steps {
+            flock.update(queue, bounds, DELTA_TIME);
+            let mut encoder = device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                flock.record(&mut pass);
+            }
+            queue.submit(Some(encoder.finish()));
+        }
+        read_boids(device, queue, &flock)
+    };
+    let first = run();
+    let second = run();
+    assert!(
+        bytemuck::cast_slice::<_, u8>(&first) == bytemuck::cast_slice::<_, u8>(&second),
+        "同样的输入两次结果不同"
+    );
+
+    let params = settings.to_gpu(bounds, start.len() as u32, DELTA_TIME);
+    let mut expected = start.clone();
+    for _ in 0..steps {
+        expected = cpu_step(&params, &expected);
+    }
+    // GPU 的除法和开方允许有几个 ULP 的误差，只比较到 1e-4
+    for (i, (gpu, cpu)) in first.iter().zip(&expected).enumerate() {
+        assert!(
+            gpu.pos.abs_diff_eq(cpu.pos, 1e-4) && gpu.vel.abs_diff_eq(cpu.vel, 1e-4),
+            "第 {i} 只鸟：GPU {:?} {:?}，CPU {:?} {:?}",
+            gpu.pos,
+            gpu.vel,
+            cpu.pos,
+            cpu.vel
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::f32::consts::FRAC_PI_2;
+
+use compute_particle::emitter::{Direction, Emitter, EmitterShape, Emitters};
+use glam::{Vec2, Vec4};
+
+#[test]
+fn emitters_share_the_spawn_budget_by_rate() {
+    let mut emitters = Emitters::new();
+    emitters
+        .push(Emitter::new(EmitterShape::Point, Vec2::ZERO).with_rate(60.0))
+        .unwrap();
+    emitters
+        .push(
+            Emitter::new(
+                EmitterShape::Line {
+                    end: Vec2::new(1.0, 0.0),
+                },
+                Vec2::new(-0.5, 0.5),
+            )
+            .with_rate(30.0),
+        )
+        .unwrap();
+
+    let mut totals = [0, 0];
+    for _ in 0..60 {
+        let frame = emitters.prepare(1.0 / 60.0);
+        assert_eq!(frame.len(), 2);
+        totals[0] += frame[0].spawn_count;
+        totals[1] += frame[1].spawn_count;
+    }
+    assert!((59..=60).contains(&totals[0]), "{totals:?}");
+    assert!((29..=30).contains(&totals[1]), "{totals:?}");
+
+    // 修改速率下一帧生效
+    emitters.get_mut(1).unwrap().rate = 0.0;
+    let frame = emitters.prepare(1.0);
+    assert_eq!(frame[1].spawn_count, 0);
+    assert_eq!(emitters.total_rate(), 60.0);
+}
+
+#[test]
+fn emitter_parameters_are_packed_for_the_shader() {
+    let emitter = Emitter::new(
+        EmitterShape::Circle {
+            radius: 0.3,
+            inner_radius: 0.2,
+        },
+        Vec2::new(0.1, 0.2),
+    )
+    .with_direction(Direction::Outward { spread: 0.5 })
+    .with_speed(0.4, 0.8)
+    .with_lifetime(1.0, 3.0)
+    .with_colors(Vec4::X, Vec4::Y)
+    .with_rate(50.0);
+    assert_eq!(emitter.steady_count(), 100.0);
+
+    let gpu = emitter.to_gpu(7);
+    assert_eq!(size_of_val(&gpu), 96);
+    assert_eq!(gpu.shape, Vec2::new(0.3, 0.2));
+    assert_eq!(gpu.direction, Vec2::new(0.0, 0.5));
+    assert_eq!(
+        (gpu.speed, gpu.lifetime),
+        (Vec2::new(0.4, 0.8), Vec2::new(1.0, 3.0))
+    );
+    assert_eq!((gpu.color_start, gpu.color_end), (Vec4::X, Vec4::Y));
+    assert_eq!(gpu.spawn_count, 7);
+
+    let cone = Emitter::new(
+        EmitterShape::Cone {
+            angle: 1.0,
+            spread: 0.2,
+        },
+        Vec2::ZERO,
+    )
+    .to_gpu(0);
+    assert_ne!(cone.shape_kind, gpu.shape_kind);
+    assert_eq!(cone.shape, Vec2::new(1.0, 0.2));
+}
+
+#[test]
+fn emitted_particles_start_inside_their_shape() {
+    let position = Vec2::new(0.1, -0.2);
+    let emit = |shape| {
+        let gpu = Emitter::new(shape, position).with_speed(0.5, 1.0).to_gpu(0);
+        (0..500).map(move |seed| gpu.emit(seed))
+    };
+
+    for particle in emit(EmitterShape::Circle {
+        radius: 0.3,
+        inner_radius: 0.2,
+    }) {
+        let distance = (particle.pos - position).length();
+        assert!((0.2 - 1e-5..=0.3 + 1e-5).contains(&distance), "{distance}");
+    }
+
+    let half_size = Vec2::new(0.4, 0.1);
+    for particle in emit(EmitterShape::Box { half_size }) {
+        assert!((particle.pos - position).abs().cmple(half_size).all());
+    }
+
+    // 锥形的速度方向在 angle 两侧各 spread / 2 以内，速度大小在范围里
+    for particle in emit(EmitterShape::Cone {
+        angle: FRAC_PI_2,
+        spread: 0.4,
+    }) {
+        let angle = particle.vel.y.atan2(particle.vel.x);
+        assert!((angle - FRAC_PI_2).abs() <= 0.2 + 1e-5, "{angle}");
+        assert!((0.5 - 1e-5..=1.0 + 1e-5).contains(&particle.vel.length()));
+    }
+
+    // 长度为 0 的线段和大小为 0 的矩形退化成一个点，不能出现 NaN
+    for shape in [
+        EmitterShape::Line { end: Vec2::ZERO },
+        EmitterShape::Box {
+            half_size: Vec2::ZERO,
+        },
+    ] {
+        let outward = Emitter::new(shape, position)
+            .with_direction(Direction::Outward { spread: 0.1 })
+            .to_gpu(0);
+        for seed in 0..100 {
+            let particle = outward.emit(seed);
+            assert_eq!(particle.pos, position);
+            assert!(particle.vel.is_finite() && particle.vel.length() > 0.0);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use compute_particle::forces::{Falloff, ForceField, ForceFields, ForceKind};
+use glam::Vec2;
+
+#[test]
+fn force_fields_are_packed_for_the_shader() {
+    let gravity = ForceField::gravity(Vec2::new(0.0, -2.0), 0.5).to_gpu();
+    assert_eq!(size_of_val(&gravity), 32);
+    // 方向归一化，大小由强度决定
+    assert_eq!(gravity.vector, Vec2::NEG_Y);
+    assert_eq!(gravity.strength, 0.5);
+    assert_eq!(gravity.radius, 0.0);
+
+    let drag = ForceField::drag(0.3, 0.1).to_gpu();
+    assert_eq!(drag.vector, Vec2::new(0.3, 0.1));
+
+    let repulsor = ForceField::repulsor(Vec2::new(0.2, 0.4), 2.0)
+        .with_falloff(Falloff::InverseSquare, 0.5)
+        .to_gpu();
+    let attractor = ForceField::attractor(Vec2::new(0.2, 0.4), 2.0)
+        .with_falloff(Falloff::Linear, 0.5)
+        .to_gpu();
+    assert_eq!(repulsor.kind, attractor.kind);
+    assert_eq!(repulsor.strength, -attractor.strength);
+    assert_eq!(repulsor.position, Vec2::new(0.2, 0.4));
+    assert_ne!(repulsor.falloff, attractor.falloff);
+
+    let noise = ForceField::curl_noise(3.0, 0.5, 0.2);
+    assert_eq!(
+        noise.kind,
+        ForceKind::CurlNoise {
+            scale: 3.0,
+            speed: 0.5
+        }
+    );
+    assert_eq!(noise.to_gpu().vector, Vec2::new(3.0, 0.5));
+}
+
+#[test]
+fn disabled_fields_are_skipped() {
+    let mut forces = ForceFields::new();
+    forces.push(ForceField::gravity(Vec2::NEG_Y, 1.0)).unwrap();
+    let mouse = forces
+        .push(ForceField::attractor(Vec2::ZERO, 3.0).with_enabled(false))
+        .unwrap();
+    forces.push(ForceField::vortex(Vec2::ZERO, 1.0)).unwrap();
+    assert_eq!(forces.to_gpu().len(), 2);
+
+    let field = forces.get_mut(mouse).unwrap();
+    field.enabled = true;
+    field.position = Vec2::new(0.5, 0.5);
+    let gpu = forces.to_gpu();
+    assert_eq!(gpu.len(), 3);
+    assert_eq!(gpu[1].position, Vec2::new(0.5, 0.5));
+}
+
+#[test]
+fn falloff_and_singular_points_are_handled() {
+    let vel = Vec2::new(0.1, 0.0);
+    let dt = 0.5;
+
+    // 粒子正好在吸引子和漩涡的中心时方向没有定义，速度保持不变
+    for field in [
+        ForceField::attractor(Vec2::ONE, 2.0),
+        ForceField::vortex(Vec2::ONE, 2.0),
+    ] {
+        assert_eq!(field.to_gpu().apply(Vec2::ONE, vel, 0.0, dt), vel);
+    }
+
+    // 衰减半径以外：Constant 和 Linear 都是 0，InverseSquare 还剩一点
+    let far = Vec2::new(2.0, 0.0);
+    let attractor = |falloff| ForceField::attractor(Vec2::ZERO, 1.0).with_falloff(falloff, 1.0);
+    assert_eq!(
+        attractor(Falloff::Constant)
+            .to_gpu()
+            .apply(far, vel, 0.0, dt),
+        vel
+    );
+    assert_eq!(
+        attractor(Falloff::Linear).to_gpu().apply(far, vel, 0.0, dt),
+        vel
+    );
+    let inverse = attractor(Falloff::InverseSquare)
+        .to_gpu()
+        .apply(far, vel, 0.0, dt);
+    assert!(
+        (inverse.x - (0.1 - 1.0 / 5.0 * dt)).abs() < 1e-6,
+        "{inverse}"
+    );
+    // 半径的一半处 Linear 是一半的强度
+    let half = attractor(Falloff::Linear)
+        .to_gpu()
+        .apply(Vec2::new(0.5, 0.0), Vec2::ZERO, 0.0, dt);
+    assert!((half.x + 0.5 * dt).abs() < 1e-6, "{half}");
+
+    // 负的阻力系数不会让粒子加速
+    let drag = ForceField::drag(-1.0, 0.0).to_gpu();
+    assert_eq!(drag.apply(Vec2::ZERO, vel, 0.0, dt), vel);
+
+    // 方向为 0 的重力没有作用
+    let gravity = ForceField::gravity(Vec2::ZERO, 9.8).to_gpu();
+    assert_eq!(gravity.vector, Vec2::ZERO);
+    assert_eq!(gravity.apply(Vec2::ZERO, vel, 0.0, dt), vel);
+}
@@ -0,0 +1,60 @@
+use compute_particle::grid::{CellList, GridParams, MAX_GRID_CELLS};
+use glam::{IVec2, UVec2, Vec2};
+
+#[test]
+fn positions_outside_the_bounds_fall_into_edge_cells() {
+    let params = GridParams::fit(Vec2::new(1.0, 0.5), 0.1, 0);
+    let last = params.size.as_ivec2() - 1;
+    assert_eq!(params.cell_coord(Vec2::new(-1.0, -0.5)), IVec2::ZERO);
+    // 正好在右上边界上时 floor 之后是 size，要夹回最后一格
+    assert_eq!(params.cell_coord(Vec2::new(1.0, 0.5)), last);
+    assert_eq!(
+        params.cell_coord(Vec2::new(-5.0, 5.0)),
+        IVec2::new(0, last.y)
+    );
+    assert_eq!(
+        params.cell_coord(Vec2::new(f32::MAX, f32::MIN)),
+        IVec2::new(last.x, 0)
+    );
+
+    // 世界缩成一个点、半径为 0 时也至少有一个格子
+    let degenerate = GridParams::fit(Vec2::ZERO, 0.0, 0);
+    assert_eq!(degenerate.size, UVec2::ONE);
+    assert!(degenerate.cell_size > 0.0);
+    assert!(GridParams::fit(Vec2::splat(100.0), 1e-3, 0).cell_count() <= MAX_GRID_CELLS);
+}
+
+#[test]
+fn neighbors_include_everything_within_the_radius() {
+    let bounds = Vec2::new(1.0, 0.5);
+    let radius = 0.15;
+    let params = GridParams::fit(bounds, radius, 0);
+    // 铺满整个区域，边角和越界的点也在里面
+    let mut positions = Vec::new();
+    for y in -11..=11 {
+        for x in -21..=21 {
+            positions.push(Vec2::new(x as f32 * 0.05, y as f32 * 0.05));
+        }
+    }
+    let cells = CellList::new(params, positions.iter().copied());
+
+    for (i, &pos) in positions.iter().enumerate() {
+        let found: Vec<usize> = cells.neighbors(pos).collect();
+        assert!(found.contains(&i), "第 {i} 个点找不到自己");
+        // 越界的点被夹到边上的格子里，只检查边界以内的
+        if !pos.abs().cmple(bounds).all() {
+            continue;
+        }
+        for (j, &other) in positions.iter().enumerate() {
+            // 距离正好等于半径时舍入误差可能让两个点隔开两个格子，只检查严格在半径以内的
+            if other.abs().cmple(bounds).all() && pos.distance(other) < radius - 1e-4 {
+                assert!(found.contains(&j), "{pos} 漏掉了 {other}");
+            }
+        }
+        // 每个粒子只属于一个格子，不会重复出现
+        let mut unique = found.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), found.len(), "{pos} 的邻居有重复");
+    }
+}
@@ -0,0 +1,40 @@
+use compute_particle::lifecycle::SpawnBudget;
+
+#[test]
+fn spawn_budget_carries_fractions_between_frames() {
+    let mut budget = SpawnBudget::new(100.0);
+    // 每帧 1.6 个：有的帧 1 个，有的帧 2 个，累计起来不丢
+    let frames: Vec<u32> = (0..50).map(|_| budget.take(0.016)).collect();
+    assert!(
+        frames.iter().all(|&count| count == 1 || count == 2),
+        "{frames:?}"
+    );
+    assert!((79..=80).contains(&frames.iter().sum::<u32>()));
+
+    budget.set_rate(-5.0);
+    assert_eq!(budget.rate(), 0.0);
+    assert_eq!(budget.take(1.0), 0);
+}
+
+#[test]
+fn steady_rate_replaces_particles_as_they_expire() {
+    let mean_lifetime = 3.0;
+    let mut budget = SpawnBudget::steady(1024, mean_lifetime);
+    // 一个平均寿命内重生的数量正好是粒子总数
+    let frames = (mean_lifetime * 60.0) as usize;
+    let spawned: u32 = (0..frames).map(|_| budget.take(1.0 / 60.0)).sum();
+    assert!((1022..=1024).contains(&spawned), "{spawned}");
+}
+
+#[test]
+fn degenerate_lifetimes_and_frame_times() {
+    // 寿命为 0 时不能除以 0 变成无穷大的速率
+    let budget = SpawnBudget::steady(1024, 0.0);
+    assert!(budget.rate().is_finite());
+
+    let mut budget = SpawnBudget::new(30.0);
+    // 暂停时每帧的时间是 0
+    assert_eq!(budget.take(0.0), 0);
+    // 一帧卡了很久时一次补上，不会丢
+    assert_eq!(budget.take(2.0), 60);
+}
@@ -0,0 +1,147 @@
+use compute_particle::{
+    Particle,
+    nbody::{
+        Energy, NBody, NBodyParams, NBodySettings, cpu_energy, cpu_step, interact, shader_defines,
+        spawn_disk,
+    },
+};
+use glam::{Vec2, Vec4};
+use gpu::{ContextOptions, GpuContext, HotShader, shader_source};
+
+#[test]
+fn two_body_energy_matches_the_softened_potential() {
+    let params = NBodyParams {
+        count: 2,
+        delta_time: 0.01,
+        gravity: 0.5,
+        softening: 0.1,
+    };
+    let bodies = [
+        Particle::new(Vec2::ZERO, Vec2::new(0.0, 1.0), Vec4::ONE, 1.0).with_mass(2.0),
+        Particle::new(Vec2::new(0.3, 0.4), Vec2::ZERO, Vec4::ONE, 1.0).with_mass(3.0),
+    ];
+    let distance = (0.5f32 * 0.5 + 0.1 * 0.1).sqrt();
+    let energy = cpu_energy(&params, &bodies);
+    assert!((energy.kinetic - 1.0).abs() < 1e-6);
+    assert!((energy.potential + 0.5 * 2.0 * 3.0 / distance).abs() < 1e-5);
+
+    // 作用力大小相等方向相反
+    let (a, _) = interact(&params, &bodies, 0);
+    let (b, _) = interact(&params, &bodies, 1);
+    assert!((a * 2.0 + b * 3.0).length() < 1e-5);
+    assert!(a.normalize().abs_diff_eq(Vec2::new(0.6, 0.8), 1e-5));
+}
+
+fn accelerations(params: &NBodyParams, bodies: &[Particle]) -> Vec<Vec2> {
+    (0..bodies.len())
+        .map(|i| interact(params, bodies, i).0)
+        .collect()
+}
+
+#[test]
+fn cpu_leapfrog_conserves_energy() {
+    let settings = NBodySettings::default();
+    let mut bodies = spawn_disk(200, 5, &settings);
+    assert_eq!(bodies, spawn_disk(200, 5, &settings));
+    let params = settings.to_gpu(bodies.len() as u32);
+    let mut acc = accelerations(&params, &bodies);
+    let initial = cpu_energy(&params, &bodies);
+    assert!(initial.total() < 0.0, "圆盘应该是束缚的：{initial:?}");
+    for _ in 0..400 {
+        cpu_step(&params, &mut bodies, &mut acc);
+    }
+    let drift = cpu_energy(&params, &bodies).drift(&initial);
+    assert!(drift.abs() < 1e-3, "能量漂移 {drift}");
+}
+
+fn read<T: bytemuck::Pod>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &wgpu::Buffer,
+) -> Vec<T> {
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+    staging
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+    bytemuck::cast_slice(&staging.slice(..).get_mapped_range()).to_vec()
+}
+
+#[test]
+fn gpu_nbody_matches_the_cpu_reference() {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let shader = HotShader::with_defines(
+        device,
+        shader_source!("src/wgsls/nbody.wgsl", includes: ["src/wgsls/particle.wgsl"]),
+        shader_defines(64, 64),
+    )
+    .unwrap();
+    // 不是工作组大小的整数倍，最后一块有空位
+    let settings = NBodySettings::default();
+    let start = spawn_disk(300, 9, &settings);
+    let frames = 5;
+
+    let nbody = NBody::new(device, queue, &start, settings, shader.module(), 64, None).unwrap();
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&Default::default());
+        for _ in 0..frames {
+            nbody.record(&mut pass);
+        }
+        nbody.record_energy(&mut pass);
+    }
+    queue.submit(Some(encoder.finish()));
+    let bodies = read::<Particle>(device, queue, nbody.bodies().buffer());
+    let energy = read::<Energy>(device, queue, nbody.energy_buffer())[0];
+
+    let params = settings.to_gpu(start.len() as u32);
+    let mut expected = start.clone();
+    let mut acc = accelerations(&params, &expected);
+    for _ in 0..frames * settings.substeps {
+        cpu_step(&params, &mut expected, &mut acc);
+    }
+    for (i, (gpu, cpu)) in bodies.iter().zip(&expected).enumerate() {
+        assert!(
+            gpu.pos.abs_diff_eq(cpu.pos, 1e-4) && gpu.vel.abs_diff_eq(cpu.vel, 1e-3),
+            "第 {i} 个粒子：GPU {:?} {:?}，CPU {:?} {:?}",
+            gpu.pos,
+            gpu.vel,
+            cpu.pos,
+            cpu.vel
+        );
+    }
+    // 求和的顺序不同，只比较相对误差
+    let reference = cpu_energy(&params, &expected);
+    assert!(
+        energy.drift(&reference).abs() < 1e-4,
+        "GPU {energy:?}，CPU {reference:?}"
+    );
+}
+
+#[test]
+#[should_panic(expected = "必须等于工作组大小")]
+fn tile_size_must_match_the_workgroup_size() {
+    shader_defines(64, 32);
+}
+
+#[test]
+#[should_panic(expected = "不是 2 的幂")]
+fn workgroup_size_must_be_a_power_of_two() {
+    shader_defines(48, 48);
+}
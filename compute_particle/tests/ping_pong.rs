@@ -0,0 +1,34 @@
+use compute_particle::ping_pong::PingPong;
+use gpu::{ContextOptions, GpuContext};
+
+#[test]
+fn pair_reads_the_latest_buffer_and_writes_the_other() {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let mut buffers = PingPong::<u32>::from_slice(
+        &gpu.device,
+        "Test Buffer",
+        &[1, 2, 3],
+        wgpu::BufferUsages::empty(),
+    )
+    .unwrap();
+    assert_eq!(buffers.len(), 3);
+    let pairs =
+        buffers.pair(|source, destination| (source.buffer().clone(), destination.buffer().clone()));
+    assert_ne!(pairs[0].0, pairs[0].1);
+    assert_eq!((&pairs[1].0, &pairs[1].1), (&pairs[0].1, &pairs[0].0));
+
+    // 每一步从最新的读，写进另一个，交换之后写入的那个变成最新的
+    for _ in 0..3 {
+        let (source, destination) = &pairs[buffers.latest_index()];
+        assert_eq!(buffers.latest().buffer(), source);
+        buffers.swap();
+        assert_eq!(buffers.latest().buffer(), destination);
+    }
+}
@@ -0,0 +1,56 @@
+use compute_particle::render_params::{
+    ParticleSize, RenderSettings, SizeCurve, pixel_to_world, world_bounds,
+};
+use glam::{Vec2, Vec4};
+
+#[test]
+fn size_curve_interpolates_between_control_points() {
+    let curve = SizeCurve([1.0, 0.4, 0.1, 0.0]);
+    assert_eq!(curve.sample(0.0), 1.0);
+    assert!((curve.sample(1.0 / 6.0) - 0.7).abs() < 1e-6);
+    assert!((curve.sample(2.0 / 3.0) - 0.1).abs() < 1e-6);
+    assert_eq!(curve.sample(1.0), 0.0);
+    // 超出范围时取两端
+    assert_eq!(curve.sample(-1.0), 1.0);
+    assert_eq!(curve.sample(2.0), 0.0);
+    assert_eq!(SizeCurve::CONSTANT.sample(0.5), 1.0);
+}
+
+#[test]
+fn render_params_follow_the_window_size() {
+    let settings = RenderSettings {
+        size: ParticleSize::World(0.02),
+        size_over_life: None,
+        ..Default::default()
+    };
+    let params = settings.to_gpu(1600, 800);
+    assert_eq!(size_of_val(&params), 48);
+    assert_eq!(params.viewport, Vec2::new(1600.0, 800.0));
+    assert_eq!(params.aspect, 2.0);
+    assert_eq!(params.size, 0.02);
+    assert_eq!(params.size_curve, Vec4::ONE);
+
+    let pixels = RenderSettings {
+        size: ParticleSize::Pixels(6.0),
+        ..settings
+    }
+    .to_gpu(0, 0);
+    // 最小化时不会除以 0
+    assert_eq!(pixels.aspect, 1.0);
+    assert_eq!(pixels.viewport, Vec2::ONE);
+    assert_ne!(pixels.size_unit, params.size_unit);
+}
+
+#[test]
+fn world_coordinates_span_the_window_aspect() {
+    assert_eq!(world_bounds(1920, 1080), Vec2::new(1920.0 / 1080.0, 1.0));
+    assert_eq!(
+        pixel_to_world(Vec2::new(0.0, 0.0), 200, 100),
+        Vec2::new(-2.0, 1.0)
+    );
+    assert_eq!(pixel_to_world(Vec2::new(100.0, 50.0), 200, 100), Vec2::ZERO);
+    assert_eq!(
+        pixel_to_world(Vec2::new(200.0, 100.0), 200, 100),
+        Vec2::new(2.0, -1.0)
+    );
+}
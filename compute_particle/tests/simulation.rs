@@ -0,0 +1,168 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use compute_particle::{
+    emitter::{Direction, Emitter, EmitterShape, Emitters},
+    forces::{Falloff, ForceField, ForceFields},
+    simulation::{CpuSimulation, ParticleSimulation, SimConfig},
+    wgsl::pcg_hash,
+};
+use glam::{Vec2, Vec4};
+use gpu::{ContextOptions, GpuContext};
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+#[test]
+fn pcg_hash_matches_the_shader_constants() {
+    assert_eq!(pcg_hash(0), 0.030199997);
+    assert_eq!(pcg_hash(1), 0.6591631);
+    assert_eq!(pcg_hash(42), 0.28497618);
+    assert_eq!(pcg_hash(0xdeadbeef), 0.4029785);
+}
+
+/// 每种形状和方向分布各一个发射器，加上所有种类的力场
+fn scene() -> (Emitters, ForceFields) {
+    let mut emitters = Emitters::new();
+    for emitter in [
+        Emitter::new(
+            EmitterShape::Cone {
+                angle: FRAC_PI_2,
+                spread: 0.4,
+            },
+            Vec2::new(0.0, -0.9),
+        )
+        .with_speed(0.9, 1.3)
+        .with_lifetime(0.5, 1.0),
+        Emitter::new(
+            EmitterShape::Circle {
+                radius: 0.25,
+                inner_radius: 0.2,
+            },
+            Vec2::ZERO,
+        )
+        .with_direction(Direction::Outward { spread: 0.3 }),
+        Emitter::new(
+            EmitterShape::Line {
+                end: Vec2::new(1.8, 0.0),
+            },
+            Vec2::new(-0.9, 0.9),
+        )
+        .with_direction(Direction::Angle {
+            angle: -FRAC_PI_2,
+            spread: 0.1,
+        }),
+        Emitter::new(
+            EmitterShape::Box {
+                half_size: Vec2::new(0.1, 0.2),
+            },
+            Vec2::new(-0.6, 0.0),
+        ),
+        Emitter::new(EmitterShape::Point, Vec2::new(0.6, 0.0)).with_direction(Direction::Angle {
+            angle: PI,
+            spread: PI,
+        }),
+    ] {
+        emitters
+            .push(
+                emitter
+                    .with_rate(300.0)
+                    .with_colors(Vec4::new(1.0, 0.5, 0.0, 1.0), Vec4::new(0.0, 0.5, 1.0, 0.5)),
+            )
+            .unwrap();
+    }
+
+    let mut forces = ForceFields::new();
+    forces.push(ForceField::gravity(Vec2::NEG_Y, 0.4)).unwrap();
+    forces.push(ForceField::drag(0.3, 0.2)).unwrap();
+    forces
+        .push(ForceField::vortex(Vec2::ZERO, 0.8).with_falloff(Falloff::Linear, 0.5))
+        .unwrap();
+    forces
+        .push(
+            ForceField::attractor(Vec2::new(0.3, 0.3), 1.0)
+                .with_falloff(Falloff::InverseSquare, 0.3),
+        )
+        .unwrap();
+    forces
+        .push(ForceField::repulsor(Vec2::new(-0.3, -0.3), 0.5).with_falloff(Falloff::Constant, 0.4))
+        .unwrap();
+    forces.push(ForceField::curl_noise(3.0, 0.2, 0.15)).unwrap();
+    (emitters, forces)
+}
+
+#[test]
+fn cpu_reference_respawns_dead_particles_in_index_order() {
+    let (mut emitters, forces) = scene();
+    // 五个发射器每帧一共 25 个名额，远少于死亡的粒子
+    let mut simulation = CpuSimulation::new(&SimConfig::new(256, 3));
+    simulation.step(&mut emitters, &forces, DELTA_TIME);
+    let counters = simulation.counters();
+    assert_eq!(counters.dead, 256);
+    assert_eq!(counters.live, 25);
+    let alive: Vec<bool> = simulation
+        .particles()
+        .iter()
+        .map(|particle| particle.life > 0.0)
+        .collect();
+    assert!(alive[..25].iter().all(|&alive| alive));
+    assert!(alive[25..].iter().all(|&alive| !alive));
+    // 每个发射器 5 个名额，第一个发射器的粒子从底部的锥形喷出
+    assert!(
+        simulation.particles()[..5]
+            .iter()
+            .all(|particle| particle.pos.y < -0.8 && particle.vel.y > 0.0)
+    );
+}
+
+#[test]
+fn gpu_simulation_is_deterministic_and_matches_the_cpu_reference() {
+    let options = ContextOptions {
+        force_fallback_adapter: true,
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的软件适配器，跳过");
+        return;
+    };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let config = SimConfig::new(1024, 7).with_bounds(Vec2::new(1.0, 0.75));
+    let (emitters, forces) = scene();
+    let frames = 90;
+
+    let run = || {
+        let mut emitters = emitters.clone();
+        let mut simulation = ParticleSimulation::headless(device, queue, &config).unwrap();
+        for _ in 0..frames {
+            simulation.step(device, queue, &mut emitters, &forces, DELTA_TIME);
+        }
+        (
+            simulation.read_particles(device, queue).unwrap(),
+            simulation.read_counters(device, queue).unwrap(),
+        )
+    };
+    let (first, counters) = run();
+    let (second, _) = run();
+    assert!(
+        bytemuck::cast_slice::<_, u8>(&first) == bytemuck::cast_slice::<_, u8>(&second),
+        "同样的输入两次结果不同"
+    );
+
+    let mut reference = CpuSimulation::new(&config);
+    let mut cpu_emitters = emitters.clone();
+    for _ in 0..frames {
+        reference.step(&mut cpu_emitters, &forces, DELTA_TIME);
+    }
+    assert_eq!(counters, reference.counters());
+    // 90 帧之后有粒子已经死过一轮又重生
+    assert!(counters.live > 0 && counters.dead > 0);
+    for (i, (gpu, cpu)) in first.iter().zip(reference.particles()).enumerate() {
+        assert!(
+            gpu.pos.abs_diff_eq(cpu.pos, 1e-3)
+                && gpu.vel.abs_diff_eq(cpu.vel, 1e-3)
+                && (gpu.life - cpu.life).abs() < 1e-4
+                && (gpu.max_life - cpu.max_life).abs() < 1e-5
+                && gpu.color.abs_diff_eq(cpu.color, 1e-5),
+            "第 {i} 个粒子：GPU {gpu:?}，CPU {cpu:?}"
+        );
+    }
+}
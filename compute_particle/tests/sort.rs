@@ -0,0 +1,145 @@
+use compute_particle::{
+    Particle,
+    ping_pong::PingPong,
+    render_params::BlendMode,
+    sort::{ParticleSorter, SortStage, bitonic_stages, sort_key, sort_len},
+};
+use glam::{Vec2, Vec4};
+use gpu::{ContextOptions, Defines, GpuContext, HotShader, shader_source};
+
+/// 确定的伪随机粒子：一部分已经死亡，其余的年龄各不相同
+fn particles(count: u32) -> Vec<Particle> {
+    let mut state = 12345u32;
+    let mut random = || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        (state >> 8) as f32 / (1 << 24) as f32
+    };
+    (0..count)
+        .map(|_| {
+            let mut particle = Particle::new(Vec2::ZERO, Vec2::ZERO, Vec4::ONE, 1.0 + random());
+            particle.life = random() * 1.2 - 0.2;
+            particle
+        })
+        .collect()
+}
+
+/// 在 CPU 上按同样的步骤执行双调排序网络
+fn run_network(keys: &mut [f32], stages: &[SortStage]) {
+    for stage in stages {
+        for i in 0..keys.len() {
+            let partner = i ^ stage.j as usize;
+            if partner <= i {
+                continue;
+            }
+            let ascending = i & stage.k as usize == 0;
+            if (keys[i] > keys[partner]) == ascending {
+                keys.swap(i, partner);
+            }
+        }
+    }
+}
+
+#[test]
+fn bitonic_network_sorts_padded_keys() {
+    assert_eq!(sort_len(1000), 1024);
+    assert_eq!(sort_len(0), 1);
+    let stages = bitonic_stages(1024);
+    // log2(n) * (log2(n) + 1) / 2 步
+    assert_eq!(stages.len(), 55);
+    assert_eq!(stages[0], SortStage { k: 2, j: 1 });
+    assert_eq!(stages.last(), Some(&SortStage { k: 1024, j: 1 }));
+
+    let mut keys: Vec<f32> = particles(1000).iter().map(sort_key).collect();
+    keys.resize(1024, f32::MAX);
+    let mut expected = keys.clone();
+    expected.sort_by(f32::total_cmp);
+    run_network(&mut keys, &stages);
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn only_order_dependent_blend_modes_are_sorted() {
+    assert!(BlendMode::Alpha.needs_sorting());
+    assert!(BlendMode::Premultiplied.needs_sorting());
+    assert!(!BlendMode::Additive.needs_sorting());
+    assert_eq!(
+        BlendMode::Additive.blend_state().color.dst_factor,
+        wgpu::BlendFactor::One
+    );
+
+    let mut mode = BlendMode::default();
+    for expected in [
+        BlendMode::Premultiplied,
+        BlendMode::Additive,
+        BlendMode::Alpha,
+    ] {
+        mode = mode.next();
+        assert_eq!(mode, expected);
+    }
+}
+
+#[test]
+fn gpu_sort_writes_oldest_first_index_buffer() {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let data = particles(1000);
+    let particles = PingPong::from_slice(
+        device,
+        "Particle Buffer",
+        &data,
+        wgpu::BufferUsages::empty(),
+    )
+    .unwrap();
+    let shader = HotShader::with_defines(
+        device,
+        shader_source!("src/wgsls/sort.wgsl", includes: ["src/wgsls/particle.wgsl"]),
+        Defines::new().set("WORKGROUP_SIZE", 64),
+    )
+    .unwrap();
+    let sorter = ParticleSorter::new(device, queue, &particles, shader.module(), 64, None).unwrap();
+
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: sorter.index_buffer().size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&Default::default());
+        sorter.record(&mut pass, &particles);
+    }
+    encoder.copy_buffer_to_buffer(sorter.index_buffer(), 0, &readback, 0, readback.size());
+    queue.submit(Some(encoder.finish()));
+    readback
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+    let indices: Vec<u32> = bytemuck::cast_slice(&readback.slice(..).get_mapped_range()).to_vec();
+
+    assert_eq!(indices.len() as u32, sorter.index_count());
+    let order: Vec<usize> = indices
+        .chunks(6)
+        .map(|quad| {
+            let first = quad[0];
+            assert_eq!(first % 6, 0);
+            assert_eq!(quad, (first..first + 6).collect::<Vec<_>>());
+            (first / 6) as usize
+        })
+        .collect();
+    let mut seen = order.clone();
+    seen.sort();
+    assert_eq!(seen, (0..data.len()).collect::<Vec<_>>());
+    assert!(
+        order
+            .windows(2)
+            .all(|pair| sort_key(&data[pair[0]]) <= sort_key(&data[pair[1]]))
+    );
+}
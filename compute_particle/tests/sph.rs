@@ -0,0 +1,138 @@
+use compute_particle::{
+    Particle,
+    sph::{Fluid, SphSettings, cpu_densities, cpu_step, spawn_block},
+};
+use glam::Vec2;
+use gpu::{ContextOptions, Defines, GpuContext, HotShader, shader_source};
+
+#[test]
+fn poly6_kernel_integrates_to_one() {
+    let settings = SphSettings::default();
+    let params = settings.to_gpu(Vec2::ONE, 1);
+    let h = settings.smoothing_radius;
+    let step = h / 200.0;
+    let mut sum = 0.0f64;
+    for y in -200..=200 {
+        for x in -200..=200 {
+            let offset = Vec2::new(x as f32, y as f32) * step;
+            sum += params.poly6(offset.length_squared()) as f64;
+        }
+    }
+    let integral = sum * (step * step) as f64;
+    assert!((integral - 1.0).abs() < 1e-3, "积分 {integral}");
+}
+
+#[test]
+fn block_starts_at_rest_density_and_stays_inside() {
+    let settings = SphSettings::default();
+    let bounds = Vec2::new(0.5, 0.5);
+    let mut particles = spawn_block(400, 3, &settings, bounds);
+    assert_eq!(particles, spawn_block(400, 3, &settings, bounds));
+    let params = settings.to_gpu(bounds, particles.len() as u32);
+
+    // 方阵中间的粒子周围是满的，密度就是静止密度
+    let densities = cpu_densities(&params, &particles);
+    let middle = 10 * 20 + 10;
+    let ratio = densities[middle] / settings.rest_density;
+    assert!(
+        (ratio - 1.0).abs() < 0.01,
+        "中间的密度是静止密度的 {ratio} 倍"
+    );
+
+    let right_edge =
+        |particles: &[Particle]| particles.iter().map(|p| p.pos.x).fold(f32::MIN, f32::max);
+    let start_edge = right_edge(&particles);
+    for _ in 0..1000 {
+        particles = cpu_step(&params, &particles);
+    }
+    // 水柱塌下来往右流
+    assert!(right_edge(&particles) > start_edge + 0.2);
+    let densities = cpu_densities(&params, &particles);
+    let max_ratio = densities.iter().copied().fold(0.0, f32::max) / settings.rest_density;
+    for particle in &particles {
+        assert!(particle.pos.is_finite() && particle.vel.is_finite());
+        assert!(particle.pos.abs().cmple(bounds).all());
+    }
+    assert!(max_ratio < 1.2, "最大密度是静止密度的 {max_ratio} 倍");
+}
+
+fn read_particles(device: &wgpu::Device, queue: &wgpu::Queue, fluid: &Fluid) -> Vec<Particle> {
+    let buffer = fluid.particles().buffer();
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+    staging
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+    bytemuck::cast_slice(&staging.slice(..).get_mapped_range()).to_vec()
+}
+
+#[test]
+fn gpu_fluid_is_deterministic_and_matches_the_cpu_reference() {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let shader = HotShader::with_defines(
+        device,
+        shader_source!(
+            "src/wgsls/sph.wgsl",
+            includes: ["src/wgsls/particle.wgsl", "src/wgsls/grid.wgsl"],
+        ),
+        Defines::new().set("WORKGROUP_SIZE", 64),
+    )
+    .unwrap();
+    let bounds = Vec2::new(0.4, 0.3);
+    let settings = SphSettings {
+        substeps: 20,
+        ..Default::default()
+    };
+    let start = spawn_block(500, 11, &settings, bounds);
+
+    let run = || {
+        let mut fluid = Fluid::new(device, &start, settings, shader.module(), 64, None).unwrap();
+        fluid.update(queue, bounds);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            fluid.record(&mut pass);
+        }
+        queue.submit(Some(encoder.finish()));
+        read_particles(device, queue, &fluid)
+    };
+    let first = run();
+    let second = run();
+    assert!(
+        bytemuck::cast_slice::<_, u8>(&first) == bytemuck::cast_slice::<_, u8>(&second),
+        "同样的输入两次结果不同"
+    );
+
+    let params = settings.to_gpu(bounds, start.len() as u32);
+    let mut expected = start.clone();
+    for _ in 0..settings.substeps {
+        expected = cpu_step(&params, &expected);
+    }
+    for (i, (gpu, cpu)) in first.iter().zip(&expected).enumerate() {
+        assert!(
+            gpu.pos.abs_diff_eq(cpu.pos, 1e-5) && gpu.vel.abs_diff_eq(cpu.vel, 1e-3),
+            "第 {i} 个粒子：GPU {:?} {:?}，CPU {:?} {:?}",
+            gpu.pos,
+            gpu.vel,
+            cpu.pos,
+            cpu.vel
+        );
+        assert!(gpu.color.abs_diff_eq(cpu.color, 1e-3));
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use copy_texture_to_texture::State;
 use winit::{
     application::ApplicationHandler,
@@ -7,20 +9,21 @@ use winit::{
 };
 
 #[derive(Default)]
-pub struct App<'window> {
-    window: Option<Window>,
-    state: Option<State<'window>>,
+pub struct App {
+    window: Option<Arc<Window>>,
+    state: Option<State>,
 }
 
-impl ApplicationHandler for App<'_> {
+impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         self.window = Some(
             event_loop
                 .create_window(Window::default_attributes())
+                .map(Arc::new)
                 .unwrap(),
         );
         if let Some(window) = &self.window {
-            self.state = Some(futures::executor::block_on(State::new(window)).unwrap());
+            self.state = Some(futures::executor::block_on(State::new(window.clone())).unwrap());
         }
     }
 
@@ -39,10 +42,10 @@ impl ApplicationHandler for App<'_> {
                     match state.render() {
                         Ok(_) => {}
                         Err(wgpu::SurfaceError::Lost) => {
-                            dbg!("丢失平面");
+                            tracing::warn!("丢失平面");
                         }
                         Err(e) => {
-                            eprintln!("{:?}", e);
+                            tracing::error!("{e:?}");
                         }
                     }
                 }
@@ -57,7 +60,7 @@ impl ApplicationHandler for App<'_> {
     }
 }
 
-impl App<'_> {
+impl App {
     pub fn run() {
         let event_loop = EventLoop::new().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
@@ -1,64 +1,28 @@
-mod vertex;
-use anyhow::anyhow;
+pub mod vertex;
+use std::sync::Arc;
+
+use gpu::{ContextOptions, GpuContext, Lesson, Tracked, TransientPool, validation};
 use vertex::{INDICES, MESH, Mesh, RECTANGLE, calc_bundle, mesh_size};
-use wgpu::{SurfaceError, util::DeviceExt};
+use wgpu::SurfaceError;
 use winit::{dpi::PhysicalSize, window::Window};
-pub struct State<'window> {
-    surface: wgpu::Surface<'window>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
+pub struct State {
+    gpu: GpuContext,
     render_pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
+    vertex_buffer: Tracked<wgpu::Buffer>,
     texture_render_pipeline: wgpu::RenderPipeline,
-    rectangle_vertex_buffer: wgpu::Buffer,
+    rectangle_vertex_buffer: Tracked<wgpu::Buffer>,
     bind_group_layout: wgpu::BindGroupLayout,
-    index_buffer: wgpu::Buffer,
+    index_buffer: Tracked<wgpu::Buffer>,
+    sampler: wgpu::Sampler,
+    /// 每帧用到的中间纹理从这里取，窗口大小不变时一直复用
+    pool: TransientPool,
 }
 
-impl State<'_> {
-    pub async fn new(window: &Window) -> anyhow::Result<Self> {
-        let (instance, _backend) = create_wgpu_instance().await?;
-        let surface = unsafe {
-            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(&window)?)
-        }?;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptionsBase {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .unwrap();
-
-        dbg!(adapter.get_info());
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: Some("设备"),
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                ..Default::default()
-            })
-            .await?;
-        let caps = surface.get_capabilities(&adapter);
-        let surface_format = caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(caps.formats[0]);
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&device, &config);
+impl State {
+    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+        let gpu = GpuContext::windowed(window, &ContextOptions::default()).await?;
+        let device = &gpu.device;
+        let config = gpu.config();
 
         let shaper = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("着色器"),
@@ -72,45 +36,48 @@ impl State<'_> {
                 immediate_size: 0,
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("渲染管线"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shaper,
-                entry_point: Some("vertex"),
-                compilation_options: Default::default(),
-                buffers: &[Mesh::desc()],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            // 深度缓冲区
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                // 多重采样
-                count: 4,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+        let render_pipeline = validation::create_render_pipeline(
+            device,
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("渲染管线"),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shaper,
+                    entry_point: Some("vertex"),
+                    compilation_options: Default::default(),
+                    buffers: &[Mesh::desc()],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                // 深度缓冲区
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    // 多重采样
+                    count: 4,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shaper,
+                    entry_point: Some("fragment"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview_mask: None,
+                cache: gpu.pipeline_cache(),
             },
-            fragment: Some(wgpu::FragmentState {
-                module: &shaper,
-                entry_point: Some("fragment"),
-                compilation_options: Default::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview_mask: None,
-            cache: None,
-        });
+        )?;
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("绑定组"),
@@ -146,18 +113,22 @@ impl State<'_> {
             source: wgpu::ShaderSource::Wgsl(include_str!("shaders/texture.wgsl").into()),
         });
         let rectangle_vertex_buffer =
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("矩形顶点缓冲"),
-                contents: bytemuck::cast_slice(RECTANGLE),
-                usage: wgpu::BufferUsages::VERTEX,
+            gpu.resources
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("矩形顶点缓冲"),
+                    contents: bytemuck::cast_slice(RECTANGLE),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+        let index_buffer = gpu
+            .resources
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("索引缓冲区"),
+                contents: bytemuck::cast_slice(INDICES),
+                usage: wgpu::BufferUsages::INDEX,
             });
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("索引缓冲区"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-        let texture_render_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let texture_render_pipeline = validation::create_render_pipeline(
+            device,
+            &wgpu::RenderPipelineDescriptor {
                 label: Some("纹理渲染管线"),
                 layout: Some(&texture_render_pipeline_layout),
                 vertex: wgpu::VertexState {
@@ -192,36 +163,41 @@ impl State<'_> {
                     })],
                 }),
                 multiview_mask: None,
-                cache: None,
-            });
+                cache: gpu.pipeline_cache(),
+            },
+        )?;
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("顶点缓冲区"),
-            contents: bytemuck::cast_slice(MESH),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let vertex_buffer = gpu
+            .resources
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("顶点缓冲区"),
+                contents: bytemuck::cast_slice(MESH),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        // 调整窗口大小时旧尺寸的纹理闲置几帧后释放
+        let pool = TransientPool::new(&gpu.resources, 3);
         Ok(Self {
-            surface,
-            device,
-            queue,
-            config,
+            gpu,
             render_pipeline,
             vertex_buffer,
             bind_group_layout,
             texture_render_pipeline,
             rectangle_vertex_buffer,
             index_buffer,
+            sampler,
+            pool,
         })
     }
 
     pub fn render(&mut self) -> Result<(), SurfaceError> {
-        // let output = self.surface.get_current_texture()?;
+        // let output = self.gpu.surface().get_current_texture()?;
         let size = wgpu::Extent3d {
-            width: self.config.width,
-            height: self.config.height,
+            width: self.gpu.config().width,
+            height: self.gpu.config().height,
             depth_or_array_layers: 1,
         };
-        let output = self.device.create_texture(&wgpu::TextureDescriptor {
+        let output = self.pool.texture(&wgpu::TextureDescriptor {
             label: Some("复制纹理"),
             size,
             mip_level_count: 1,
@@ -235,11 +211,11 @@ impl State<'_> {
         });
         let view = output.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let msaa_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+        let msaa_texture = self.pool.texture(&wgpu::TextureDescriptor {
             label: Some("多重采样抗锯齿纹理"),
             size: wgpu::Extent3d {
-                width: self.config.width,
-                height: self.config.height,
+                width: self.gpu.config().width,
+                height: self.gpu.config().height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -251,6 +227,7 @@ impl State<'_> {
         });
         let msaa_texture_view = msaa_texture.create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self
+            .gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
         {
@@ -273,7 +250,7 @@ impl State<'_> {
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.draw(0..MESH.len() as u32, 0..1);
         }
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
 
         let bundle = calc_bundle();
         let (width, height) = mesh_size(&bundle.0, &bundle.1);
@@ -282,7 +259,7 @@ impl State<'_> {
             height: height as u32 * size.height / 2,
             depth_or_array_layers: 1,
         };
-        let destination_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+        let destination_texture = self.pool.texture(&wgpu::TextureDescriptor {
             label: Some("复制纹理"),
             size,
             mip_level_count: 1,
@@ -296,6 +273,7 @@ impl State<'_> {
         });
 
         let mut encoder = self
+            .gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
@@ -304,8 +282,8 @@ impl State<'_> {
                 texture: &output,
                 mip_level: 0,
                 origin: wgpu::Origin3d {
-                    x: (bundle.1.x * self.config.width as f32) as u32 / 2,
-                    y: (bundle.1.y * self.config.height as f32) as u32 / 2,
+                    x: (bundle.1.x * self.gpu.config().width as f32) as u32 / 2,
+                    y: (bundle.1.y * self.gpu.config().height as f32) as u32 / 2,
                     z: 0,
                 },
                 aspect: wgpu::TextureAspect::All,
@@ -319,25 +297,24 @@ impl State<'_> {
             size,
         );
         let dest_view = destination_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("纹理绑定组"),
-            layout: &self.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&dest_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(
-                        &self
-                            .device
-                            .create_sampler(&wgpu::SamplerDescriptor::default()),
-                    ),
-                },
-            ],
-        });
-        let output = self.surface.get_current_texture()?;
+        let bind_group = self
+            .gpu
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("纹理绑定组"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&dest_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+        let output = self.gpu.surface().get_current_texture()?;
 
         let view = output
             .texture
@@ -363,39 +340,25 @@ impl State<'_> {
             render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
             render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
         }
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+        self.pool.end_frame();
+        self.gpu.resources.end_frame();
         Ok(())
     }
 
     pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
-        if physical_size.width > 0 && physical_size.height > 0 {
-            self.config.width = physical_size.width;
-            self.config.height = physical_size.height;
-            self.surface.configure(&self.device, &self.config);
-        }
+        self.gpu.resize(physical_size);
     }
 }
 
-async fn create_wgpu_instance() -> anyhow::Result<(wgpu::Instance, wgpu::Backends)> {
-    for backend in wgpu::Backends::all() {
-        if let Some(instance) = try_wgpu_backend(backend).await {
-            return Ok((instance, backend));
-        }
+impl Lesson for State {
+    fn render(&mut self) -> Result<(), SurfaceError> {
+        State::render(self)
     }
-    Err(anyhow!("没有找到可用渲染后端"))
-}
 
-async fn try_wgpu_backend(backends: wgpu::Backends) -> Option<wgpu::Instance> {
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        backends,
-        flags: wgpu::InstanceFlags::default().with_env(),
-        ..Default::default()
-    });
-    if instance.enumerate_adapters(backends).await.is_empty() {
-        None
-    } else {
-        Some(instance)
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        State::resize(self, size);
     }
 }
 
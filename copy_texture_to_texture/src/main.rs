@@ -1,5 +1,6 @@
 use app::App;
 mod app;
 fn main() {
+    gpu::logging::init();
     App::run();
 }
@@ -1,25 +1,14 @@
-use glam::{vec3, Vec2, Vec3};
+use glam::{Vec2, Vec3, vec3};
+use vertex_layout::VertexLayout;
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
 pub struct Mesh {
     position: Vec3,
     color: Vec3,
     uv: Vec2,
 }
 
-impl Mesh {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2=>Float32x2];
-    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Mesh>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &Self::ATTRIBUTES,
-        }
-    }
-}
-
 pub const MESH: &[Mesh] = &[
     Mesh {
         position: Vec3::new(0.0, 0.5, 0.0),
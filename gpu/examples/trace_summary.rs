@@ -0,0 +1,21 @@
+//! 统计 `WGPU_TRACE` 生成的 API 调用记录
+//!
+//! ```text
+//! cargo run -p gpu --example trace_summary -- wgpu_trace
+//! ```
+use std::path::PathBuf;
+
+use anyhow::Context;
+use gpu::trace::{TRACE_DIR_ENV, TraceSummary, dir_from_env};
+
+fn main() -> anyhow::Result<()> {
+    let path = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .or_else(dir_from_env)
+        .with_context(|| {
+            format!("用法：trace_summary <记录目录或文件>，或者设置 {TRACE_DIR_ENV}")
+        })?;
+    print!("{}", TraceSummary::load(&path)?);
+    Ok(())
+}
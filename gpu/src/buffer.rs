@@ -0,0 +1,455 @@
+//! 带类型的 uniform / storage 缓冲区
+//!
+//! ```ignore
+//! let time = UniformBuffer::new(&device, "Delta Time", &0.0f32)?;
+//! let layout_entry = time.layout_entry(1, wgpu::ShaderStages::COMPUTE);
+//! let bind_group_entry = time.bind_group_entry(1);
+//! time.update(&queue, &delta_time.as_secs_f32());
+//! ```
+//!
+//! 编译期只做最基本的检查：大小不为 0、是 4 的倍数、对齐不超过 16（WGSL 里最大的对齐是 vec4 的 16）。
+//! 这些条件满足了也不保证和着色器里的布局一致，uniform 地址空间还有更严的规则：
+//! 数组的步长要是 16 的倍数，`vec3` 占 16 字节，结构体成员按 16 字节对齐。
+//! Rust 一侧需要自己补齐 padding，再在测试里用 `shader_reflect` 的 `check_struct`
+//! 和着色器里的结构体比较大小。
+//! 设备限制（绑定大小、动态偏移对齐）在创建时检查。
+//!
+//! 多个实例共用一个缓冲区时用 `new_dynamic`，每个实例按
+//! `min_uniform_buffer_offset_alignment`（storage 是 `min_storage_buffer_offset_alignment`）对齐，
+//! 绘制时用 [`UniformBuffer::offset`] 得到 `set_bind_group` 的动态偏移。
+use std::{marker::PhantomData, num::NonZeroU64};
+
+use anyhow::{Result, bail};
+use bytemuck::Pod;
+
+/// 编译期排除明显不能放进 GPU 缓冲区的 `T`，通过了也不代表和 WGSL 的布局一致
+struct Layout<T>(PhantomData<T>);
+
+impl<T> Layout<T> {
+    const SIZE: u64 = {
+        assert!(size_of::<T>() > 0, "GPU 缓冲区的类型大小不能为 0");
+        assert!(
+            size_of::<T>().is_multiple_of(4),
+            "WGSL 类型的大小都是 4 字节的倍数，需要手动补齐 padding"
+        );
+        assert!(
+            align_of::<T>() <= 16,
+            "WGSL 类型的对齐不超过 16 字节，Rust 类型的对齐更大时两边布局对不上"
+        );
+        size_of::<T>() as u64
+    };
+}
+
+/// `value` 向上对齐到 `alignment` 的倍数
+pub fn align_to(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+/// 缓冲区用途，决定设备限制和偏移对齐
+#[derive(Clone, Copy)]
+enum Kind {
+    Uniform,
+    /// 附带额外的用途，比如粒子缓冲区还要当顶点缓冲区
+    Storage(wgpu::BufferUsages),
+}
+
+impl Kind {
+    fn usage(self) -> wgpu::BufferUsages {
+        match self {
+            Kind::Uniform => wgpu::BufferUsages::UNIFORM,
+            Kind::Storage(extra) => wgpu::BufferUsages::STORAGE | extra,
+        }
+    }
+
+    fn max_binding_size(self, limits: &wgpu::Limits) -> u64 {
+        match self {
+            Kind::Uniform => limits.max_uniform_buffer_binding_size as u64,
+            Kind::Storage(_) => limits.max_storage_buffer_binding_size as u64,
+        }
+    }
+
+    fn offset_alignment(self, limits: &wgpu::Limits) -> u64 {
+        match self {
+            Kind::Uniform => limits.min_uniform_buffer_offset_alignment as u64,
+            Kind::Storage(_) => limits.min_storage_buffer_offset_alignment as u64,
+        }
+    }
+}
+
+/// 两种缓冲区共用的部分：一个 wgpu 缓冲区里按 `stride` 排列 `count` 个实例
+struct Instances {
+    buffer: wgpu::Buffer,
+    /// 一个实例在着色器里看到的字节数
+    size: u64,
+    /// 相邻两个实例的起始位置之差，动态偏移时已经对齐
+    stride: u64,
+    count: u32,
+    dynamic: bool,
+}
+
+impl Instances {
+    fn new(
+        device: &wgpu::Device,
+        label: &str,
+        kind: Kind,
+        size: u64,
+        count: u32,
+        dynamic: bool,
+        contents: Option<&[u8]>,
+    ) -> Result<Self> {
+        let limits = device.limits();
+        if size == 0 || count == 0 {
+            bail!("缓冲区 {label} 不能为空");
+        }
+        let max_binding_size = kind.max_binding_size(&limits);
+        if size > max_binding_size {
+            bail!("缓冲区 {label} 的绑定大小 {size} 超过了设备限制 {max_binding_size}");
+        }
+        let stride = if dynamic {
+            align_to(size, kind.offset_alignment(&limits))
+        } else {
+            size
+        };
+        let total = stride * count as u64;
+        if total > limits.max_buffer_size {
+            bail!(
+                "缓冲区 {label} 的总大小 {total} 超过了设备限制 {}",
+                limits.max_buffer_size
+            );
+        }
+        // 有初始数据时映射写入，创建时还拿不到 queue；COPY_SRC 方便回读调试
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: total,
+            usage: kind.usage() | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: contents.is_some(),
+        });
+        if let Some(contents) = contents {
+            buffer.slice(..).get_mapped_range_mut()[..contents.len()].copy_from_slice(contents);
+            buffer.unmap();
+        }
+        Ok(Self {
+            buffer,
+            size,
+            stride,
+            count,
+            dynamic,
+        })
+    }
+
+    fn offset(&self, index: u32) -> u64 {
+        assert!(
+            index < self.count,
+            "实例下标 {index} 越界，共 {} 个实例",
+            self.count
+        );
+        index as u64 * self.stride
+    }
+
+    /// 把每个实例的数据按 `stride` 铺开，一次写入
+    fn write_all(&self, queue: &wgpu::Queue, instances: &[&[u8]]) {
+        assert!(
+            instances.len() as u32 <= self.count,
+            "写入 {} 个实例，缓冲区只有 {} 个",
+            instances.len(),
+            self.count
+        );
+        if self.stride == self.size {
+            let bytes: Vec<u8> = instances.concat();
+            queue.write_buffer(&self.buffer, 0, &bytes);
+            return;
+        }
+        let mut bytes = vec![0u8; self.stride as usize * instances.len()];
+        for (chunk, instance) in bytes.chunks_mut(self.stride as usize).zip(instances) {
+            chunk[..instance.len()].copy_from_slice(instance);
+        }
+        queue.write_buffer(&self.buffer, 0, &bytes);
+    }
+
+    fn layout_entry(
+        &self,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+        ty: wgpu::BufferBindingType,
+    ) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: self.dynamic,
+                min_binding_size: NonZeroU64::new(self.size),
+            },
+            count: None,
+        }
+    }
+
+    /// 动态偏移时只绑定一个实例的大小，偏移在 `set_bind_group` 时给出
+    fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        if self.dynamic {
+            wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &self.buffer,
+                offset: 0,
+                size: NonZeroU64::new(self.size),
+            })
+        } else {
+            self.buffer.as_entire_binding()
+        }
+    }
+}
+
+/// `var<uniform>` 绑定的缓冲区，每个实例是一个 `T`
+///
+/// 不检查 `T` 是否符合 uniform 地址空间的布局规则，见[模块文档](self)。
+pub struct UniformBuffer<T> {
+    inner: Instances,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> UniformBuffer<T> {
+    /// 单个实例，用 `value` 初始化
+    pub fn new(device: &wgpu::Device, label: &str, value: &T) -> Result<Self> {
+        Self::create(device, label, 1, false, Some(bytemuck::bytes_of(value)))
+    }
+
+    /// `count` 个实例，绑定时用动态偏移选择其中一个
+    pub fn new_dynamic(device: &wgpu::Device, label: &str, count: u32) -> Result<Self> {
+        Self::create(device, label, count, true, None)
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        label: &str,
+        count: u32,
+        dynamic: bool,
+        contents: Option<&[u8]>,
+    ) -> Result<Self> {
+        let inner = Instances::new(
+            device,
+            label,
+            Kind::Uniform,
+            Layout::<T>::SIZE,
+            count,
+            dynamic,
+            contents,
+        )?;
+        Ok(Self {
+            inner,
+            _marker: PhantomData,
+        })
+    }
+
+    /// 更新第 0 个实例
+    pub fn update(&self, queue: &wgpu::Queue, value: &T) {
+        self.update_at(queue, 0, value);
+    }
+
+    /// 更新第 `index` 个实例
+    pub fn update_at(&self, queue: &wgpu::Queue, index: u32, value: &T) {
+        let offset = self.inner.offset(index);
+        queue.write_buffer(&self.inner.buffer, offset, bytemuck::bytes_of(value));
+    }
+
+    /// 从第 0 个实例开始连续更新，对齐用的空隙补 0
+    pub fn update_all(&self, queue: &wgpu::Queue, values: &[T]) {
+        let instances: Vec<&[u8]> = values.iter().map(bytemuck::bytes_of).collect();
+        self.inner.write_all(queue, &instances);
+    }
+
+    /// 第 `index` 个实例的动态偏移，传给 `set_bind_group`
+    pub fn offset(&self, index: u32) -> u32 {
+        self.inner.offset(index) as u32
+    }
+
+    /// 相邻两个实例之间的字节数
+    pub fn stride(&self) -> u64 {
+        self.inner.stride
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.inner.count
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.inner.buffer
+    }
+
+    pub fn layout_entry(
+        &self,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> wgpu::BindGroupLayoutEntry {
+        self.inner
+            .layout_entry(binding, visibility, wgpu::BufferBindingType::Uniform)
+    }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        self.inner.binding_resource()
+    }
+
+    pub fn bind_group_entry(&self, binding: u32) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding,
+            resource: self.binding_resource(),
+        }
+    }
+}
+
+/// `var<storage>` 绑定的缓冲区，每个实例是 `len` 个 `T` 组成的数组
+///
+/// 着色器里可以声明成 `array<T>`；`len` 为 1 时也可以直接声明成 `T`。
+pub struct StorageBuffer<T> {
+    inner: Instances,
+    len: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> StorageBuffer<T> {
+    /// 单个实例，用 `data` 初始化，`usage` 是 `STORAGE | COPY_DST | COPY_SRC` 之外的额外用途
+    pub fn from_slice(
+        device: &wgpu::Device,
+        label: &str,
+        data: &[T],
+        usage: wgpu::BufferUsages,
+    ) -> Result<Self> {
+        let contents = bytemuck::cast_slice(data);
+        Self::create(
+            device,
+            label,
+            data.len() as u32,
+            1,
+            false,
+            usage,
+            Some(contents),
+        )
+    }
+
+    /// 单个实例，`len` 个元素全部清零
+    pub fn zeroed(
+        device: &wgpu::Device,
+        label: &str,
+        len: u32,
+        usage: wgpu::BufferUsages,
+    ) -> Result<Self> {
+        Self::create(device, label, len, 1, false, usage, None)
+    }
+
+    /// `count` 个实例，每个实例 `len` 个元素，绑定时用动态偏移选择其中一个
+    pub fn new_dynamic(
+        device: &wgpu::Device,
+        label: &str,
+        len: u32,
+        count: u32,
+        usage: wgpu::BufferUsages,
+    ) -> Result<Self> {
+        Self::create(device, label, len, count, true, usage, None)
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        label: &str,
+        len: u32,
+        count: u32,
+        dynamic: bool,
+        usage: wgpu::BufferUsages,
+        contents: Option<&[u8]>,
+    ) -> Result<Self> {
+        let inner = Instances::new(
+            device,
+            label,
+            Kind::Storage(usage),
+            Layout::<T>::SIZE * len as u64,
+            count,
+            dynamic,
+            contents,
+        )?;
+        Ok(Self {
+            inner,
+            len,
+            _marker: PhantomData,
+        })
+    }
+
+    /// 更新第 0 个实例的第 0 个元素，适合只有一个结构体的参数缓冲区
+    pub fn update(&self, queue: &wgpu::Queue, value: &T) {
+        self.write(queue, 0, std::slice::from_ref(value));
+    }
+
+    /// 从第 0 个实例的第 `first` 个元素开始写入
+    pub fn write(&self, queue: &wgpu::Queue, first: u32, data: &[T]) {
+        assert!(
+            first as usize + data.len() <= self.len as usize,
+            "写入范围 {first}..{} 越界，共 {} 个元素",
+            first as usize + data.len(),
+            self.len
+        );
+        queue.write_buffer(
+            &self.inner.buffer,
+            first as u64 * Layout::<T>::SIZE,
+            bytemuck::cast_slice(data),
+        );
+    }
+
+    /// 覆盖第 `index` 个实例的开头
+    pub fn write_instance(&self, queue: &wgpu::Queue, index: u32, data: &[T]) {
+        assert!(
+            data.len() <= self.len as usize,
+            "写入 {} 个元素，每个实例只有 {} 个",
+            data.len(),
+            self.len
+        );
+        let offset = self.inner.offset(index);
+        queue.write_buffer(&self.inner.buffer, offset, bytemuck::cast_slice(data));
+    }
+
+    /// 第 `index` 个实例的动态偏移，传给 `set_bind_group`
+    pub fn offset(&self, index: u32) -> u32 {
+        self.inner.offset(index) as u32
+    }
+
+    /// 相邻两个实例之间的字节数
+    pub fn stride(&self) -> u64 {
+        self.inner.stride
+    }
+
+    /// 每个实例的元素个数
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.inner.count
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.inner.buffer
+    }
+
+    pub fn layout_entry(
+        &self,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+        read_only: bool,
+    ) -> wgpu::BindGroupLayoutEntry {
+        self.inner.layout_entry(
+            binding,
+            visibility,
+            wgpu::BufferBindingType::Storage { read_only },
+        )
+    }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        self.inner.binding_resource()
+    }
+
+    pub fn bind_group_entry(&self, binding: u32) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding,
+            resource: self.binding_resource(),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! 所有课程共用的 GPU 上下文
+//!
+//! ```ignore
+//! // 有窗口
+//! let gpu = GpuContext::windowed(window.clone(), &ContextOptions::default()).await?;
+//! // 离屏渲染 / 计算
+//! let gpu = GpuContext::headless(&ContextOptions::default()).await?;
+//! ```
+//!
+//! 按 Vulkan、Metal、DX12、GL 的顺序逐个尝试后端，第一个能给出适配器
+//! （有窗口时还要求能在这个窗口上呈现）并且成功创建设备的后端胜出。
+//!
+//! 表面格式统一用 [`preferred_surface_format`]：优先选 sRGB 格式。
+//! 着色器里算出来的都是线性颜色，写入 sRGB 表面时由硬件做 gamma 编码，
+//! 所以各课程不需要再自己决定表面格式。
+//!
+//! 后端支持时会打开磁盘上的管线缓存（见 [`crate::pipeline_cache`]），
+//! 创建管线时把 [`GpuContext::pipeline_cache`] 传给 `cache` 字段。
+//!
+//! 设备上没有被错误作用域接住的校验错误会记成日志（见 [`crate::validation`]），不会 panic。
+//! 设置了 `WGPU_TRACE` 时把 API 调用记录到这个目录（见 [`crate::trace`]）。
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use winit::{dpi::PhysicalSize, window::Window};
+
+use crate::{
+    pipeline_cache::{self, PipelineCacheFile},
+    resources::ResourceTracker,
+    trace, validation,
+};
+
+/// 创建上下文的选项，默认值适合大部分课程
+#[derive(Debug, Clone)]
+pub struct ContextOptions {
+    pub power_preference: wgpu::PowerPreference,
+    /// 只用软件实现的适配器（比如 llvmpipe、WARP），测试在没有显卡的机器上也能得到同样的结果
+    pub force_fallback_adapter: bool,
+    /// 必须支持的特性，适配器不支持时创建失败
+    pub required_features: wgpu::Features,
+    /// 适配器支持就打开的特性，比如 `TIMESTAMP_QUERY`
+    pub optional_features: wgpu::Features,
+    pub limits: wgpu::Limits,
+    pub present_mode: wgpu::PresentMode,
+    /// 管线缓存目录，`None` 表示不使用管线缓存，默认值见 [`pipeline_cache::default_dir`]
+    pub pipeline_cache_dir: Option<PathBuf>,
+    /// API 调用记录的输出目录，默认读环境变量 `WGPU_TRACE`，见 [`crate::trace`]
+    pub trace_dir: Option<PathBuf>,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default(),
+            // Fifo 是唯一保证所有平台都支持的呈现模式
+            present_mode: wgpu::PresentMode::Fifo,
+            pipeline_cache_dir: pipeline_cache::default_dir(),
+            trace_dir: trace::dir_from_env(),
+        }
+    }
+}
+
+/// 窗口表面和它的配置
+struct WindowSurface {
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+}
+
+pub struct GpuContext {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    /// 通过它创建缓冲区和纹理可以统计显存占用，见 [`crate::resources`]
+    pub resources: ResourceTracker,
+    pipeline_cache: Option<PipelineCacheFile>,
+    /// 离屏上下文没有表面
+    surface: Option<WindowSurface>,
+}
+
+impl GpuContext {
+    /// 创建带窗口表面的上下文，并按窗口当前大小配置好表面
+    ///
+    /// 表面持有窗口的 `Arc`，窗口至少和上下文活得一样久。
+    pub async fn windowed(window: Arc<Window>, options: &ContextOptions) -> Result<Self> {
+        let (instance, adapter, device, queue, surface) = connect(options, |instance| {
+            Ok(Some(instance.create_surface(window.clone())?))
+        })
+        .await?;
+        let surface = surface.expect("有窗口时一定创建了表面");
+        let pipeline_cache = open_pipeline_cache(&device, &adapter, options);
+
+        let caps = surface.get_capabilities(&adapter);
+        let size = window.inner_size();
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: preferred_surface_format(&caps),
+            // 窗口刚创建时可能是 0 大小，配置 0 大小的表面会报错
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: options.present_mode,
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&device, &config);
+
+        Ok(Self {
+            instance,
+            adapter,
+            resources: ResourceTracker::new(&device),
+            device,
+            queue,
+            pipeline_cache,
+            surface: Some(WindowSurface { surface, config }),
+        })
+    }
+
+    /// 创建没有窗口的上下文，用于离屏渲染和计算
+    pub async fn headless(options: &ContextOptions) -> Result<Self> {
+        let (instance, adapter, device, queue, _) = connect(options, |_| Ok(None)).await?;
+        let pipeline_cache = open_pipeline_cache(&device, &adapter, options);
+        Ok(Self {
+            instance,
+            adapter,
+            resources: ResourceTracker::new(&device),
+            device,
+            queue,
+            pipeline_cache,
+            surface: None,
+        })
+    }
+
+    /// 创建管线时传给 `cache` 字段，后端不支持管线缓存时为 `None`
+    pub fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.pipeline_cache.as_ref().map(PipelineCacheFile::cache)
+    }
+
+    /// 立即把管线缓存写回磁盘，上下文释放时也会自动写一次
+    pub fn save_pipeline_cache(&self) -> Result<()> {
+        match &self.pipeline_cache {
+            Some(cache) => cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// 窗口表面，离屏上下文调用会 panic
+    pub fn surface(&self) -> &wgpu::Surface<'static> {
+        &self.window_surface().surface
+    }
+
+    /// 当前的表面配置，离屏上下文调用会 panic
+    pub fn config(&self) -> &wgpu::SurfaceConfiguration {
+        &self.window_surface().config
+    }
+
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.config().format
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        let config = self.config();
+        PhysicalSize::new(config.width, config.height)
+    }
+
+    /// 窗口大小变化时重新配置表面，最小化时的 0 大小直接忽略
+    ///
+    /// 返回表面是否真的重新配置了。
+    pub fn resize(&mut self, size: PhysicalSize<u32>) -> bool {
+        if size.width == 0 || size.height == 0 {
+            return false;
+        }
+        let Some(window_surface) = &mut self.surface else {
+            return false;
+        };
+        window_surface.config.width = size.width;
+        window_surface.config.height = size.height;
+        window_surface
+            .surface
+            .configure(&self.device, &window_surface.config);
+        true
+    }
+
+    /// 用当前配置重新配置表面，用于处理 `SurfaceError::Lost` / `Outdated`
+    pub fn reconfigure(&self) {
+        let window_surface = self.window_surface();
+        window_surface
+            .surface
+            .configure(&self.device, &window_surface.config);
+    }
+
+    fn window_surface(&self) -> &WindowSurface {
+        self.surface
+            .as_ref()
+            .expect("离屏上下文没有窗口表面，请使用 GpuContext::windowed")
+    }
+}
+
+/// 统一的表面格式选择：有 sRGB 格式就用 sRGB，否则用第一个
+pub fn preferred_surface_format(caps: &wgpu::SurfaceCapabilities) -> wgpu::TextureFormat {
+    caps.formats
+        .iter()
+        .find(|f| f.is_srgb())
+        .copied()
+        .unwrap_or(caps.formats[0])
+}
+
+/// 后端的可读名字，用于日志和错误信息
+pub fn backend_names(backends: wgpu::Backends) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if backends.contains(wgpu::Backends::VULKAN) {
+        names.push("Vulkan");
+    }
+    if backends.contains(wgpu::Backends::DX12) {
+        names.push("DirectX 12");
+    }
+    if backends.contains(wgpu::Backends::METAL) {
+        names.push("Metal");
+    }
+    if backends.contains(wgpu::Backends::GL) {
+        names.push("Open GL");
+    }
+    if backends.contains(wgpu::Backends::BROWSER_WEBGPU) {
+        names.push("Web GPU");
+    }
+    names
+}
+
+fn create_instance(backends: wgpu::Backends) -> wgpu::Instance {
+    wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends,
+        flags: wgpu::InstanceFlags::default().with_env(),
+        ..Default::default()
+    })
+}
+
+async fn request_device(
+    adapter: &wgpu::Adapter,
+    options: &ContextOptions,
+) -> Result<(wgpu::Device, wgpu::Queue)> {
+    let mut optional_features = options.optional_features;
+    if options.pipeline_cache_dir.is_some() {
+        optional_features |= wgpu::Features::PIPELINE_CACHE;
+    }
+    let features = options.required_features | (optional_features & adapter.features());
+    // 在创建设备之前开始记录，设备创建本身也会记下来
+    if let Some(dir) = &options.trace_dir {
+        match trace::start_capture(dir, &adapter.get_info(), features) {
+            Ok(path) => tracing::info!(path = %path.display(), "正在记录 API 调用"),
+            Err(e) => tracing::warn!("打开 API 调用记录失败：{e:#}"),
+        }
+    }
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("设备"),
+            required_features: features,
+            required_limits: options.limits.clone(),
+            memory_hints: wgpu::MemoryHints::default(),
+            // wgpu 28 自带的跟踪暂时不可用，API 调用由 crate::trace 记录
+            trace: wgpu::Trace::Off,
+            experimental_features: wgpu::ExperimentalFeatures::disabled(),
+        })
+        .await?;
+    validation::install_error_handler(&device);
+    Ok((device, queue))
+}
+
+/// 打开失败（比如目录不可写）只影响启动速度，打印出来继续运行
+fn open_pipeline_cache(
+    device: &wgpu::Device,
+    adapter: &wgpu::Adapter,
+    options: &ContextOptions,
+) -> Option<PipelineCacheFile> {
+    let dir = options.pipeline_cache_dir.as_ref()?;
+    PipelineCacheFile::open(device, &adapter.get_info(), dir).unwrap_or_else(|e| {
+        tracing::warn!("打开管线缓存失败：{e:#}");
+        None
+    })
+}
+
+type Connection = (
+    wgpu::Instance,
+    wgpu::Adapter,
+    wgpu::Device,
+    wgpu::Queue,
+    Option<wgpu::Surface<'static>>,
+);
+
+/// 按顺序逐个尝试后端，`create_surface` 为每个后端的实例创建表面（离屏时返回 `None`）
+///
+/// 某个后端没有适配器、或者适配器创建设备失败（比如驱动不支持要求的特性和限制）时
+/// 记下原因换下一个后端，全部失败时把每个后端的原因一起报出来。
+async fn connect(
+    options: &ContextOptions,
+    create_surface: impl Fn(&wgpu::Instance) -> Result<Option<wgpu::Surface<'static>>>,
+) -> Result<Connection> {
+    let mut failures = Vec::new();
+    for backend in wgpu::Backends::all() {
+        let name = backend_names(backend).join(", ");
+        let instance = create_instance(backend);
+        let surface = match create_surface(&instance) {
+            Ok(surface) => surface,
+            Err(e) => {
+                failures.push(format!("{name}：创建表面失败：{e}"));
+                continue;
+            }
+        };
+        let Ok(adapter) = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: options.power_preference,
+                compatible_surface: surface.as_ref(),
+                force_fallback_adapter: options.force_fallback_adapter,
+            })
+            .await
+        else {
+            failures.push(format!("{name}：没有适配器"));
+            continue;
+        };
+        match request_device(&adapter, options).await {
+            Ok((device, queue)) => return Ok((instance, adapter, device, queue, surface)),
+            Err(e) => {
+                let adapter_name = adapter.get_info().name;
+                tracing::warn!(
+                    backend = name,
+                    adapter = adapter_name,
+                    "创建设备失败，尝试下一个后端：{e:#}"
+                );
+                failures.push(format!("{name}（{adapter_name}）：创建设备失败：{e:#}"));
+            }
+        }
+    }
+    Err(anyhow!("没有找到可用适配器：\n  {}", failures.join("\n  ")))
+}
@@ -0,0 +1,266 @@
+//! 着色器热重载
+//!
+//! 平时着色器用 `include_str!` 嵌入二进制；设置环境变量 `SHADER_HOT_RELOAD=1` 进入开发模式，
+//! 着色器改为从磁盘读取，每帧调用 [`HotShader::poll`] 检查文件的修改时间。
+//!
+//! ```ignore
+//! let mut shader = HotShader::new(&device, shader_source!("src/wgsls/compute.wgsl"))?;
+//! // 每帧
+//! if shader.poll(&device) {
+//!     // 用 shader.module() 重建管线，失败时保留旧管线
+//!     if let Ok(pipeline) = try_create(&device, "管线", |device| create_pipeline(device, shader.module())) {
+//!         self.pipeline = pipeline;
+//!     }
+//! }
+//! ```
+//!
+//! 着色器先经过 [`shader_preprocess`] 展开 `#include` 和宏。`#include "..."` 引用的文件要在
+//! [`shader_source!`](crate::shader_source) 的 `includes` 里列出来才会被嵌入；
+//! `#include <...>` 引用的是 `gpu/wgsl` 下的公共库（[`LIBRARY`]），总是嵌入的。
+//! 开发模式下被包含的文件同样会被监视。
+//!
+//! 重新编译前先用 naga 解析和校验，出错时记一条错误日志，继续使用上一次编译成功的模块。
+//! 日志里的位置已经换算回 `#include` 展开之前的文件和行号。
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use shader_preprocess::{Defines, Preprocessor, normalize};
+use shader_reflect::ShaderReflection;
+
+use crate::validation::try_create;
+
+/// 公共 WGSL 库所在的目录，`#include <...>` 在这里查找
+pub const LIBRARY_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/wgsl");
+
+/// 嵌入的公共 WGSL 库：文件名和内容
+pub const LIBRARY: &[(&str, &str)] = &[
+    ("random.wgsl", include_str!("../wgsl/random.wgsl")),
+    ("color.wgsl", include_str!("../wgsl/color.wgsl")),
+];
+
+/// 开发模式的开关
+pub const HOT_RELOAD_ENV: &str = "SHADER_HOT_RELOAD";
+
+/// 是否处于开发模式，只在第一次调用时读取环境变量
+pub fn hot_reload_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var(HOT_RELOAD_ENV).is_ok_and(|v| !v.is_empty() && v != "0"))
+}
+
+/// 一个 WGSL 文件：磁盘上的绝对路径，以及编译时嵌入的内容
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderSource {
+    pub path: &'static str,
+    pub embedded: &'static str,
+    /// 用 `#include "..."` 引用的文件：绝对路径和嵌入的内容
+    pub includes: &'static [(&'static str, &'static str)],
+}
+
+/// 从当前 crate 根目录的相对路径生成 [`ShaderSource`]，同时用 `include_str!` 嵌入内容
+///
+/// ```ignore
+/// shader_source!("src/wgsls/compute.wgsl", includes: ["src/wgsls/particle.wgsl"])
+/// ```
+#[macro_export]
+macro_rules! shader_source {
+    ($path:literal $(, includes: [$($include:literal),* $(,)?])? $(,)?) => {
+        $crate::hot_reload::ShaderSource {
+            path: concat!(env!("CARGO_MANIFEST_DIR"), "/", $path),
+            embedded: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)),
+            includes: &[$($((
+                concat!(env!("CARGO_MANIFEST_DIR"), "/", $include),
+                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $include)),
+            )),*)?],
+        }
+    };
+}
+
+impl ShaderSource {
+    /// 文件名，用作着色器模块的标签
+    pub fn name(&self) -> &'static str {
+        Path::new(self.path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(self.path)
+    }
+
+    /// 入口文件和 `includes` 里列出的文件，开发模式下至少要监视这些
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        std::iter::once(self.path)
+            .chain(self.includes.iter().map(|&(path, _)| path))
+            .map(PathBuf::from)
+    }
+
+    /// 在嵌入的内容里查找文件，找不到时返回 `NotFound`
+    fn embedded(&self, path: &Path) -> io::Result<String> {
+        let library = LIBRARY
+            .iter()
+            .map(|&(name, code)| (Path::new(LIBRARY_DIR).join(name), code));
+        let includes = self
+            .includes
+            .iter()
+            .map(|&(p, code)| (PathBuf::from(p), code));
+        std::iter::once((PathBuf::from(self.path), self.embedded))
+            .chain(includes)
+            .chain(library)
+            .find(|(p, _)| normalize(p) == path)
+            .map(|(_, code)| code.to_string())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "{} 没有嵌入，请加到 shader_source! 的 includes 里",
+                        path.display()
+                    ),
+                )
+            })
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 可以热重载的着色器模块
+pub struct HotShader {
+    source: ShaderSource,
+    defines: Defines,
+    module: wgpu::ShaderModule,
+    /// 上次读取时用到的每个文件和它的修改时间，非开发模式下为空
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl HotShader {
+    pub fn new(device: &wgpu::Device, source: ShaderSource) -> Result<Self> {
+        Self::with_defines(device, source, Defines::new())
+    }
+
+    /// 带上从 Rust 注入的宏创建
+    ///
+    /// 开发模式下从磁盘读取，磁盘上的版本编译失败时退回嵌入的版本。
+    pub fn with_defines(
+        device: &wgpu::Device,
+        source: ShaderSource,
+        defines: Defines,
+    ) -> Result<Self> {
+        let mut shader = Self {
+            module: compile(device, &source, &defines, false)?.0,
+            source,
+            defines,
+            watched: Vec::new(),
+        };
+        if hot_reload_enabled() {
+            match compile(device, &shader.source, &shader.defines, true) {
+                Ok((module, files)) => {
+                    shader.module = module;
+                    shader.watch(files);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        shader = shader.source.name(),
+                        "磁盘上的版本编译失败，使用编译时嵌入的版本：{e}"
+                    );
+                    // 出错的可能是被包含的文件，它们也要监视，改好之后才能重新加载
+                    shader.watch(shader.source.files().collect());
+                }
+            }
+        }
+        Ok(shader)
+    }
+
+    /// 最近一次编译成功的模块
+    pub fn module(&self) -> &wgpu::ShaderModule {
+        &self.module
+    }
+
+    pub fn source(&self) -> &ShaderSource {
+        &self.source
+    }
+
+    pub fn defines(&self) -> &Defines {
+        &self.defines
+    }
+
+    /// 换一组宏重新编译，成功后调用方需要重建管线；失败时保留原来的模块和宏
+    pub fn set_defines(&mut self, device: &wgpu::Device, defines: Defines) -> Result<()> {
+        let (module, files) = compile(device, &self.source, &defines, hot_reload_enabled())?;
+        self.module = module;
+        self.defines = defines;
+        if hot_reload_enabled() {
+            self.watch(files);
+        }
+        Ok(())
+    }
+
+    /// 入口文件或者它包含的文件变化、并且重新编译成功时返回 `true`，
+    /// 调用方需要重建用到这个模块的管线
+    pub fn poll(&mut self, device: &wgpu::Device) -> bool {
+        if !hot_reload_enabled() {
+            return false;
+        }
+        let changed = self
+            .watched
+            .iter()
+            .any(|(path, time)| modified(path).is_some_and(|now| Some(now) != *time));
+        if !changed {
+            return false;
+        }
+        // 先记下修改时间，编译失败时不会每帧重复报同一个错误
+        let files: Vec<PathBuf> = self.watched.iter().map(|(path, _)| path.clone()).collect();
+        self.watch(files);
+        match compile(device, &self.source, &self.defines, true) {
+            Ok((module, files)) => {
+                tracing::info!(shader = self.source.name(), "已重新加载");
+                self.module = module;
+                self.watch(files);
+                true
+            }
+            Err(e) => {
+                tracing::error!(
+                    shader = self.source.name(),
+                    "重新编译失败，继续使用上一次编译成功的模块：{e}"
+                );
+                false
+            }
+        }
+    }
+
+    fn watch(&mut self, files: Vec<PathBuf>) {
+        self.watched = files
+            .into_iter()
+            .map(|path| {
+                let time = modified(&path);
+                (path, time)
+            })
+            .collect();
+    }
+}
+
+/// 展开 `#include` 和宏，先用 naga 检查，通过后再交给 wgpu，避免校验错误变成未捕获错误直接 panic
+///
+/// 返回编译好的模块和用到的文件。
+fn compile(
+    device: &wgpu::Device,
+    source: &ShaderSource,
+    defines: &Defines,
+    from_disk: bool,
+) -> Result<(wgpu::ShaderModule, Vec<PathBuf>)> {
+    let mut preprocessor = Preprocessor::new(defines.clone()).include_dir(LIBRARY_DIR);
+    if !from_disk {
+        preprocessor = preprocessor.loader(|path| source.embedded(path));
+    }
+    let preprocessed = preprocessor.process_file(source.path)?;
+    ShaderReflection::from_preprocessed(&preprocessed)?;
+    let module = try_create(device, source.name(), |device| {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(source.name()),
+            source: wgpu::ShaderSource::Wgsl(preprocessed.code.as_str().into()),
+        })
+    })?;
+    Ok((module, preprocessed.files))
+}
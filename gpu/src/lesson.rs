@@ -0,0 +1,22 @@
+//! 课程的公共接口
+//!
+//! 每个课程的 `State` 都实现 [`Lesson`]，启动器只通过这个 trait 驱动它们：
+//! 每帧 `update` -> `render`，窗口大小变化时 `resize`，其余窗口事件交给 `input`。
+use std::time::Duration;
+
+use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+pub trait Lesson {
+    /// 每帧调用一次，`delta_time` 是距离上一帧的时间
+    fn update(&mut self, _delta_time: Duration) {}
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError>;
+
+    /// 窗口大小变化，表面丢失后也会用当前大小再调用一次
+    fn resize(&mut self, size: PhysicalSize<u32>);
+
+    /// 处理窗口事件，返回 `true` 表示事件已经被课程消费
+    fn input(&mut self, _event: &WindowEvent) -> bool {
+        false
+    }
+}
@@ -0,0 +1,21 @@
+//! 各个示例共用的 GPU 工具
+pub mod buffer;
+pub mod context;
+pub mod hot_reload;
+pub mod lesson;
+pub mod logging;
+pub mod pipeline_cache;
+pub mod pool;
+pub mod profiler;
+pub mod resources;
+pub mod trace;
+pub mod validation;
+
+pub use buffer::{StorageBuffer, UniformBuffer};
+pub use context::{ContextOptions, GpuContext};
+pub use hot_reload::{HotShader, ShaderSource};
+pub use lesson::Lesson;
+pub use pool::TransientPool;
+pub use profiler::GpuProfiler;
+pub use resources::{ResourceTracker, Tracked};
+pub use shader_preprocess::Defines;
@@ -0,0 +1,79 @@
+//! 日志
+//!
+//! [`init`] 安装 tracing-subscriber 的订阅者，再用 tracing-log 把 `log` 的输出（wgpu 内部用的是 `log`）
+//! 转成 tracing 事件，两边共用同一个过滤规则和输出格式：
+//!
+//! ```text
+//!   0.532140913s  WARN gpu::validation: 着色器校验失败 label="Render Pipeline" error=...
+//! ```
+//!
+//! 过滤规则读环境变量 `RUST_LOG`，写法见 [`EnvFilter`]：
+//!
+//! ```text
+//! RUST_LOG=debug                         # 全部 debug
+//! RUST_LOG=info,gpu=trace,wgpu_hal=off   # 默认 info，gpu 开到 trace，关掉 wgpu_hal
+//! ```
+//!
+//! 没有设置时用 [`DEFAULT_FILTER`]。过滤规则只管终端输出，[API 记录](crate::trace) 是另一个层，
+//! 有自己的过滤规则，开始记录时不会让终端多出日志。
+//!
+//! `log` 的全局上限一直是 trace，`log` 的记录要不要处理由 tracing-log 按 tracing 这边
+//! 所有层的过滤规则合起来判断，没有层需要的记录转换之前就被丢掉了。
+use std::{io::IsTerminal, sync::OnceLock};
+
+use tracing_subscriber::{Layer, fmt, layer::SubscriberExt};
+
+pub use tracing_subscriber::EnvFilter;
+
+use crate::trace;
+
+/// 过滤规则的环境变量
+pub const FILTER_ENV: &str = "RUST_LOG";
+
+/// 默认只看 info 以上；wgpu 的内部日志很多，只看警告
+pub const DEFAULT_FILTER: &str = "info,wgpu_core=warn,wgpu_hal=warn,naga=warn";
+
+/// 读 `RUST_LOG`，没有设置或者写错时用 [`DEFAULT_FILTER`]
+pub fn env_filter() -> EnvFilter {
+    let spec = std::env::var(FILTER_ENV).unwrap_or_default();
+    if spec.trim().is_empty() {
+        return EnvFilter::new(DEFAULT_FILTER);
+    }
+    EnvFilter::try_new(&spec).unwrap_or_else(|e| {
+        eprintln!("{FILTER_ENV}={spec} 无效（{e}），使用默认规则 {DEFAULT_FILTER}");
+        EnvFilter::new(DEFAULT_FILTER)
+    })
+}
+
+/// 终端输出的层：带启动以来的秒数，`ansi` 决定是否带颜色
+pub fn fmt_layer<S, W>(filter: EnvFilter, writer: W, ansi: bool) -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    W: for<'writer> fmt::MakeWriter<'writer> + Send + Sync + 'static,
+{
+    fmt::layer()
+        .with_timer(fmt::time::Uptime::default())
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_filter(filter)
+}
+
+/// 安装日志，可以重复调用，只有第一次生效
+///
+/// 已经有别的 tracing 订阅者或者 `log` 实现时保留原来的。
+pub fn init() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    INSTALLED.get_or_init(|| {
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt_layer(
+                env_filter(),
+                std::io::stderr,
+                std::io::stderr().is_terminal(),
+            ))
+            .with(trace::layer());
+        if tracing::subscriber::set_global_default(subscriber).is_err() {
+            return;
+        }
+        let _ = tracing_log::LogTracer::init();
+    });
+}
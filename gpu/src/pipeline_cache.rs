@@ -0,0 +1,150 @@
+//! 保存到磁盘的管线缓存
+//!
+//! 驱动把着色器编译成机器码很慢，`wgpu::PipelineCache` 可以把编译结果存下来，下次启动直接复用。
+//! 每个适配器一个文件，文件名来自 [`wgpu::util::pipeline_cache_key`]。
+//! 文件开头记录适配器的指纹（名字、驱动版本、后端），换了显卡或者升级了驱动时指纹对不上，
+//! 旧文件直接丢弃。
+//!
+//! 目前只有 Vulkan 支持 `PIPELINE_CACHE` 特性，其它后端 [`GpuContext::pipeline_cache`](crate::GpuContext::pipeline_cache)
+//! 返回 `None`，创建管线时传 `cache: None` 和以前一样。
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// 覆盖缓存目录的环境变量
+pub const PIPELINE_CACHE_DIR_ENV: &str = "PIPELINE_CACHE_DIR";
+
+/// 文件格式的标识，格式变化时改这里让旧文件失效
+const MAGIC: &[u8] = b"WGPU-LESSONS-PIPELINE-CACHE-1\n";
+
+/// 缓存目录下的子目录名
+pub const CACHE_DIR_NAME: &str = "pipeline_cache";
+
+/// 默认缓存目录，运行时决定：
+/// 1. 设置了 `PIPELINE_CACHE_DIR` 就用它
+/// 2. 设置了 `CARGO_TARGET_DIR` 就放在它下面
+/// 3. 否则放在可执行文件旁边（`cargo run` 时就是 `target/debug` 下）
+///
+/// 不能用编译时的源码路径，编译好的程序拷到别的机器上时那个路径不存在。
+/// 三者都拿不到时返回 `None`，不使用管线缓存。
+pub fn default_dir() -> Option<PathBuf> {
+    if let Some(dir) = env_dir(PIPELINE_CACHE_DIR_ENV) {
+        return Some(dir);
+    }
+    if let Some(dir) = env_dir("CARGO_TARGET_DIR") {
+        return Some(dir.join(CACHE_DIR_NAME));
+    }
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(CACHE_DIR_NAME))
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 适配器的指纹，任何一项变化都说明旧缓存不能用了
+pub fn adapter_fingerprint(info: &wgpu::AdapterInfo) -> String {
+    format!(
+        "{}|{:x}|{:x}|{:?}|{}|{}",
+        info.name, info.vendor, info.device, info.backend, info.driver, info.driver_info
+    )
+}
+
+/// 文件内容：标识、指纹、缓存数据
+pub fn encode(fingerprint: &str, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MAGIC.len() + fingerprint.len() + 1 + data.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(fingerprint.as_bytes());
+    out.push(b'\n');
+    out.extend_from_slice(data);
+    out
+}
+
+/// 取出缓存数据，标识或者指纹对不上时返回 `None`
+pub fn decode<'a>(fingerprint: &str, file: &'a [u8]) -> Option<&'a [u8]> {
+    let rest = file.strip_prefix(MAGIC)?;
+    let rest = rest.strip_prefix(fingerprint.as_bytes())?;
+    rest.strip_prefix(b"\n")
+}
+
+/// 和某个文件绑定的管线缓存，释放时自动写回磁盘
+pub struct PipelineCacheFile {
+    cache: wgpu::PipelineCache,
+    path: PathBuf,
+    fingerprint: String,
+}
+
+impl PipelineCacheFile {
+    /// 打开 `dir` 下这个适配器的缓存文件
+    ///
+    /// 设备没有打开 `PIPELINE_CACHE` 特性或者后端不支持时返回 `Ok(None)`。
+    pub fn open(
+        device: &wgpu::Device,
+        info: &wgpu::AdapterInfo,
+        dir: &Path,
+    ) -> Result<Option<Self>> {
+        if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+            return Ok(None);
+        }
+        let Some(key) = wgpu::util::pipeline_cache_key(info) else {
+            return Ok(None);
+        };
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("创建管线缓存目录 {} 失败", dir.display()))?;
+        let path = dir.join(key);
+        let fingerprint = adapter_fingerprint(info);
+
+        let file = std::fs::read(&path).ok();
+        let data = file.as_deref().and_then(|file| decode(&fingerprint, file));
+        if file.is_some() && data.is_none() {
+            tracing::info!(path = %path.display(), "管线缓存已过期，丢弃");
+            let _ = std::fs::remove_file(&path);
+        }
+
+        // SAFETY: data 来自上一次 get_data 写出的文件，并且指纹和当前适配器一致；
+        // fallback 为 true，驱动不认这份数据时会创建空缓存
+        let cache = unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("管线缓存"),
+                data,
+                fallback: true,
+            })
+        };
+        Ok(Some(Self {
+            cache,
+            path,
+            fingerprint,
+        }))
+    }
+
+    pub fn cache(&self) -> &wgpu::PipelineCache {
+        &self.cache
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 写回磁盘：先写临时文件再改名，中途退出也不会留下半个文件
+    pub fn save(&self) -> Result<()> {
+        let Some(data) = self.cache.get_data() else {
+            return Ok(());
+        };
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, encode(&self.fingerprint, &data))
+            .with_context(|| format!("写入 {} 失败", temp.display()))?;
+        std::fs::rename(&temp, &self.path)
+            .with_context(|| format!("保存管线缓存 {} 失败", self.path.display()))?;
+        Ok(())
+    }
+}
+
+impl Drop for PipelineCacheFile {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("{e:#}");
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! 每帧临时资源的复用池
+//!
+//! 后处理、中间结果这类每帧都要用一次的纹理和缓冲区，不必每帧重新分配：
+//! 按描述符（标签除外）查找上一帧留下的同样规格的资源，找不到才新建。
+//!
+//! ```ignore
+//! let texture = self.pool.texture(&descriptor);
+//! // ... 这一帧里使用 texture
+//! self.pool.end_frame();
+//! ```
+//!
+//! 同一帧里拿到的资源互不相同，[`TransientPool::end_frame`] 之后全部回到池里。
+//! 连续 `max_idle_frames` 帧没有用到的资源会被释放，比如窗口大小变化后旧尺寸的纹理。
+//! 资源通过 [`ResourceTracker`] 创建，显存统计里能看到池子占了多少。
+use std::collections::HashMap;
+
+use crate::resources::{ResourceTracker, Tracked};
+
+/// 决定纹理能否复用的字段，和 [`wgpu::TextureDescriptor`] 相同但不含标签
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TextureKey {
+    size: wgpu::Extent3d,
+    mip_level_count: u32,
+    sample_count: u32,
+    dimension: wgpu::TextureDimension,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    view_formats: Vec<wgpu::TextureFormat>,
+}
+
+impl TextureKey {
+    fn new(descriptor: &wgpu::TextureDescriptor) -> Self {
+        Self {
+            size: descriptor.size,
+            mip_level_count: descriptor.mip_level_count,
+            sample_count: descriptor.sample_count,
+            dimension: descriptor.dimension,
+            format: descriptor.format,
+            usage: descriptor.usage,
+            view_formats: descriptor.view_formats.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BufferKey {
+    size: wgpu::BufferAddress,
+    usage: wgpu::BufferUsages,
+}
+
+struct Entry<T> {
+    resource: Tracked<T>,
+    last_used: u64,
+    in_use: bool,
+}
+
+/// 池子的当前状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub textures: usize,
+    pub buffers: usize,
+    /// 从池里拿到现成资源的次数
+    pub hits: u64,
+    /// 需要新建的次数
+    pub misses: u64,
+    pub evicted: u64,
+}
+
+pub struct TransientPool {
+    resources: ResourceTracker,
+    max_idle_frames: u64,
+    frame: u64,
+    textures: HashMap<TextureKey, Vec<Entry<wgpu::Texture>>>,
+    buffers: HashMap<BufferKey, Vec<Entry<wgpu::Buffer>>>,
+    stats: PoolStats,
+}
+
+impl TransientPool {
+    /// `max_idle_frames` 帧没有用到的资源会被释放
+    pub fn new(resources: &ResourceTracker, max_idle_frames: u64) -> Self {
+        Self {
+            resources: resources.clone(),
+            max_idle_frames,
+            frame: 0,
+            textures: HashMap::new(),
+            buffers: HashMap::new(),
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// 这一帧用的纹理，规格相同的纹理会复用，标签沿用第一次创建时的
+    pub fn texture(&mut self, descriptor: &wgpu::TextureDescriptor) -> wgpu::Texture {
+        let entries = self
+            .textures
+            .entry(TextureKey::new(descriptor))
+            .or_default();
+        acquire(entries, self.frame, &mut self.stats, || {
+            self.resources.create_texture(descriptor)
+        })
+    }
+
+    /// 这一帧用的缓冲区，不支持 `mapped_at_creation`，内容由调用方写入
+    pub fn buffer(&mut self, descriptor: &wgpu::BufferDescriptor) -> wgpu::Buffer {
+        assert!(
+            !descriptor.mapped_at_creation,
+            "复用的缓冲区不能在创建时映射"
+        );
+        let key = BufferKey {
+            size: descriptor.size,
+            usage: descriptor.usage,
+        };
+        let entries = self.buffers.entry(key).or_default();
+        acquire(entries, self.frame, &mut self.stats, || {
+            self.resources.create_buffer(descriptor)
+        })
+    }
+
+    /// 每帧结束时调用：这一帧拿出去的资源全部回到池里，释放闲置太久的
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+        let (frame, max_idle) = (self.frame, self.max_idle_frames);
+        self.stats.evicted += recycle(&mut self.textures, frame, max_idle);
+        self.stats.evicted += recycle(&mut self.buffers, frame, max_idle);
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            textures: self.textures.values().map(Vec::len).sum(),
+            buffers: self.buffers.values().map(Vec::len).sum(),
+            ..self.stats
+        }
+    }
+
+    /// 释放池里的所有资源
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.buffers.clear();
+    }
+}
+
+fn acquire<T: Clone>(
+    entries: &mut Vec<Entry<T>>,
+    frame: u64,
+    stats: &mut PoolStats,
+    create: impl FnOnce() -> Tracked<T>,
+) -> T {
+    let index = match entries.iter().position(|entry| !entry.in_use) {
+        Some(index) => {
+            stats.hits += 1;
+            index
+        }
+        None => {
+            stats.misses += 1;
+            entries.push(Entry {
+                resource: create(),
+                last_used: frame,
+                in_use: false,
+            });
+            entries.len() - 1
+        }
+    };
+    let entry = &mut entries[index];
+    entry.in_use = true;
+    entry.last_used = frame;
+    (*entry.resource).clone()
+}
+
+/// 返回释放的数量
+fn recycle<K, T>(map: &mut HashMap<K, Vec<Entry<T>>>, frame: u64, max_idle: u64) -> u64 {
+    let mut evicted = 0;
+    map.retain(|_, entries| {
+        let before = entries.len();
+        // 帧号已经加一，闲置的帧数是 frame - last_used - 1，达到 max_idle 就释放
+        entries.retain(|entry| frame - entry.last_used <= max_idle);
+        evicted += (before - entries.len()) as u64;
+        for entry in entries.iter_mut() {
+            entry.in_use = false;
+        }
+        !entries.is_empty()
+    });
+    evicted
+}
@@ -0,0 +1,588 @@
+//! GPU 计时
+//!
+//! 设备打开了 `TIMESTAMP_QUERY` 时，每个具名的通道在开始和结束处各写一个时间戳，
+//! 帧末解析到缓冲区里异步读回，不会让 CPU 等 GPU。
+//! 不支持时退回 CPU 计时：记录提交的时刻和 `on_submitted_work_done` 回调的时刻，
+//! 只能得到整次提交的耗时，同一次提交里的几个通道合并成一条记录（名字用 ` + ` 连起来）。
+//!
+//! ```ignore
+//! let mut encoder = device.create_command_encoder(&Default::default());
+//! {
+//!     let mut pass = profiler.begin_compute_pass(&mut encoder, "粒子更新");
+//!     // ...
+//! }
+//! profiler.resolve(&mut encoder);
+//! queue.submit(Some(encoder.finish()));
+//! profiler.end_frame(&device, &queue);
+//! ```
+//!
+//! 结果可以记成日志汇总（[`GpuProfiler::log_summary`]），也可以写成 CSV 或者
+//! chrome://tracing 能打开的 JSON（[`GpuProfiler::write_reports`]）。
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+
+/// 设置后程序退出时把计时结果写到这个目录
+pub const PROFILE_OUTPUT_ENV: &str = "GPU_PROFILE_OUTPUT";
+
+/// 最多保留多少帧的历史
+const HISTORY_FRAMES: usize = 4096;
+
+/// 计时结果的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingSource {
+    /// GPU 时间戳，每个通道单独计时
+    Gpu,
+    /// CPU 计时，整次提交一条记录
+    Cpu,
+}
+
+/// 一个通道的耗时
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+    pub name: String,
+    /// 相对于第一次计时的开始时间
+    pub start_ms: f64,
+    pub duration_ms: f64,
+}
+
+/// 一帧里所有通道的耗时
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTimings {
+    pub frame: u64,
+    pub source: TimingSource,
+    pub passes: Vec<PassTiming>,
+}
+
+/// 同名通道的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassSummary {
+    pub name: String,
+    pub count: usize,
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+struct Queries {
+    /// 需要时创建新的读回缓冲区
+    device: wgpu::Device,
+    set: wgpu::QuerySet,
+    /// `resolve_query_set` 的目标，不能直接映射
+    resolve: wgpu::Buffer,
+    /// 可以映射的读回缓冲区，读完放回来复用
+    free: Vec<wgpu::Buffer>,
+    /// 每个时间戳刻度多少纳秒
+    period: f64,
+    /// 第一次读到的时间戳，之后的开始时间都相对它
+    epoch: Option<u64>,
+}
+
+/// 读回缓冲区的映射状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapState {
+    Pending,
+    Mapped,
+    /// 映射失败的帧没有结果，但不能挡住后面的帧
+    Failed,
+}
+
+enum InFlight {
+    Gpu {
+        buffer: wgpu::Buffer,
+        state: Arc<Mutex<MapState>>,
+    },
+    Cpu {
+        submitted: Instant,
+        done: Arc<Mutex<Option<Instant>>>,
+    },
+}
+
+struct Frame {
+    index: u64,
+    names: Vec<String>,
+    in_flight: InFlight,
+}
+
+pub struct GpuProfiler {
+    /// `None` 表示退回 CPU 计时
+    queries: Option<Queries>,
+    max_passes: u32,
+    /// 这一帧已经记录的通道
+    names: Vec<String>,
+    /// 这一帧解析好、还没提交映射的读回缓冲区
+    readback: Option<wgpu::Buffer>,
+    frames: VecDeque<Frame>,
+    history: VecDeque<FrameTimings>,
+    frame: u64,
+    epoch: Instant,
+    warned_overflow: bool,
+}
+
+impl GpuProfiler {
+    /// `max_passes` 是每帧最多计时的通道数，超出的通道不计时
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, max_passes: u32) -> Self {
+        let queries = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| Queries {
+                device: device.clone(),
+                set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("计时查询集"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: max_passes * 2,
+                }),
+                resolve: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("计时解析缓冲区"),
+                    size: u64::from(max_passes) * 2 * 8,
+                    usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                free: Vec::new(),
+                period: f64::from(queue.get_timestamp_period()),
+                epoch: None,
+            });
+        Self {
+            queries,
+            max_passes,
+            names: Vec::new(),
+            readback: None,
+            frames: VecDeque::new(),
+            history: VecDeque::new(),
+            frame: 0,
+            epoch: Instant::now(),
+            warned_overflow: false,
+        }
+    }
+
+    pub fn source(&self) -> TimingSource {
+        match self.queries {
+            Some(_) => TimingSource::Gpu,
+            None => TimingSource::Cpu,
+        }
+    }
+
+    /// 记录一个通道，返回这个通道的两个时间戳位置；CPU 计时或者超出上限时返回 `None`
+    fn record(&mut self, name: &str) -> Option<(&wgpu::QuerySet, u32)> {
+        if self.names.len() as u32 >= self.max_passes {
+            if !self.warned_overflow {
+                tracing::warn!(
+                    pass = name,
+                    "每帧最多计时 {} 个通道，这个通道不计时",
+                    self.max_passes
+                );
+                self.warned_overflow = true;
+            }
+            return None;
+        }
+        let index = self.names.len() as u32 * 2;
+        self.names.push(name.to_string());
+        self.queries.as_ref().map(|queries| (&queries.set, index))
+    }
+
+    /// 填进 `ComputePassDescriptor::timestamp_writes`
+    pub fn compute_timestamp_writes(
+        &mut self,
+        name: &str,
+    ) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        self.record(name)
+            .map(|(query_set, index)| wgpu::ComputePassTimestampWrites {
+                query_set,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: Some(index + 1),
+            })
+    }
+
+    /// 填进 `RenderPassDescriptor::timestamp_writes`
+    pub fn render_timestamp_writes(
+        &mut self,
+        name: &str,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        self.record(name)
+            .map(|(query_set, index)| wgpu::RenderPassTimestampWrites {
+                query_set,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: Some(index + 1),
+            })
+    }
+
+    /// 开始一个计时的计算通道
+    pub fn begin_compute_pass<'encoder>(
+        &mut self,
+        encoder: &'encoder mut wgpu::CommandEncoder,
+        name: &str,
+    ) -> wgpu::ComputePass<'encoder> {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(name),
+            timestamp_writes: self.compute_timestamp_writes(name),
+        })
+    }
+
+    /// 把这一帧的时间戳解析到读回缓冲区，在 `encoder.finish()` 之前调用
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(queries) = &mut self.queries else {
+            return;
+        };
+        if self.names.is_empty() || self.readback.is_some() {
+            return;
+        }
+        let count = self.names.len() as u32 * 2;
+        let size = u64::from(count) * 8;
+        encoder.resolve_query_set(&queries.set, 0..count, &queries.resolve, 0);
+        let readback = queries.free.pop().unwrap_or_else(|| {
+            queries.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("计时读回缓冲区"),
+                size: queries.resolve.size(),
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+        encoder.copy_buffer_to_buffer(&queries.resolve, 0, &readback, 0, size);
+        self.readback = Some(readback);
+    }
+
+    /// 提交之后调用：开始异步读回这一帧的结果，顺便收集已经完成的帧
+    pub fn end_frame(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let names = std::mem::take(&mut self.names);
+        if !names.is_empty() {
+            let in_flight = match self.readback.take() {
+                Some(buffer) => {
+                    let state = Arc::new(Mutex::new(MapState::Pending));
+                    let slot = state.clone();
+                    let size = names.len() as u64 * 2 * 8;
+                    buffer
+                        .slice(..size)
+                        .map_async(wgpu::MapMode::Read, move |result| {
+                            *slot.lock().unwrap() = match result {
+                                Ok(()) => MapState::Mapped,
+                                Err(_) => MapState::Failed,
+                            };
+                        });
+                    Some(InFlight::Gpu { buffer, state })
+                }
+                None if self.queries.is_none() => {
+                    let done = Arc::new(Mutex::new(None));
+                    let slot = done.clone();
+                    queue.on_submitted_work_done(move || {
+                        *slot.lock().unwrap() = Some(Instant::now());
+                    });
+                    Some(InFlight::Cpu {
+                        submitted: Instant::now(),
+                        done,
+                    })
+                }
+                // 开启了时间戳却忘了调用 resolve，这一帧没有结果
+                None => None,
+            };
+            if let Some(in_flight) = in_flight {
+                self.frames.push_back(Frame {
+                    index: self.frame,
+                    names,
+                    in_flight,
+                });
+            }
+        }
+        self.frame += 1;
+        let _ = device.poll(wgpu::PollType::Poll);
+        self.collect();
+    }
+
+    /// 等待所有在途的帧完成，离屏程序结束前调用
+    pub fn wait(&mut self, device: &wgpu::Device) {
+        let _ = device.poll(wgpu::PollType::wait_indefinitely());
+        self.collect();
+    }
+
+    /// 按帧顺序收集已经完成的结果，映射失败的帧直接丢掉
+    fn collect(&mut self) {
+        while let Some(frame) = self.frames.front() {
+            // `None` 表示这一帧读回失败
+            let passes = match &frame.in_flight {
+                InFlight::Gpu { buffer, state } => match *state.lock().unwrap() {
+                    MapState::Pending => break,
+                    MapState::Failed => None,
+                    MapState::Mapped => {
+                        let size = frame.names.len() as u64 * 2 * 8;
+                        let timestamps: Vec<u64> =
+                            bytemuck::pod_collect_to_vec(&buffer.slice(..size).get_mapped_range());
+                        buffer.unmap();
+                        let queries = self.queries.as_mut().expect("GPU 帧一定有查询集");
+                        Some(gpu_passes(queries, &frame.names, &timestamps))
+                    }
+                },
+                InFlight::Cpu { submitted, done } => {
+                    let Some(done) = *done.lock().unwrap() else {
+                        break;
+                    };
+                    Some(vec![PassTiming {
+                        name: frame.names.join(" + "),
+                        start_ms: millis(*submitted - self.epoch),
+                        duration_ms: millis(done - *submitted),
+                    }])
+                }
+            };
+            let frame = self.frames.pop_front().unwrap();
+            if let (InFlight::Gpu { buffer, .. }, Some(queries)) =
+                (frame.in_flight, &mut self.queries)
+            {
+                queries.free.push(buffer);
+            }
+            let Some(passes) = passes else {
+                tracing::warn!(frame = frame.index, "计时结果读回失败，丢掉这一帧");
+                continue;
+            };
+            if self.history.len() == HISTORY_FRAMES {
+                self.history.pop_front();
+            }
+            self.history.push_back(FrameTimings {
+                frame: frame.index,
+                source: self.source(),
+                passes,
+            });
+        }
+    }
+
+    /// 已经完成的帧，按帧号排列
+    pub fn history(&self) -> impl Iterator<Item = &FrameTimings> {
+        self.history.iter()
+    }
+
+    /// 下一帧的帧号
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// 最近完成的一帧
+    pub fn latest(&self) -> Option<&FrameTimings> {
+        self.history.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// 按通道名汇总，顺序是第一次出现的顺序
+    pub fn summary(&self) -> Vec<PassSummary> {
+        self.summary_since(0)
+    }
+
+    /// 只汇总帧号不小于 `frame` 的帧，用于定期打印最近一段时间的耗时
+    pub fn summary_since(&self, frame: u64) -> Vec<PassSummary> {
+        let mut summaries: Vec<PassSummary> = Vec::new();
+        let frames = self.history.iter().filter(|f| f.frame >= frame);
+        for pass in frames.flat_map(|frame| &frame.passes) {
+            match summaries.iter_mut().find(|s| s.name == pass.name) {
+                Some(summary) => {
+                    // mean_ms 先当总和用，最后再除
+                    summary.count += 1;
+                    summary.mean_ms += pass.duration_ms;
+                    summary.min_ms = summary.min_ms.min(pass.duration_ms);
+                    summary.max_ms = summary.max_ms.max(pass.duration_ms);
+                }
+                None => summaries.push(PassSummary {
+                    name: pass.name.clone(),
+                    count: 1,
+                    mean_ms: pass.duration_ms,
+                    min_ms: pass.duration_ms,
+                    max_ms: pass.duration_ms,
+                }),
+            }
+        }
+        for summary in &mut summaries {
+            summary.mean_ms /= summary.count as f64;
+        }
+        summaries
+    }
+
+    /// 每个通道的平均、最小、最大耗时各记一条 info 日志
+    pub fn log_summary(&self) {
+        let source = match self.source() {
+            TimingSource::Gpu => "GPU 时间戳",
+            TimingSource::Cpu => "CPU 计时",
+        };
+        let frames = self.history.len();
+        for s in self.summary() {
+            tracing::info!(
+                source,
+                frames,
+                pass = s.name,
+                "平均 {:.3} ms  最小 {:.3} ms  最大 {:.3} ms",
+                s.mean_ms,
+                s.min_ms,
+                s.max_ms
+            );
+        }
+    }
+
+    /// `frame,source,pass,start_ms,duration_ms`，每个通道一行
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("frame,source,pass,start_ms,duration_ms\n");
+        for frame in &self.history {
+            for pass in &frame.passes {
+                let _ = writeln!(
+                    out,
+                    "{},{:?},{},{:.6},{:.6}",
+                    frame.frame,
+                    frame.source,
+                    csv_field(&pass.name),
+                    pass.start_ms,
+                    pass.duration_ms
+                );
+            }
+        }
+        out
+    }
+
+    /// chrome://tracing（或者 Perfetto）能直接打开的 JSON
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+        for frame in &self.history {
+            for pass in &frame.passes {
+                events.push(format!(
+                    r#"{{"name":{},"cat":"{:?}","ph":"X","ts":{:.3},"dur":{:.3},"pid":0,"tid":0,"args":{{"frame":{}}}}}"#,
+                    json_string(&pass.name),
+                    frame.source,
+                    pass.start_ms * 1000.0,
+                    pass.duration_ms * 1000.0,
+                    frame.frame
+                ));
+            }
+        }
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+
+    /// 在 `dir` 下写出 `{name}.csv` 和 `{name}.json`，返回两个文件的路径
+    pub fn write_reports(&self, dir: &Path, name: &str) -> Result<[PathBuf; 2]> {
+        std::fs::create_dir_all(dir).with_context(|| format!("创建 {} 失败", dir.display()))?;
+        let csv = dir.join(format!("{name}.csv"));
+        let json = dir.join(format!("{name}.json"));
+        std::fs::write(&csv, self.to_csv())
+            .with_context(|| format!("写入 {} 失败", csv.display()))?;
+        std::fs::write(&json, self.to_chrome_trace())
+            .with_context(|| format!("写入 {} 失败", json.display()))?;
+        Ok([csv, json])
+    }
+
+    /// 设置了 `GPU_PROFILE_OUTPUT` 时写出报告，程序退出前调用
+    pub fn write_reports_from_env(&self, name: &str) {
+        let Some(dir) = std::env::var_os(PROFILE_OUTPUT_ENV) else {
+            return;
+        };
+        match self.write_reports(Path::new(&dir), name) {
+            Ok([csv, json]) => {
+                tracing::info!(csv = %csv.display(), json = %json.display(), "计时结果已写入")
+            }
+            Err(e) => tracing::warn!("{e:#}"),
+        }
+    }
+}
+
+fn gpu_passes(queries: &mut Queries, names: &[String], timestamps: &[u64]) -> Vec<PassTiming> {
+    let epoch = *queries.epoch.get_or_insert(timestamps[0]);
+    let to_ms = |ticks: u64| ticks as f64 * queries.period / 1_000_000.0;
+    names
+        .iter()
+        .zip(timestamps.chunks_exact(2))
+        .map(|(name, pair)| PassTiming {
+            name: name.clone(),
+            start_ms: to_ms(pair[0].saturating_sub(epoch)),
+            // 有的驱动偶尔会给出乱序的时间戳，按 0 处理
+            duration_ms: to_ms(pair[1].saturating_sub(pair[0])),
+        })
+        .collect()
+}
+
+fn millis(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContextOptions, GpuContext};
+
+    /// 跑一帧：一个空的计时计算通道
+    fn frame(profiler: &mut GpuProfiler, gpu: &GpuContext) {
+        let mut encoder = gpu.device.create_command_encoder(&Default::default());
+        drop(profiler.begin_compute_pass(&mut encoder, "通道"));
+        profiler.resolve(&mut encoder);
+        gpu.queue.submit(Some(encoder.finish()));
+        profiler.end_frame(&gpu.device, &gpu.queue);
+    }
+
+    #[test]
+    fn failed_readback_is_dropped_without_stalling_later_frames() {
+        let options = ContextOptions {
+            optional_features: wgpu::Features::TIMESTAMP_QUERY,
+            pipeline_cache_dir: None,
+            ..Default::default()
+        };
+        let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+            eprintln!("没有可用的适配器，跳过");
+            return;
+        };
+        let mut profiler = GpuProfiler::new(&gpu.device, &gpu.queue, 1);
+        if profiler.source() != TimingSource::Gpu {
+            eprintln!("不支持时间戳查询，跳过");
+            return;
+        }
+
+        // 第 0 帧的映射失败了，比如设备暂时丢失
+        let failed = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        profiler.frames.push_back(Frame {
+            index: 0,
+            names: vec!["通道".to_string()],
+            in_flight: InFlight::Gpu {
+                buffer: failed.clone(),
+                state: Arc::new(Mutex::new(MapState::Failed)),
+            },
+        });
+        profiler.frame = 1;
+
+        frame(&mut profiler, &gpu);
+        frame(&mut profiler, &gpu);
+        profiler.wait(&gpu.device);
+
+        assert!(profiler.frames.is_empty());
+        let frames: Vec<u64> = profiler.history().map(|f| f.frame).collect();
+        assert_eq!(frames, [1, 2]);
+        // 失败那一帧的缓冲区回到了池子里，可能已经被后面的帧用过一次
+        assert!(profiler.queries.as_ref().unwrap().free.contains(&failed));
+    }
+}
@@ -0,0 +1,347 @@
+//! 显存资源统计
+//!
+//! 通过 [`ResourceTracker`] 创建的缓冲区和纹理会记下标签、大小和存活的帧数，
+//! 返回的 [`Tracked`] 解引用就是原来的资源，释放时自动从统计里去掉。
+//!
+//! ```ignore
+//! let texture = self.gpu.resources.create_texture(&descriptor);
+//! // 每帧结束时
+//! self.gpu.resources.end_frame();
+//! ```
+//!
+//! [`ResourceTracker::end_frame`] 每隔 [`REPORT_INTERVAL`] 记一条 info 日志：还活着的资源数量和字节数、
+//! 这段时间平均每帧新建了多少，后端支持时附上 wgpu 分配器的统计（`generate_allocator_report`）。
+//! 同一个标签连续 [`PER_FRAME_WARNING_FRAMES`] 帧都在新建资源时记一条警告，提示改成复用。
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use wgpu::util::DeviceExt;
+
+/// 定期报告的间隔
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 连续这么多帧都新建同一个标签的资源就警告
+pub const PER_FRAME_WARNING_FRAMES: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Buffer,
+    Texture,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ResourceKind::Buffer => "缓冲区",
+            ResourceKind::Texture => "纹理",
+        })
+    }
+}
+
+/// 某一类资源当前的数量和字节数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiveStats {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// 同一个标签的资源从开始到现在的统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelStats {
+    pub created: u64,
+    pub released: u64,
+    pub bytes_created: u64,
+    /// 已释放的资源平均存活了多少帧
+    pub mean_lifetime_frames: f64,
+    /// 连续新建的帧数，这一帧没有新建时归零
+    pub streak: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResourceStats {
+    pub frame: u64,
+    pub buffers: LiveStats,
+    pub textures: LiveStats,
+    pub labels: BTreeMap<(ResourceKind, String), LabelStats>,
+}
+
+impl ResourceStats {
+    pub fn label(&self, kind: ResourceKind, label: &str) -> Option<&LabelStats> {
+        self.labels.get(&(kind, label.to_string()))
+    }
+}
+
+struct Live {
+    key: (ResourceKind, String),
+    bytes: u64,
+    frame: u64,
+}
+
+#[derive(Default)]
+struct Label {
+    created: u64,
+    released: u64,
+    bytes_created: u64,
+    lifetime_frames: u64,
+    streak: u64,
+    created_this_frame: bool,
+    warned: bool,
+}
+
+struct Inner {
+    next_id: u64,
+    live: HashMap<u64, Live>,
+    labels: BTreeMap<(ResourceKind, String), Label>,
+    frame: u64,
+    last_report: (Instant, u64),
+    /// 上次报告之后新建的数量和字节数
+    created_since_report: (u64, u64),
+}
+
+impl Inner {
+    fn live_stats(&self, kind: ResourceKind) -> LiveStats {
+        self.live.values().filter(|live| live.key.0 == kind).fold(
+            LiveStats::default(),
+            |stats, live| LiveStats {
+                count: stats.count + 1,
+                bytes: stats.bytes + live.bytes,
+            },
+        )
+    }
+}
+
+/// 记录通过它创建的缓冲区和纹理，可以随意克隆，克隆之间共享统计
+#[derive(Clone)]
+pub struct ResourceTracker {
+    device: wgpu::Device,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ResourceTracker {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            device: device.clone(),
+            inner: Arc::new(Mutex::new(Inner {
+                next_id: 0,
+                live: HashMap::new(),
+                labels: BTreeMap::new(),
+                frame: 0,
+                last_report: (Instant::now(), 0),
+                created_since_report: (0, 0),
+            })),
+        }
+    }
+
+    pub fn create_buffer(&self, descriptor: &wgpu::BufferDescriptor) -> Tracked<wgpu::Buffer> {
+        let buffer = self.device.create_buffer(descriptor);
+        self.track(
+            buffer,
+            ResourceKind::Buffer,
+            descriptor.label,
+            descriptor.size,
+        )
+    }
+
+    pub fn create_buffer_init(
+        &self,
+        descriptor: &wgpu::util::BufferInitDescriptor,
+    ) -> Tracked<wgpu::Buffer> {
+        let buffer = self.device.create_buffer_init(descriptor);
+        let size = buffer.size();
+        self.track(buffer, ResourceKind::Buffer, descriptor.label, size)
+    }
+
+    pub fn create_texture(&self, descriptor: &wgpu::TextureDescriptor) -> Tracked<wgpu::Texture> {
+        let texture = self.device.create_texture(descriptor);
+        let bytes = texture_bytes(descriptor);
+        self.track(texture, ResourceKind::Texture, descriptor.label, bytes)
+    }
+
+    fn track<T>(
+        &self,
+        resource: T,
+        kind: ResourceKind,
+        label: Option<&str>,
+        bytes: u64,
+    ) -> Tracked<T> {
+        let key = (kind, label.unwrap_or("<未命名>").to_string());
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let frame = inner.frame;
+        let stats = inner.labels.entry(key.clone()).or_default();
+        stats.created += 1;
+        stats.bytes_created += bytes;
+        stats.created_this_frame = true;
+        inner.created_since_report.0 += 1;
+        inner.created_since_report.1 += bytes;
+        inner.live.insert(id, Live { key, bytes, frame });
+        Tracked {
+            resource,
+            id,
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// 每帧结束时调用：检查每帧都在新建的资源，到时间了就报告一次
+    pub fn end_frame(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.frame += 1;
+        for ((kind, label), stats) in &mut inner.labels {
+            stats.streak = if stats.created_this_frame {
+                stats.streak + 1
+            } else {
+                0
+            };
+            stats.created_this_frame = false;
+            if stats.streak >= PER_FRAME_WARNING_FRAMES && !stats.warned {
+                stats.warned = true;
+                tracing::warn!(
+                    target: "gpu::resources",
+                    kind = %kind,
+                    label = label.as_str(),
+                    "连续 {} 帧都在新建，平均每个 {}，考虑创建一次后复用",
+                    stats.streak,
+                    format_bytes(stats.bytes_created / stats.created),
+                );
+            }
+        }
+        let (last_time, last_frame) = inner.last_report;
+        if last_time.elapsed() >= REPORT_INTERVAL {
+            let frames = (inner.frame - last_frame).max(1);
+            let (created, bytes) = inner.created_since_report;
+            let buffers = inner.live_stats(ResourceKind::Buffer);
+            let textures = inner.live_stats(ResourceKind::Texture);
+            inner.last_report = (Instant::now(), inner.frame);
+            inner.created_since_report = (0, 0);
+            drop(inner);
+            tracing::info!(
+                target: "gpu::resources",
+                "存活：缓冲区 {} 个 {}，纹理 {} 个 {}；平均每帧新建 {:.1} 个 {}",
+                buffers.count,
+                format_bytes(buffers.bytes),
+                textures.count,
+                format_bytes(textures.bytes),
+                created as f64 / frames as f64,
+                format_bytes(bytes / frames),
+            );
+            self.log_allocator_report();
+        }
+    }
+
+    /// 后端支持时记一条 wgpu 分配器的统计，GL 等后端什么也不做
+    pub fn log_allocator_report(&self) {
+        if let Some(report) = self.device.generate_allocator_report() {
+            tracing::info!(
+                target: "gpu::resources",
+                "分配器：{} 个分配，已用 {}，保留 {}（{} 个内存块）",
+                report.allocations.len(),
+                format_bytes(report.total_allocated_bytes),
+                format_bytes(report.total_reserved_bytes),
+                report.blocks.len(),
+            );
+        }
+    }
+
+    pub fn stats(&self) -> ResourceStats {
+        let inner = self.inner.lock().unwrap();
+        let labels = inner
+            .labels
+            .iter()
+            .map(|(key, label)| {
+                let stats = LabelStats {
+                    created: label.created,
+                    released: label.released,
+                    bytes_created: label.bytes_created,
+                    mean_lifetime_frames: if label.released == 0 {
+                        0.0
+                    } else {
+                        label.lifetime_frames as f64 / label.released as f64
+                    },
+                    streak: label.streak,
+                };
+                (key.clone(), stats)
+            })
+            .collect();
+        ResourceStats {
+            frame: inner.frame,
+            buffers: inner.live_stats(ResourceKind::Buffer),
+            textures: inner.live_stats(ResourceKind::Texture),
+            labels,
+        }
+    }
+}
+
+/// 被统计的资源，解引用得到原来的 wgpu 资源，释放时更新统计
+pub struct Tracked<T> {
+    resource: T,
+    id: u64,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.resource
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock().unwrap();
+        let frame = inner.frame;
+        if let Some(live) = inner.live.remove(&self.id)
+            && let Some(label) = inner.labels.get_mut(&live.key)
+        {
+            label.released += 1;
+            label.lifetime_frames += frame - live.frame;
+        }
+    }
+}
+
+/// 纹理占用的字节数：所有 mip 层级、数组层和采样数，压缩格式按块计算
+pub fn texture_bytes(descriptor: &wgpu::TextureDescriptor) -> u64 {
+    let format = descriptor.format;
+    // 深度模板组合格式没有统一的块大小，分别加上两个分量
+    let block_bytes = format.block_copy_size(None).unwrap_or_else(|| {
+        [
+            wgpu::TextureAspect::DepthOnly,
+            wgpu::TextureAspect::StencilOnly,
+        ]
+        .into_iter()
+        .filter_map(|aspect| format.block_copy_size(Some(aspect)))
+        .sum::<u32>()
+        .max(4)
+    });
+    let (block_width, block_height) = format.block_dimensions();
+    let bytes: u64 = (0..descriptor.mip_level_count)
+        .filter_map(|level| descriptor.mip_level_size(level))
+        .map(|size| {
+            let blocks_x = u64::from(size.width.div_ceil(block_width));
+            let blocks_y = u64::from(size.height.div_ceil(block_height));
+            blocks_x * blocks_y * u64::from(size.depth_or_array_layers) * u64::from(block_bytes)
+        })
+        .sum();
+    bytes * u64::from(descriptor.sample_count)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
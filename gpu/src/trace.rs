@@ -0,0 +1,372 @@
+//! API 调用记录
+//!
+//! 课程在别人的机器上出问题时，让对方设置环境变量 `WGPU_TRACE` 再运行一次，把生成的目录发回来：
+//!
+//! ```text
+//! WGPU_TRACE=wgpu_trace cargo run -p launcher -- texture
+//! cargo run -p gpu --example trace_summary -- wgpu_trace
+//! ```
+//!
+//! wgpu 28 暂时移除了自带的 API 跟踪（`Trace::Directory` 被放在一个没有对外开放的特性后面，
+//! 传了也只会报错，见 gfx-rs/wgpu#5974），设备描述符里只能写 `Trace::Off`。
+//! 这里改为收集 wgpu-core 在 trace 级别打出的 API 日志（每个 `Device::create_*`、
+//! `Queue::submit`、通道里的每条命令都有一条，资源带着标签），写进 [`TRACE_FILE`]，
+//! 同时记下 wgpu 自己的警告和错误，以及所有 warn 以上的 tracing 事件（包括校验错误）。
+//!
+//! 记录是订阅者里单独的一层，有自己的过滤规则，不改 `RUST_LOG` 的规则也不改 `log` 的全局上限：
+//! 没在记录时这一层什么都不要，开始记录后它要 trace 级别，终端输出仍然只按 `RUST_LOG` 显示。
+//!
+//! 这依赖 wgpu-core 在默认特性下用 `api_log!` 以 trace 级别打 API 日志。
+//! 打开 wgpu-core 的 `api_log_info` 特性后这些日志变成 info 级别，目标不变，仍会被记录；
+//! 但如果以后 wgpu-core 改了日志的目标或者去掉了 `api_log!`，记录里就没有 API 调用了，
+//! `headless_context_records_api_calls` 这个测试会失败。
+//!
+//! 文件按行写入，程序中途崩溃也能保留崩溃前的记录。
+//! 这份记录不能像 wgpu 以前的跟踪文件那样重放，[`TraceSummary`] 负责按命令统计和列出问题。
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    fs::File,
+    io::{LineWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use tracing::{
+    Event, Level, Metadata, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    subscriber::Interest,
+};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    Layer,
+    layer::{self, Filter},
+    registry::LookupSpan,
+};
+
+/// 打开记录的环境变量，值是输出目录
+pub const TRACE_DIR_ENV: &str = "WGPU_TRACE";
+
+/// 目录下的记录文件名
+pub const TRACE_FILE: &str = "api_trace.log";
+
+/// `WGPU_TRACE` 指定的目录，没有设置时返回 `None`
+pub fn dir_from_env() -> Option<PathBuf> {
+    std::env::var_os(TRACE_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+struct Capture {
+    path: PathBuf,
+    file: LineWriter<File>,
+    start: Instant,
+    devices: usize,
+}
+
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+/// 日志的热路径上只读这个标志，不用每条都加锁
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// 开始记录，返回记录文件的路径
+///
+/// 同一个进程里创建多个设备时（比如启动器切换课程）写进同一个文件，每个设备一段文件头。
+/// 需要 [`logging`](crate::logging) 接管 `log` 的输出，没有初始化时这里会初始化。
+pub fn start_capture(
+    dir: &Path,
+    info: &wgpu::AdapterInfo,
+    features: wgpu::Features,
+) -> Result<PathBuf> {
+    crate::logging::init();
+    let mut capture = CAPTURE.lock().unwrap();
+    if capture.is_none() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("创建 API 记录目录 {} 失败", dir.display()))?;
+        let path = dir.join(TRACE_FILE);
+        let file = File::create(&path).with_context(|| format!("创建 {} 失败", path.display()))?;
+        *capture = Some(Capture {
+            path,
+            file: LineWriter::new(file),
+            start: Instant::now(),
+            devices: 0,
+        });
+    }
+    let capture = capture.as_mut().expect("上面刚创建");
+    capture.devices += 1;
+    let header = format!(
+        "# device: {}\n# adapter: {}\n# backend: {:?}\n# driver: {} {}\n# features: {:?}\n",
+        capture.devices, info.name, info.backend, info.driver, info.driver_info, features
+    );
+    capture
+        .file
+        .write_all(header.as_bytes())
+        .with_context(|| format!("写入 {} 失败", capture.path.display()))?;
+
+    ACTIVE.store(true, Ordering::Release);
+    // 记录层的级别上限变成了 trace，让 tracing 重新合并各层的上限
+    tracing::callsite::rebuild_interest_cache();
+    Ok(capture.path.clone())
+}
+
+/// 停止记录，返回记录文件的路径
+pub fn stop_capture() -> Option<PathBuf> {
+    ACTIVE.store(false, Ordering::Release);
+    tracing::callsite::rebuild_interest_cache();
+    let capture = CAPTURE.lock().unwrap().take()?;
+    Some(capture.path)
+}
+
+pub fn is_capturing() -> bool {
+    ACTIVE.load(Ordering::Acquire)
+}
+
+/// 这条日志是否要写进记录：wgpu-core 的全部日志，wgpu 其它模块和其它目标的 warn 以上
+pub(crate) fn wants(target: &str, level: Level) -> bool {
+    is_capturing() && candidate(target, level)
+}
+
+/// 可能要写进记录的调用点：是否真的写由 [`wants`] 每次判断
+fn candidate(target: &str, level: Level) -> bool {
+    level <= Level::WARN || target == "wgpu_core" || target.starts_with("wgpu_core::")
+}
+
+/// 把记录写进文件的层，由 [`logging`](crate::logging) 装进订阅者
+pub(crate) fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    CaptureLayer.with_filter(CaptureFilter)
+}
+
+struct CaptureLayer;
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: layer::Context<'_, S>) {
+        // 从 log 转过来的事件目标都是 "log"，真正的目标在字段里
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut message = MessageWriter::default();
+        event.record(&mut message);
+        record(*metadata.level(), metadata.target(), &message.finish());
+    }
+}
+
+/// 只看自己的规则，和终端输出的过滤规则无关
+struct CaptureFilter;
+
+impl<S> Filter<S> for CaptureFilter {
+    fn enabled(&self, metadata: &Metadata<'_>, _cx: &layer::Context<'_, S>) -> bool {
+        wants(metadata.target(), *metadata.level())
+    }
+
+    fn callsite_enabled(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // 记录随时可能开始，不能把结果缓存成 never；从 log 转过来的调用点目标是 "log"，也要每次判断
+        if metadata.target() == "log" || candidate(metadata.target(), *metadata.level()) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(if is_capturing() {
+            LevelFilter::TRACE
+        } else {
+            LevelFilter::OFF
+        })
+    }
+}
+
+/// 消息在前，其它字段以 ` key=value` 跟在后面；跳过 tracing-log 加的 `log.*` 字段
+#[derive(Default)]
+struct MessageWriter {
+    message: String,
+    fields: String,
+}
+
+impl MessageWriter {
+    fn finish(self) -> String {
+        self.message + &self.fields
+    }
+}
+
+impl Visit for MessageWriter {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{value:?}");
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {name}={value:?}");
+            }
+        }
+    }
+}
+
+pub(crate) fn record(level: Level, target: &str, message: &str) {
+    let mut capture = CAPTURE.lock().unwrap();
+    let Some(capture) = capture.as_mut() else {
+        return;
+    };
+    let elapsed = capture.start.elapsed().as_secs_f64() * 1000.0;
+    // 一条记录占一行，消息里的换行转义掉
+    let message = message.replace('\\', "\\\\").replace('\n', "\\n");
+    let _ = writeln!(capture.file, "{elapsed:.3}\t{level}\t{target}\t{message}");
+}
+
+/// 记录文件里的一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+    pub time_ms: f64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl TraceRecord {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+        let time_ms = fields.next()?.parse().ok()?;
+        let level = fields.next()?.to_string();
+        let target = fields.next()?.to_string();
+        let message = unescape(fields.next()?);
+        Some(Self {
+            time_ms,
+            level,
+            target,
+            message,
+        })
+    }
+
+    /// API 日志以 `Device::create_buffer`、`RenderPass::draw` 这样的名字开头，
+    /// 其它日志返回 `None`
+    pub fn command(&self) -> Option<&str> {
+        let name = self
+            .message
+            .split(|c: char| c.is_whitespace() || c == '(')
+            .next()?;
+        let (owner, method) = name.split_once("::")?;
+        let is_name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_');
+        (is_name(owner) && is_name(method)).then_some(name)
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// 记录文件的统计
+#[derive(Debug, Clone, Default)]
+pub struct TraceSummary {
+    /// 文件头，每个设备一组
+    pub header: Vec<(String, String)>,
+    /// 每种 API 调用的次数
+    pub commands: BTreeMap<String, usize>,
+    /// warn 和 error 级别的记录
+    pub problems: Vec<TraceRecord>,
+    pub records: usize,
+    /// 第一条到最后一条记录的时间跨度
+    pub duration_ms: f64,
+}
+
+impl TraceSummary {
+    /// 读取记录文件，`path` 也可以是 `WGPU_TRACE` 指定的目录
+    pub fn load(path: &Path) -> Result<Self> {
+        let path = if path.is_dir() {
+            path.join(TRACE_FILE)
+        } else {
+            path.to_path_buf()
+        };
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("读取 {} 失败", path.display()))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut summary = Self::default();
+        let mut first = None;
+        for line in text.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                if let Some((key, value)) = header.split_once(": ") {
+                    summary.header.push((key.to_string(), value.to_string()));
+                }
+                continue;
+            }
+            let Some(record) = TraceRecord::parse(line) else {
+                continue;
+            };
+            summary.records += 1;
+            let first = *first.get_or_insert(record.time_ms);
+            summary.duration_ms = record.time_ms - first;
+            if let Some(command) = record.command() {
+                *summary.commands.entry(command.to_string()).or_default() += 1;
+            }
+            if record.level == "WARN" || record.level == "ERROR" {
+                summary.problems.push(record);
+            }
+        }
+        summary
+    }
+
+    pub fn count(&self, command: &str) -> usize {
+        self.commands.get(command).copied().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for TraceSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.header {
+            writeln!(f, "{key}: {value}")?;
+        }
+        writeln!(
+            f,
+            "共 {} 条记录，时间跨度 {:.1} ms",
+            self.records, self.duration_ms
+        )?;
+
+        let mut commands: Vec<_> = self.commands.iter().collect();
+        commands.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        writeln!(f, "API 调用（{} 种）：", commands.len())?;
+        for (command, count) in commands {
+            writeln!(f, "  {count:>8}  {command}")?;
+        }
+
+        writeln!(f, "警告和错误（{} 条）：", self.problems.len())?;
+        for record in &self.problems {
+            writeln!(
+                f,
+                "  {:>10.3} ms {:>5} {}: {}",
+                record.time_ms, record.level, record.target, record.message
+            )?;
+        }
+        Ok(())
+    }
+}
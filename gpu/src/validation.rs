@@ -0,0 +1,71 @@
+//! wgpu 校验错误的捕获
+//!
+//! wgpu 默认遇到没有被错误作用域接住的校验错误会直接 panic。
+//! [`install_error_handler`] 把它们改成 `gpu::validation` 目标下的 error 事件，
+//! [`try_create`] 和两个创建管线的函数在错误作用域里创建资源，出错时返回 `Err`。
+//!
+//! wgpu 的错误信息里带有资源的标签（`label = '渲染管线'`），日志里能直接看出是哪个资源出了问题。
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+
+/// 校验错误事件的目标，可以用 `RUST_LOG=gpu::validation=off` 单独关掉
+pub const TARGET: &str = "gpu::validation";
+
+/// 把设备上没有被错误作用域接住的错误转成日志事件，而不是 panic
+///
+/// [`GpuContext`](crate::GpuContext) 创建设备时已经调用过。
+pub fn install_error_handler(device: &wgpu::Device) {
+    device.on_uncaptured_error(Arc::new(|error| {
+        let kind = match error {
+            wgpu::Error::OutOfMemory { .. } => "内存不足",
+            wgpu::Error::Validation { .. } => "校验",
+            wgpu::Error::Internal { .. } => "内部",
+        };
+        tracing::error!(target: TARGET, kind, "未捕获的错误：{error}");
+    }));
+}
+
+/// 在校验错误作用域里创建资源，出错时记一条 error 事件并返回错误
+///
+/// `label` 只用于日志，一般和资源描述符里的标签相同。
+/// 热重载重建管线时用：新着色器和旧的管线布局对不上时保留旧管线。
+pub fn try_create<T>(
+    device: &wgpu::Device,
+    label: &str,
+    create: impl FnOnce(&wgpu::Device) -> T,
+) -> Result<T> {
+    let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create(device);
+    match futures::executor::block_on(scope.pop()) {
+        Some(error) => {
+            tracing::error!(target: TARGET, label, "创建失败：{error}");
+            Err(anyhow!("创建 {label} 失败：{error}"))
+        }
+        None => Ok(value),
+    }
+}
+
+/// 在错误作用域里创建渲染管线
+pub fn create_render_pipeline(
+    device: &wgpu::Device,
+    descriptor: &wgpu::RenderPipelineDescriptor,
+) -> Result<wgpu::RenderPipeline> {
+    try_create(
+        device,
+        descriptor.label.unwrap_or("渲染管线"),
+        |device| device.create_render_pipeline(descriptor),
+    )
+}
+
+/// 在错误作用域里创建计算管线
+pub fn create_compute_pipeline(
+    device: &wgpu::Device,
+    descriptor: &wgpu::ComputePipelineDescriptor,
+) -> Result<wgpu::ComputePipeline> {
+    try_create(
+        device,
+        descriptor.label.unwrap_or("计算管线"),
+        |device| device.create_compute_pipeline(descriptor),
+    )
+}
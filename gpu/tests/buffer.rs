@@ -0,0 +1,114 @@
+use gpu::{ContextOptions, GpuContext, StorageBuffer, UniformBuffer, buffer::align_to};
+
+/// 没有可用适配器（比如 CI 里没有 GPU 也没有软件渲染）时跳过
+fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let gpu = futures::executor::block_on(GpuContext::headless(&ContextOptions::default())).ok()?;
+    assert!(gpu.is_headless());
+    Some((gpu.device, gpu.queue))
+}
+
+fn read_back(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> Vec<u8> {
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+    staging
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+    staging.slice(..).get_mapped_range().to_vec()
+}
+
+#[test]
+fn align_to_rounds_up() {
+    assert_eq!(align_to(4, 256), 256);
+    assert_eq!(align_to(256, 256), 256);
+    assert_eq!(align_to(257, 256), 512);
+    assert_eq!(align_to(48, 16), 48);
+}
+
+#[test]
+fn dynamic_uniform_offsets_are_aligned() {
+    let Some((device, queue)) = device() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let alignment = device.limits().min_uniform_buffer_offset_alignment;
+    let buffer = UniformBuffer::<[f32; 4]>::new_dynamic(&device, "Instances", 3).unwrap();
+    assert_eq!(buffer.stride(), alignment as u64);
+    assert_eq!(buffer.offset(2), 2 * alignment);
+
+    let entry = buffer.layout_entry(0, wgpu::ShaderStages::VERTEX);
+    let wgpu::BindingType::Buffer {
+        has_dynamic_offset,
+        min_binding_size,
+        ..
+    } = entry.ty
+    else {
+        panic!("不是缓冲区绑定");
+    };
+    assert!(has_dynamic_offset);
+    assert_eq!(min_binding_size.unwrap().get(), 16);
+
+    buffer.update_all(&queue, &[[1.0; 4], [2.0; 4], [3.0; 4]]);
+    let bytes = read_back(&device, &queue, buffer.buffer());
+    for i in 0..3 {
+        let start = (buffer.offset(i) as usize) / 4;
+        let floats: &[f32] = bytemuck::cast_slice(&bytes);
+        assert_eq!(floats[start..start + 4], [(i + 1) as f32; 4]);
+    }
+}
+
+#[test]
+fn uniform_initial_value_and_update() {
+    let Some((device, queue)) = device() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let buffer = UniformBuffer::new(&device, "Delta Time", &0.5f32).unwrap();
+    assert_eq!(
+        read_back(&device, &queue, buffer.buffer()),
+        0.5f32.to_ne_bytes()
+    );
+    buffer.update(&queue, &0.25);
+    assert_eq!(
+        read_back(&device, &queue, buffer.buffer()),
+        0.25f32.to_ne_bytes()
+    );
+}
+
+#[test]
+fn storage_buffer_writes_elements() {
+    let Some((device, queue)) = device() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let buffer = StorageBuffer::from_slice(
+        &device,
+        "Values",
+        &[1u32, 2, 3, 4],
+        wgpu::BufferUsages::empty(),
+    )
+    .unwrap();
+    assert_eq!(buffer.len(), 4);
+    buffer.write(&queue, 2, &[30, 40]);
+    let bytes = read_back(&device, &queue, buffer.buffer());
+    assert_eq!(bytemuck::cast_slice::<u8, u32>(&bytes), [1, 2, 30, 40]);
+}
+
+#[test]
+fn empty_buffer_is_rejected() {
+    let Some((device, _queue)) = device() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    assert!(UniformBuffer::<[u32; 4]>::new_dynamic(&device, "Empty", 0).is_err());
+    assert!(
+        StorageBuffer::<u32>::zeroed(&device, "Empty", 0, wgpu::BufferUsages::empty()).is_err()
+    );
+}
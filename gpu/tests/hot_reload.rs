@@ -0,0 +1,59 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use gpu::{
+    ContextOptions, GpuContext,
+    hot_reload::{HOT_RELOAD_ENV, HotShader, ShaderSource},
+};
+
+const ENTRY: &str = "#include \"common.wgsl\"\n\
+    @compute @workgroup_size(1)\n\
+    fn main() { let x = helper(); }\n";
+const COMMON: &str = "fn helper() -> f32 { return 1.0; }\n";
+
+fn leak(path: PathBuf) -> &'static str {
+    Box::leak(path.to_string_lossy().into_owned().into_boxed_str())
+}
+
+/// 写入文件，修改时间往后拨一秒，不受文件系统时间精度的影响
+fn write(path: &str, code: &str) {
+    std::fs::write(path, code).unwrap();
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(1))
+        .unwrap();
+}
+
+#[test]
+fn broken_include_at_startup_is_still_watched() {
+    // 这个测试单独一个进程，开发模式的开关只在第一次用到时读取
+    unsafe { std::env::set_var(HOT_RELOAD_ENV, "1") };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    })) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+
+    let dir = std::env::temp_dir().join(format!("hot_reload_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = ShaderSource {
+        path: leak(dir.join("entry.wgsl")),
+        embedded: ENTRY,
+        includes: Box::leak(Box::new([(leak(dir.join("common.wgsl")), COMMON)])),
+    };
+    // 磁盘上被包含的文件有语法错误，启动时退回嵌入的版本
+    write(source.path, ENTRY);
+    write(source.includes[0].0, "fn helper() -> f32 { return 1.0 }\n");
+    let mut shader = HotShader::new(&gpu.device, source).unwrap();
+    assert!(!shader.poll(&gpu.device));
+
+    // 只改好被包含的文件也要重新加载
+    write(source.includes[0].0, COMMON);
+    assert!(shader.poll(&gpu.device));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
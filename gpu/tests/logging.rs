@@ -0,0 +1,136 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use gpu::{
+    ContextOptions, GpuContext,
+    logging::{self, EnvFilter},
+    validation,
+};
+use tracing_subscriber::{Layer, layer::SubscriberExt};
+
+fn context() -> Option<GpuContext> {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    futures::executor::block_on(GpuContext::headless(&options)).ok()
+}
+
+/// 入口函数名写错的计算管线，创建时一定会触发校验错误
+fn broken_pipeline_descriptor(module: &wgpu::ShaderModule) -> wgpu::ComputePipelineDescriptor<'_> {
+    wgpu::ComputePipelineDescriptor {
+        label: Some("坏掉的计算管线"),
+        layout: None,
+        module,
+        entry_point: Some("不存在"),
+        compilation_options: Default::default(),
+        cache: None,
+    }
+}
+
+fn empty_module(device: &wgpu::Device) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("空着色器"),
+        source: wgpu::ShaderSource::Wgsl("@compute @workgroup_size(1) fn main() {}".into()),
+    })
+}
+
+/// 把终端输出收集起来的写入端
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Output {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 在只装了终端输出层的订阅者下运行 `f`，返回输出
+fn captured(filter: &str, f: impl FnOnce()) -> String {
+    let output = Output::default();
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::registry()
+        .with(logging::fmt_layer(EnvFilter::new(filter), move || writer.clone(), false).boxed());
+    tracing::subscriber::with_default(subscriber, f);
+    output.text()
+}
+
+#[test]
+fn default_filter_hides_wgpu_internals_below_warn() {
+    let output = captured(logging::DEFAULT_FILTER, || {
+        tracing::info!(target: "gpu::hot_reload", label = "粒子", "重新编译");
+        tracing::debug!(target: "gpu::hot_reload", "不显示的调试信息");
+        tracing::info!(target: "wgpu_core::device", "不显示的 wgpu 信息");
+        tracing::warn!(target: "wgpu_hal::vulkan", "显示的 wgpu 警告");
+    });
+    assert!(output.contains("gpu::hot_reload: 重新编译 label=\"粒子\""));
+    assert!(output.contains("显示的 wgpu 警告"));
+    assert!(!output.contains("不显示"));
+}
+
+#[test]
+fn validation_errors_become_events_with_the_label() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let module = empty_module(&gpu.device);
+
+    let caught = captured(logging::DEFAULT_FILTER, || {
+        validation::create_compute_pipeline(&gpu.device, &broken_pipeline_descriptor(&module))
+            .unwrap_err();
+    });
+    // 标签是单独的字段，可以按字段过滤
+    assert!(caught.contains("gpu::validation: 创建失败"), "{caught}");
+    assert!(caught.contains("label=\"坏掉的计算管线\""), "{caught}");
+
+    // 没有错误作用域时由 on_uncaptured_error 转成事件而不是 panic，标签在 wgpu 的错误信息里
+    let uncaught = captured(logging::DEFAULT_FILTER, || {
+        let _pipeline = gpu
+            .device
+            .create_compute_pipeline(&broken_pipeline_descriptor(&module));
+        gpu.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .unwrap();
+    });
+    assert!(
+        uncaught.contains("gpu::validation: 未捕获的错误"),
+        "{uncaught}"
+    );
+    assert!(uncaught.contains("kind=\"校验\""), "{uncaught}");
+    assert!(uncaught.contains("坏掉的计算管线"), "{uncaught}");
+}
+
+#[test]
+fn validation_errors_are_returned_with_the_label() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let module = empty_module(&gpu.device);
+
+    let error =
+        validation::create_compute_pipeline(&gpu.device, &broken_pipeline_descriptor(&module))
+            .unwrap_err();
+    assert!(error.to_string().contains("坏掉的计算管线"), "{error}");
+
+    let descriptor = wgpu::ComputePipelineDescriptor {
+        label: Some("正常的计算管线"),
+        entry_point: Some("main"),
+        ..broken_pipeline_descriptor(&module)
+    };
+    validation::create_compute_pipeline(&gpu.device, &descriptor).unwrap();
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use gpu::{
+    ContextOptions, GpuContext,
+    pipeline_cache::{
+        CACHE_DIR_NAME, PIPELINE_CACHE_DIR_ENV, PipelineCacheFile, adapter_fingerprint, decode,
+        default_dir, encode,
+    },
+};
+
+fn adapter_info() -> wgpu::AdapterInfo {
+    wgpu::AdapterInfo {
+        name: "Test GPU".to_string(),
+        vendor: 0x10de,
+        device: 0x2684,
+        device_type: wgpu::DeviceType::DiscreteGpu,
+        device_pci_bus_id: String::new(),
+        driver: "NVIDIA".to_string(),
+        driver_info: "550.54".to_string(),
+        backend: wgpu::Backend::Vulkan,
+        subgroup_min_size: 32,
+        subgroup_max_size: 32,
+        transient_saves_memory: false,
+    }
+}
+
+/// 每个测试一个独立的临时目录
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pipeline_cache_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn cache_file_round_trips() {
+    let fingerprint = adapter_fingerprint(&adapter_info());
+    let file = encode(&fingerprint, b"driver blob");
+    assert_eq!(decode(&fingerprint, &file), Some(&b"driver blob"[..]));
+}
+
+#[test]
+fn stale_cache_file_is_rejected() {
+    let old = adapter_fingerprint(&adapter_info());
+    let file = encode(&old, b"driver blob");
+
+    // 升级驱动之后
+    let mut info = adapter_info();
+    info.driver_info = "555.42".to_string();
+    let new = adapter_fingerprint(&info);
+    assert_ne!(old, new);
+    assert_eq!(decode(&new, &file), None);
+
+    // 不是这个程序写出的文件
+    assert_eq!(decode(&old, b"garbage"), None);
+    assert_eq!(decode(&old, &file[..10]), None);
+}
+
+#[test]
+fn default_dir_is_resolved_at_runtime() {
+    if std::env::var_os(PIPELINE_CACHE_DIR_ENV).is_some()
+        || std::env::var_os("CARGO_TARGET_DIR").is_some()
+    {
+        eprintln!("设置了缓存目录的环境变量，跳过");
+        return;
+    }
+    // 没有环境变量时放在可执行文件旁边，而不是编译时的源码目录
+    let exe = std::env::current_exe().unwrap();
+    assert_eq!(
+        default_dir(),
+        Some(exe.parent().unwrap().join(CACHE_DIR_NAME))
+    );
+}
+
+#[test]
+fn headless_context_persists_cache_when_supported() {
+    let dir = temp_dir("persist");
+    let options = ContextOptions {
+        pipeline_cache_dir: Some(dir.clone()),
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let info = gpu.adapter.get_info();
+    let supported = gpu
+        .device
+        .features()
+        .contains(wgpu::Features::PIPELINE_CACHE)
+        && wgpu::util::pipeline_cache_key(&info).is_some();
+    assert_eq!(gpu.pipeline_cache().is_some(), supported);
+    if !supported {
+        eprintln!("{:?} 不支持管线缓存，跳过", info.backend);
+        return;
+    }
+
+    let module = gpu
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl("@compute @workgroup_size(1) fn main() {}".into()),
+        });
+    let _pipeline = gpu
+        .device
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: gpu.pipeline_cache(),
+        });
+    gpu.save_pipeline_cache().unwrap();
+
+    let path = dir.join(wgpu::util::pipeline_cache_key(&info).unwrap());
+    let file = std::fs::read(&path).unwrap();
+    assert!(decode(&adapter_fingerprint(&info), &file).is_some());
+
+    // 写入一个别的适配器留下的文件，重新打开时被丢弃
+    std::fs::write(&path, encode("another gpu", b"blob")).unwrap();
+    let reopened = PipelineCacheFile::open(&gpu.device, &info, &dir)
+        .unwrap()
+        .unwrap();
+    assert!(!path.exists());
+    drop(reopened);
+    assert!(decode(&adapter_fingerprint(&info), &std::fs::read(&path).unwrap()).is_some());
+    let _ = std::fs::remove_dir_all(&dir);
+}
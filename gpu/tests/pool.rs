@@ -0,0 +1,113 @@
+use gpu::{ContextOptions, GpuContext, TransientPool, pool::PoolStats};
+
+fn context() -> Option<GpuContext> {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    futures::executor::block_on(GpuContext::headless(&options)).ok()
+}
+
+fn texture_descriptor(width: u32) -> wgpu::TextureDescriptor<'static> {
+    wgpu::TextureDescriptor {
+        label: Some("临时纹理"),
+        size: wgpu::Extent3d {
+            width,
+            height: 16,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }
+}
+
+#[test]
+fn textures_are_reused_across_frames_but_not_within_one() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let mut pool = TransientPool::new(&gpu.resources, 3);
+
+    let first = pool.texture(&texture_descriptor(16));
+    let second = pool.texture(&texture_descriptor(16));
+    assert_ne!(first, second);
+    pool.end_frame();
+
+    // 标签不参与比较
+    let reused = pool.texture(&wgpu::TextureDescriptor {
+        label: Some("另一个标签"),
+        ..texture_descriptor(16)
+    });
+    assert!(reused == first || reused == second);
+    pool.end_frame();
+
+    assert_eq!(
+        pool.stats(),
+        PoolStats {
+            textures: 2,
+            buffers: 0,
+            hits: 1,
+            misses: 2,
+            evicted: 0,
+        }
+    );
+    assert_eq!(gpu.resources.stats().textures.count, 2);
+}
+
+#[test]
+fn idle_resources_are_evicted() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let mut pool = TransientPool::new(&gpu.resources, 2);
+
+    pool.texture(&texture_descriptor(16));
+    pool.end_frame();
+    // 模拟窗口大小变化：之后每帧只用新尺寸
+    for _ in 0..2 {
+        pool.texture(&texture_descriptor(32));
+        pool.end_frame();
+    }
+
+    let stats = pool.stats();
+    assert_eq!(stats.textures, 1);
+    assert_eq!(stats.evicted, 1);
+    assert_eq!(gpu.resources.stats().textures.count, 1);
+
+    pool.clear();
+    assert_eq!(gpu.resources.stats().textures.count, 0);
+}
+
+#[test]
+fn buffers_are_keyed_by_size_and_usage() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let mut pool = TransientPool::new(&gpu.resources, 3);
+    let descriptor = |size, usage| wgpu::BufferDescriptor {
+        label: Some("临时缓冲区"),
+        size,
+        usage,
+        mapped_at_creation: false,
+    };
+    let uniform = wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST;
+    let storage = wgpu::BufferUsages::STORAGE;
+
+    for _ in 0..3 {
+        pool.buffer(&descriptor(256, uniform));
+        pool.buffer(&descriptor(256, storage));
+        pool.buffer(&descriptor(512, uniform));
+        pool.end_frame();
+    }
+
+    let stats = pool.stats();
+    assert_eq!(stats.buffers, 3);
+    assert_eq!((stats.hits, stats.misses), (6, 3));
+}
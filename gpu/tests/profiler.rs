@@ -0,0 +1,123 @@
+use gpu::{
+    ContextOptions, GpuContext, GpuProfiler,
+    profiler::{FrameTimings, TimingSource},
+};
+
+fn context(features: wgpu::Features) -> Option<GpuContext> {
+    let options = ContextOptions {
+        optional_features: features,
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    futures::executor::block_on(GpuContext::headless(&options)).ok()
+}
+
+/// 两个计算通道放在同一次提交里，跑 `frames` 帧
+fn run(gpu: &GpuContext, frames: usize) -> GpuProfiler {
+    let device = &gpu.device;
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl("@compute @workgroup_size(64) fn main() {}".into()),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: None,
+        layout: None,
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: Default::default(),
+        cache: None,
+    });
+
+    let mut profiler = GpuProfiler::new(device, &gpu.queue, 2);
+    for _ in 0..frames {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        for name in ["第一遍", "第二遍", "超出上限"] {
+            let mut pass = profiler.begin_compute_pass(&mut encoder, name);
+            pass.set_pipeline(&pipeline);
+            pass.dispatch_workgroups(16, 1, 1);
+        }
+        profiler.resolve(&mut encoder);
+        gpu.queue.submit(Some(encoder.finish()));
+        profiler.end_frame(device, &gpu.queue);
+    }
+    profiler.wait(device);
+    profiler
+}
+
+fn names(frame: &FrameTimings) -> Vec<&str> {
+    frame.passes.iter().map(|p| p.name.as_str()).collect()
+}
+
+#[test]
+fn timestamps_are_reported_per_pass() {
+    let Some(gpu) = context(wgpu::Features::TIMESTAMP_QUERY) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    if !gpu
+        .device
+        .features()
+        .contains(wgpu::Features::TIMESTAMP_QUERY)
+    {
+        eprintln!("适配器不支持时间戳，跳过");
+        return;
+    }
+    let profiler = run(&gpu, 3);
+    assert_eq!(profiler.source(), TimingSource::Gpu);
+
+    let frames: Vec<_> = profiler.history().collect();
+    assert_eq!(frames.len(), 3);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.frame, i as u64);
+        assert_eq!(frame.source, TimingSource::Gpu);
+        // 超出每帧上限的通道不计时
+        assert_eq!(names(frame), ["第一遍", "第二遍"]);
+        assert!(frame.passes[1].start_ms >= frame.passes[0].start_ms);
+    }
+
+    let summary = profiler.summary();
+    assert_eq!(summary.len(), 2);
+    assert_eq!(summary[0].count, 3);
+    assert!(summary[0].min_ms <= summary[0].mean_ms && summary[0].mean_ms <= summary[0].max_ms);
+}
+
+#[test]
+fn cpu_fallback_times_whole_submissions() {
+    let Some(gpu) = context(wgpu::Features::empty()) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let profiler = run(&gpu, 2);
+    assert_eq!(profiler.source(), TimingSource::Cpu);
+    let frames: Vec<_> = profiler.history().collect();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(names(frames[0]), ["第一遍 + 第二遍"]);
+    assert!(frames[0].passes[0].duration_ms >= 0.0);
+}
+
+#[test]
+fn reports_are_written_as_csv_and_chrome_trace() {
+    let Some(gpu) = context(wgpu::Features::TIMESTAMP_QUERY) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let profiler = run(&gpu, 2);
+
+    let csv = profiler.to_csv();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("frame,source,pass,start_ms,duration_ms"));
+    let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(row[0], "0");
+    assert_eq!(row.len(), 5);
+
+    let json = profiler.to_chrome_trace();
+    assert!(json.starts_with("{\"traceEvents\":["));
+    assert!(json.contains("\"ph\":\"X\""));
+    assert!(json.trim_end().ends_with("]}"));
+
+    let dir = std::env::temp_dir().join(format!("profiler_{}", std::process::id()));
+    let [csv_path, json_path] = profiler.write_reports(&dir, "test").unwrap();
+    assert_eq!(std::fs::read_to_string(csv_path).unwrap(), csv);
+    assert_eq!(std::fs::read_to_string(json_path).unwrap(), json);
+    let _ = std::fs::remove_dir_all(&dir);
+}
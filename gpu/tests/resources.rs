@@ -0,0 +1,140 @@
+use gpu::{
+    ContextOptions, GpuContext,
+    resources::{PER_FRAME_WARNING_FRAMES, ResourceKind, format_bytes, texture_bytes},
+};
+
+fn context() -> Option<GpuContext> {
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        ..Default::default()
+    };
+    futures::executor::block_on(GpuContext::headless(&options)).ok()
+}
+
+fn texture_descriptor(
+    format: wgpu::TextureFormat,
+    size: u32,
+    mip_level_count: u32,
+    sample_count: u32,
+) -> wgpu::TextureDescriptor<'static> {
+    wgpu::TextureDescriptor {
+        label: Some("测试纹理"),
+        size: wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    }
+}
+
+fn vertex_buffer(label: &str) -> wgpu::BufferDescriptor<'_> {
+    wgpu::BufferDescriptor {
+        label: Some(label),
+        size: 1024,
+        usage: wgpu::BufferUsages::VERTEX,
+        mapped_at_creation: false,
+    }
+}
+
+#[test]
+fn texture_sizes_include_mips_samples_and_blocks() {
+    use wgpu::TextureFormat::*;
+    // 256² + 128² + ... + 1²
+    let full_chain: u64 = (0..9).map(|level| (256u64 >> level).pow(2)).sum();
+    assert_eq!(
+        texture_bytes(&texture_descriptor(Rgba8Unorm, 256, 9, 1)),
+        full_chain * 4
+    );
+    assert_eq!(
+        texture_bytes(&texture_descriptor(Bgra8UnormSrgb, 100, 1, 4)),
+        100 * 100 * 4 * 4
+    );
+    // BC1 每个 4x4 块 8 字节
+    assert_eq!(
+        texture_bytes(&texture_descriptor(Bc1RgbaUnorm, 64, 1, 1)),
+        16 * 16 * 8
+    );
+    assert_eq!(
+        texture_bytes(&texture_descriptor(Depth32Float, 10, 1, 1)),
+        400
+    );
+
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+}
+
+#[test]
+fn live_resources_and_lifetimes_are_tracked() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let resources = &gpu.resources;
+
+    let kept = resources.create_buffer(&vertex_buffer("常驻缓冲区"));
+    let texture = resources.create_texture(&texture_descriptor(
+        wgpu::TextureFormat::Rgba8Unorm,
+        16,
+        1,
+        1,
+    ));
+    // 解引用就是原来的资源
+    assert_eq!(kept.size(), 1024);
+    assert_eq!(texture.width(), 16);
+
+    let stats = resources.stats();
+    assert_eq!((stats.buffers.count, stats.buffers.bytes), (1, 1024));
+    assert_eq!(
+        (stats.textures.count, stats.textures.bytes),
+        (1, 16 * 16 * 4)
+    );
+
+    resources.end_frame();
+    resources.end_frame();
+    drop(texture);
+    let stats = resources.stats();
+    assert_eq!(stats.textures.count, 0);
+    let label = stats.label(ResourceKind::Texture, "测试纹理").unwrap();
+    assert_eq!((label.created, label.released), (1, 1));
+    assert_eq!(label.mean_lifetime_frames, 2.0);
+    assert_eq!(stats.buffers.count, 1);
+}
+
+#[test]
+fn per_frame_allocations_build_a_streak() {
+    let Some(gpu) = context() else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let resources = &gpu.resources;
+
+    let mut buffer = resources.create_buffer(&vertex_buffer("每帧重建"));
+    for _ in 0..PER_FRAME_WARNING_FRAMES {
+        buffer = resources.create_buffer(&vertex_buffer("每帧重建"));
+        resources.end_frame();
+    }
+    let stats = resources.stats();
+    let label = stats.label(ResourceKind::Buffer, "每帧重建").unwrap();
+    assert_eq!(label.streak, PER_FRAME_WARNING_FRAMES);
+    assert_eq!(label.created, PER_FRAME_WARNING_FRAMES + 1);
+    // 旧的缓冲区在被替换时释放，只剩最后一个
+    assert_eq!(stats.buffers.count, 1);
+    assert_eq!(buffer.size(), 1024);
+
+    // 一帧不新建，连续计数就归零
+    resources.end_frame();
+    let stats = resources.stats();
+    assert_eq!(
+        stats
+            .label(ResourceKind::Buffer, "每帧重建")
+            .unwrap()
+            .streak,
+        0
+    );
+}
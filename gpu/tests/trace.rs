@@ -0,0 +1,89 @@
+use gpu::{
+    ContextOptions, GpuContext,
+    trace::{self, TraceRecord, TraceSummary},
+};
+
+const SAMPLE: &str = "\
+# device: 1
+# adapter: llvmpipe
+0.100\tTRACE\twgpu_core::device::global\tDevice::create_buffer(\"顶点缓冲区\", false) -> Id(0,1)
+0.200\tTRACE\twgpu_core::device::global\tDevice::create_buffer -> Id(1,1)
+0.300\tTRACE\twgpu_core::command::render\tRenderPass::draw 0..6 0..1
+1.500\tERROR\tgpu::validation\t未捕获的错误：第一行\\n第二行 C:\\\\shaders kind=\"校验\"
+2.100\tTRACE\twgpu_core::device::queue\tQueue::submit
+";
+
+#[test]
+fn summary_counts_commands_and_collects_problems() {
+    let summary = TraceSummary::parse(SAMPLE);
+
+    assert_eq!(summary.records, 5);
+    assert!((summary.duration_ms - 2.0).abs() < 1e-9);
+    assert_eq!(summary.count("Device::create_buffer"), 2);
+    assert_eq!(summary.count("RenderPass::draw"), 1);
+    assert_eq!(summary.count("Queue::submit"), 1);
+    assert_eq!(summary.commands.len(), 3);
+    assert_eq!(summary.header[1], ("adapter".into(), "llvmpipe".into()));
+
+    let [problem] = summary.problems.as_slice() else {
+        panic!("{:?}", summary.problems);
+    };
+    assert_eq!(problem.target, "gpu::validation");
+    assert_eq!(
+        problem.message,
+        "未捕获的错误：第一行\n第二行 C:\\shaders kind=\"校验\""
+    );
+    assert!(summary.to_string().contains("Device::create_buffer"));
+}
+
+#[test]
+fn non_api_messages_are_not_commands() {
+    let record =
+        TraceRecord::parse("0.0\tWARN\twgpu_hal::gles\tEGL 初始化失败: code 0x3001").unwrap();
+    assert_eq!(record.command(), None);
+    assert!(TraceRecord::parse("不是记录").is_none());
+}
+
+/// 同一个调用点在开始记录前后各打一次
+fn internal_message(index: u32) {
+    tracing::trace!(target: "wgpu_core::device", "记录前后都打的内部日志 {index}");
+}
+
+#[test]
+fn headless_context_records_api_calls() {
+    // 开始记录前调用点已经被 RUST_LOG 的规则拒绝过，开始记录后仍要能记下来
+    gpu::logging::init();
+    let level_before = tracing::level_filters::LevelFilter::current();
+    internal_message(0);
+
+    let dir = std::env::temp_dir().join(format!("wgpu_trace_test_{}", std::process::id()));
+    let options = ContextOptions {
+        pipeline_cache_dir: None,
+        trace_dir: Some(dir.clone()),
+        ..Default::default()
+    };
+    let Ok(gpu) = futures::executor::block_on(GpuContext::headless(&options)) else {
+        eprintln!("没有可用的适配器，跳过");
+        return;
+    };
+    let _buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("记录测试缓冲区"),
+        size: 256,
+        usage: wgpu::BufferUsages::UNIFORM,
+        mapped_at_creation: false,
+    });
+    gpu.queue.submit([]);
+    internal_message(1);
+    let path = trace::stop_capture().expect("设置了 trace_dir 就应该在记录");
+    assert_eq!(tracing::level_filters::LevelFilter::current(), level_before);
+
+    let summary = TraceSummary::load(&dir).unwrap();
+    assert_eq!(path, dir.join(trace::TRACE_FILE));
+    assert!(summary.count("Device::create_buffer") >= 1, "{summary}");
+    assert!(summary.count("Queue::submit") >= 1, "{summary}");
+    assert!(summary.header.iter().any(|(key, _)| key == "adapter"));
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.contains("记录前后都打的内部日志 1"), "{summary}");
+    assert!(!text.contains("记录前后都打的内部日志 0"), "{summary}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,11 @@
+// 线性颜色转 sRGB，写入不带 srgb 后缀的存储纹理时需要手动做 gamma 编码
+fn linear_to_srgb(linear: vec4<f32>) -> vec4<f32> {
+    var rgb: vec3<f32> = linear.rgb;
+    if (linear.a > 0.0) {
+        rgb = rgb / linear.a;
+    }
+    let a = 12.92 * rgb;
+    let b = 1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055;
+    let c = step(vec3<f32>(0.0031308), rgb);
+    return vec4<f32>(mix(a, b, c) * linear.a, linear.a);
+}
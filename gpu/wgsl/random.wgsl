@@ -0,0 +1,6 @@
+// GPU 用的伪随机数生成器 (输入一个种子，输出一个 0.0 到 1.0 的随机数)
+fn pcg_hash(seed: u32) -> f32 {
+    var state = seed * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return f32((word >> 22u) ^ word) / f32(0xFFFFFFFFu);
+}
@@ -1,58 +1,21 @@
-use anyhow::anyhow;
-use wgpu::{SurfaceError, TextureFormat, util::DeviceExt};
-use winit::window::Window;
-pub struct State<'window> {
-    surface: wgpu::Surface<'window>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
+use std::sync::Arc;
+
+use gpu::{ContextOptions, GpuContext, Lesson, validation};
+use vertex_layout::VertexLayout;
+use wgpu::{SurfaceError, util::DeviceExt};
+use winit::{dpi::PhysicalSize, window::Window};
+pub struct State {
+    gpu: GpuContext,
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
 }
 
-impl State<'_> {
-    pub async fn new(window: &Window) -> anyhow::Result<Self> {
-        let (instance, _backend) = create_wgpu_instance().await?;
-        let surface = unsafe {
-            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(&window)?)
-        }?;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-        dbg!(adapter.get_info());
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                label: Some("Device"),
-                memory_hints: wgpu::MemoryHints::default(),
-                trace: wgpu::Trace::Off,
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            })
-            .await?;
-        let caps = surface.get_capabilities(&adapter);
-        let format = TextureFormat::Rgba8Unorm;
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: if caps.formats.contains(&format) {
-                format
-            } else {
-                caps.formats[0]
-            },
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&device, &config);
+impl State {
+    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+        let gpu = GpuContext::windowed(window, &ContextOptions::default()).await?;
+        let device = &gpu.device;
+        let config = gpu.config();
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
@@ -64,52 +27,55 @@ impl State<'_> {
                 bind_group_layouts: &[],
                 immediate_size: 0,
             });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                compilation_options: Default::default(),
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    // blend: Some(wgpu::BlendState {
-                    //     color: BlendComponent {
-                    //         src_factor: wgpu::BlendFactor::One,
-                    //         dst_factor: wgpu::BlendFactor::One,
-                    //         operation: wgpu::BlendOperation::Add,
-                    //     },
-                    //     alpha: BlendComponent::OVER,
-                    // }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            // 图元，描述了如何将顶点数据转换为图元
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+        let render_pipeline = validation::create_render_pipeline(
+            device,
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("Render Pipeline"),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[Vertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    compilation_options: Default::default(),
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        // blend: Some(wgpu::BlendState {
+                        //     color: BlendComponent {
+                        //         src_factor: wgpu::BlendFactor::One,
+                        //         dst_factor: wgpu::BlendFactor::One,
+                        //         operation: wgpu::BlendOperation::Add,
+                        //     },
+                        //     alpha: BlendComponent::OVER,
+                        // }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                // 图元，描述了如何将顶点数据转换为图元
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview_mask: None,
+                cache: gpu.pipeline_cache(),
             },
-            multiview_mask: None,
-            cache: None,
-        });
+        )?;
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(VERTICES),
@@ -121,21 +87,19 @@ impl State<'_> {
             usage: wgpu::BufferUsages::INDEX,
         });
         Ok(Self {
-            surface,
-            device,
-            queue,
-            config,
+            gpu,
             render_pipeline,
             vertex_buffer,
             index_buffer,
         })
     }
     pub fn render(&mut self) -> Result<(), SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let output = self.gpu.surface().get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self
+            .gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
@@ -166,44 +130,29 @@ impl State<'_> {
             render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
             render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
         }
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         Ok(())
     }
     pub fn update(&mut self) {}
-    pub fn resize(&mut self, physical_size: winit::dpi::PhysicalSize<u32>) {
-        if physical_size.width > 0 && physical_size.height > 0 {
-            self.config.width = physical_size.width;
-            self.config.height = physical_size.height;
-            self.surface.configure(&self.device, &self.config);
-        }
+    pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
+        self.gpu.resize(physical_size);
     }
 }
 
-async fn create_wgpu_instance() -> anyhow::Result<(wgpu::Instance, wgpu::Backends)> {
-    for backend in wgpu::Backends::all() {
-        if let Some(instance) = try_wgpu_backend(backend).await {
-            return Ok((instance, backend));
-        }
+impl Lesson for State {
+    fn render(&mut self) -> Result<(), SurfaceError> {
+        State::render(self)
     }
-    Err(anyhow!("没有找到可用渲染后端"))
-}
-async fn try_wgpu_backend(backend: wgpu::Backends) -> Option<wgpu::Instance> {
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        backends: backend,
-        flags: wgpu::InstanceFlags::default().with_env(),
-        ..Default::default()
-    });
-    if instance.enumerate_adapters(backend).await.is_empty() {
-        None
-    } else {
-        Some(instance)
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        State::resize(self, size);
     }
 }
 
 #[repr(C)] // 保证结构体的内存布局和C语言一致，用于和C语言交互，共享数据
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct Vertex {
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
+pub struct Vertex {
     position: [f32; 3],
     color: [f32; 3],
 }
@@ -229,27 +178,6 @@ const VERTICES: &[Vertex] = &[
 
 const INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
 
-impl Vertex {
-    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                    shader_location: 0,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    format: wgpu::VertexFormat::Float32x3,
-                    shader_location: 1,
-                },
-            ],
-        }
-    }
-}
-
 /// 🎨 标准 sRGB 转 Linear RGB 转换器
 ///
 /// 这是一个纯 Rust 实现，不依赖任何第三方库。
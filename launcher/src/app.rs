@@ -0,0 +1,222 @@
+use std::{sync::Arc, time::Instant};
+
+use gpu::Lesson;
+use winit::{
+    application::ApplicationHandler,
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowId},
+};
+
+use crate::lessons::LESSONS;
+
+pub struct App {
+    // 课程的窗口表面持有窗口的 Arc，窗口总是比表面活得久
+    lesson: Option<Box<dyn Lesson>>,
+    window: Option<Arc<Window>>,
+    current: usize,
+    last_frame: Instant,
+}
+
+impl App {
+    pub fn run(start: usize) -> anyhow::Result<()> {
+        let event_loop = EventLoop::new()?;
+        event_loop.set_control_flow(ControlFlow::Poll);
+        let mut app = App {
+            lesson: None,
+            window: None,
+            current: start,
+            last_frame: Instant::now(),
+        };
+        event_loop.run_app(&mut app)?;
+        Ok(())
+    }
+
+    /// 释放当前课程，在同一个窗口上创建新的课程
+    fn switch_to(&mut self, index: usize) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        // 先释放旧的表面，同一个窗口上不能同时存在两个表面
+        self.lesson = None;
+        self.current = index;
+        let entry = &LESSONS[index];
+        window.set_title(&format!(
+            "[{}/{}] {} - {}",
+            index + 1,
+            LESSONS.len(),
+            entry.name,
+            entry.title
+        ));
+        match (entry.create)(window.clone()) {
+            Ok(lesson) => self.lesson = Some(lesson),
+            Err(e) => tracing::error!(lesson = entry.name, "课程创建失败：{e:?}"),
+        }
+        self.last_frame = Instant::now();
+    }
+
+    /// 处理切换课程的快捷键
+    fn handle_hotkey(&mut self, event_loop: &ActiveEventLoop, key: KeyCode) {
+        if key == KeyCode::Escape {
+            event_loop.exit();
+            return;
+        }
+        let Some(target) = hotkey_target(key, self.current, LESSONS.len()) else {
+            return;
+        };
+        if target != self.current || self.lesson.is_none() {
+            self.switch_to(target);
+        }
+    }
+
+    fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(lesson), Some(window)) = (&mut self.lesson, &self.window) else {
+            return;
+        };
+        let now = Instant::now();
+        lesson.update(now - self.last_frame);
+        self.last_frame = now;
+        match lesson.render() {
+            Ok(()) => {}
+            // 表面过期（比如窗口大小刚变）时按当前大小重新配置
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                lesson.resize(window.inner_size());
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                tracing::error!("显存不足，退出");
+                event_loop.exit();
+            }
+            Err(e) => tracing::error!("{e:?}"),
+        }
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+        self.window = Some(
+            event_loop
+                .create_window(Window::default_attributes())
+                .map(Arc::new)
+                .unwrap(),
+        );
+        self.switch_to(self.current);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(lesson) = &mut self.lesson
+            && lesson.input(&event)
+        {
+            return;
+        }
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => self.handle_hotkey(event_loop, key),
+            WindowEvent::Resized(physical_size) => {
+                if let Some(lesson) = &mut self.lesson {
+                    lesson.resize(physical_size);
+                }
+            }
+            WindowEvent::RedrawRequested => self.redraw(event_loop),
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+}
+
+/// 按下 `key` 之后要切换到的课程下标，`current` 是当前课程，一共 `count` 个课程
+///
+/// 左右方向键和 PageUp / PageDown 在两端循环，数字键直接跳转，不是快捷键时返回 `None`。
+fn hotkey_target(key: KeyCode, current: usize, count: usize) -> Option<usize> {
+    match key {
+        KeyCode::ArrowRight | KeyCode::PageDown => Some((current + 1) % count),
+        KeyCode::ArrowLeft | KeyCode::PageUp => Some((current + count - 1) % count),
+        _ => digit(key)
+            .filter(|n| (1..=count).contains(n))
+            .map(|n| n - 1),
+    }
+}
+
+/// 数字键对应的课程序号，0 像键盘上的位置一样排在 9 后面，表示第 10 课
+fn digit(key: KeyCode) -> Option<usize> {
+    let n = match key {
+        KeyCode::Digit1 | KeyCode::Numpad1 => 1,
+        KeyCode::Digit2 | KeyCode::Numpad2 => 2,
+        KeyCode::Digit3 | KeyCode::Numpad3 => 3,
+        KeyCode::Digit4 | KeyCode::Numpad4 => 4,
+        KeyCode::Digit5 | KeyCode::Numpad5 => 5,
+        KeyCode::Digit6 | KeyCode::Numpad6 => 6,
+        KeyCode::Digit7 | KeyCode::Numpad7 => 7,
+        KeyCode::Digit8 | KeyCode::Numpad8 => 8,
+        KeyCode::Digit9 | KeyCode::Numpad9 => 9,
+        KeyCode::Digit0 | KeyCode::Numpad0 => 10,
+        _ => return None,
+    };
+    Some(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_jump_to_lessons_one_to_ten() {
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+            KeyCode::Digit0,
+        ];
+        for (index, key) in digits.into_iter().enumerate() {
+            assert_eq!(hotkey_target(key, 0, 10), Some(index), "{key:?}");
+        }
+        assert_eq!(hotkey_target(KeyCode::Numpad0, 0, 10), Some(9));
+        assert_eq!(hotkey_target(KeyCode::Numpad1, 5, 10), Some(0));
+        // 课程不够时超出的数字键不起作用
+        assert_eq!(hotkey_target(KeyCode::Digit0, 0, 9), None);
+        assert_eq!(hotkey_target(KeyCode::KeyA, 0, 10), None);
+    }
+
+    #[test]
+    fn next_and_previous_wrap_at_both_ends() {
+        let count = LESSONS.len();
+        for key in [KeyCode::ArrowRight, KeyCode::PageDown] {
+            assert_eq!(hotkey_target(key, 0, count), Some(1));
+            assert_eq!(hotkey_target(key, count - 1, count), Some(0));
+        }
+        for key in [KeyCode::ArrowLeft, KeyCode::PageUp] {
+            assert_eq!(hotkey_target(key, 1, count), Some(0));
+            assert_eq!(hotkey_target(key, 0, count), Some(count - 1));
+        }
+        // 只有一个课程时原地不动
+        assert_eq!(hotkey_target(KeyCode::ArrowRight, 0, 1), Some(0));
+    }
+}
@@ -0,0 +1,118 @@
+//! 所有课程的注册表，按学习顺序排列
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::executor::block_on;
+use gpu::Lesson;
+use render::{
+    demos::{ArmScene, Triangle, TwoTriangles},
+    render::Renderer,
+};
+use winit::window::Window;
+
+pub struct LessonEntry {
+    /// 命令行参数用的名字
+    pub name: &'static str,
+    /// 窗口标题里显示的说明
+    pub title: &'static str,
+    pub create: fn(Arc<Window>) -> Result<Box<dyn Lesson>>,
+}
+
+pub const LESSONS: &[LessonEntry] = &[
+    LessonEntry {
+        name: "render_triangle",
+        title: "着色器里写死的三角形",
+        create: |window| Ok(Box::new(block_on(Renderer::attach(Triangle, window))?)),
+    },
+    LessonEntry {
+        name: "vertex_buffer",
+        title: "顶点缓冲区",
+        create: |window| Ok(Box::new(block_on(vertex_buffer::State::new(window))?)),
+    },
+    LessonEntry {
+        name: "index_buffer",
+        title: "索引缓冲区",
+        create: |window| Ok(Box::new(block_on(index_buffer::State::new(window))?)),
+    },
+    LessonEntry {
+        name: "render_demo",
+        title: "一个顶点缓冲区分两次绘制",
+        create: |window| Ok(Box::new(block_on(Renderer::attach(TwoTriangles, window))?)),
+    },
+    LessonEntry {
+        name: "texture",
+        title: "纹理和采样器",
+        create: |window| Ok(Box::new(block_on(texture::State::new(window))?)),
+    },
+    LessonEntry {
+        name: "shader_transform",
+        title: "在着色器里做变换",
+        create: |window| Ok(Box::new(block_on(shader_transform::State::new(window))?)),
+    },
+    LessonEntry {
+        name: "transform",
+        title: "关键帧动画",
+        create: |window| Ok(Box::new(block_on(transform::State::new(window))?)),
+    },
+    LessonEntry {
+        name: "render_scene",
+        title: "场景图",
+        create: |window| {
+            Ok(Box::new(block_on(Renderer::attach(
+                ArmScene::default(),
+                window,
+            ))?))
+        },
+    },
+    LessonEntry {
+        name: "copy_texture_to_texture",
+        title: "纹理之间的拷贝",
+        create: |window| {
+            Ok(Box::new(block_on(copy_texture_to_texture::State::new(
+                window,
+            ))?))
+        },
+    },
+    LessonEntry {
+        name: "compute_particle",
+        title: "计算着色器粒子",
+        create: |window| Ok(Box::new(block_on(compute_particle::State::new(window))?)),
+    },
+];
+
+/// 按名字或从 1 开始的序号查找
+pub fn find(arg: &str) -> Option<usize> {
+    if let Ok(number) = arg.parse::<usize>() {
+        return (1..=LESSONS.len()).contains(&number).then(|| number - 1);
+    }
+    LESSONS.iter().position(|lesson| lesson.name == arg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lessons_are_found_by_name_and_number() {
+        assert_eq!(find("render_triangle"), Some(0));
+        assert_eq!(find("compute_particle"), Some(LESSONS.len() - 1));
+        // 序号从 1 开始
+        assert_eq!(find("1"), Some(0));
+        assert_eq!(find("10"), Some(9));
+        assert_eq!(find(&LESSONS.len().to_string()), Some(LESSONS.len() - 1));
+
+        assert_eq!(find("0"), None);
+        assert_eq!(find(&(LESSONS.len() + 1).to_string()), None);
+        assert_eq!(find("-1"), None);
+        assert_eq!(find("不存在的课程"), None);
+        assert_eq!(find("Texture"), None, "名字区分大小写");
+        assert_eq!(find(""), None);
+    }
+
+    #[test]
+    fn names_are_unique() {
+        for (i, lesson) in LESSONS.iter().enumerate() {
+            assert_eq!(find(lesson.name), Some(i), "{}", lesson.name);
+        }
+    }
+}
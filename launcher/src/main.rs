@@ -0,0 +1,34 @@
+//! 课程启动器：一个窗口里运行所有课程
+//!
+//! ```text
+//! cargo run -p launcher                    # 从第一个课程开始
+//! cargo run -p launcher -- compute_particle
+//! cargo run -p launcher -- 3               # 按序号
+//! cargo run -p launcher -- --list
+//! ```
+//!
+//! 快捷键：← / → 切换上一个、下一个课程，1-9 直接跳转，0 跳到第 10 课，Esc 退出。
+mod app;
+mod lessons;
+
+use app::App;
+use lessons::LESSONS;
+
+fn main() -> anyhow::Result<()> {
+    let arg = std::env::args().nth(1);
+    let start = match arg.as_deref() {
+        None => 0,
+        Some("--list") => {
+            for (i, lesson) in LESSONS.iter().enumerate() {
+                println!("{:>2}. {:<24} {}", i + 1, lesson.name, lesson.title);
+            }
+            return Ok(());
+        }
+        Some(arg) => lessons::find(arg).ok_or_else(|| {
+            let names: Vec<&str> = LESSONS.iter().map(|lesson| lesson.name).collect();
+            anyhow::anyhow!("没有叫 {arg} 的课程，可选：{}", names.join(", "))
+        })?,
+    };
+    gpu::logging::init();
+    App::run(start)
+}
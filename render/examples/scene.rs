@@ -0,0 +1,5 @@
+use render::{App, demos::ArmScene};
+
+fn main() {
+    App::run(ArmScene::default());
+}
@@ -0,0 +1,355 @@
+//! 渲染器的几个演示，`cargo run -p render` 和 `examples/` 以及启动器共用
+use glam::{Quat, Vec3, Vec4};
+use wgpu::{MultisampleState, PrimitiveState, VertexState, util::DeviceExt};
+
+use crate::{
+    SpecialRenderPipeline,
+    render::{
+        material::Material,
+        mesh::{Mesh, Vertex},
+        scene::{Scene, Transform},
+    },
+};
+
+/// 着色器里写死的三角形，不需要顶点缓冲区
+pub struct Triangle;
+
+impl SpecialRenderPipeline for Triangle {
+    fn special_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        texture_format: wgpu::TextureFormat,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("wgsls/shader.wgsl").into()),
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[],
+                immediate_size: 0,
+            });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview_mask: None,
+            cache,
+        })
+    }
+
+    fn draw(&self, mut render_pass: wgpu::RenderPass, _device: &wgpu::Device) {
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// 同一个顶点缓冲区的两段，分两次绘制
+pub struct TwoTriangles;
+
+impl SpecialRenderPipeline for TwoTriangles {
+    fn special_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        texture_format: wgpu::TextureFormat,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../assets/wgsls/demo.wgsl").into()),
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[],
+                immediate_size: 0,
+            });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[Vertex::desc()],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview_mask: None,
+            cache,
+        })
+    }
+
+    fn draw(&self, mut render_pass: wgpu::RenderPass, device: &wgpu::Device) {
+        let vertices_one = vec![
+            Vertex {
+                position: [0.0, 0.5, 0.0],
+                color: [1.0, 0.0, 0.0, 1.0],
+            },
+            Vertex {
+                position: [-0.5, -0.5, 0.0],
+                color: [0.0, 1.0, 0.0, 1.0],
+            },
+            Vertex {
+                position: [0.5, -0.5, 0.0],
+                color: [0.0, 0.0, 1.0, 1.0],
+            },
+        ];
+
+        let vertices_slice: &[u8] = bytemuck::cast_slice(&vertices_one);
+
+        let offset = vertices_slice.len() as u64;
+
+        let vertices_two = vec![
+            Vertex {
+                position: [0.0, 0.5, 0.0],
+                color: [1.0, 0.0, 0.0, 1.0],
+            },
+            Vertex {
+                position: [0.5, -0.5, 0.0],
+                color: [0.0, 0.0, 1.0, 1.0],
+            },
+            Vertex {
+                position: [1.0, 0.5, 0.0],
+                color: [0.0, 1.0, 0.0, 1.0],
+            },
+        ];
+
+        let vertices_slice_two: &[u8] = bytemuck::cast_slice(&vertices_two);
+
+        let indices_one = bytemuck::cast_slice(&[0, 1, 2]);
+        let indices_two = bytemuck::cast_slice(&[3, 4, 5]);
+
+        let indices_offset = indices_one.len() as u64;
+
+        let mut vertices = Vec::new();
+        vertices.extend_from_slice(vertices_slice);
+        vertices.extend_from_slice(vertices_slice_two);
+        let mut indices = Vec::new();
+        indices.extend_from_slice(indices_one);
+        indices.extend_from_slice(indices_two);
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: vertices.as_slice(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: indices.as_slice(),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..offset));
+        render_pass.set_index_buffer(
+            index_buffer.slice(..indices_offset),
+            wgpu::IndexFormat::Uint32,
+        );
+        render_pass.draw_indexed(0..3, 0, 0..1);
+
+        render_pass.set_vertex_buffer(1, vertex_buffer.slice(offset..));
+        render_pass.set_index_buffer(
+            index_buffer.slice(indices_offset..),
+            wgpu::IndexFormat::Uint32,
+        );
+        render_pass.draw_indexed(0..3, 0, 0..1);
+
+        // render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        // render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        // render_pass.draw_indexed(0..3 as u32, 0, 0..1);
+        // render_pass.draw_indexed(3..6 as u32, 0, 0..1);
+    }
+}
+
+/// 一条三节的机械臂：肩 -> 肘 -> 手，每一节都相对上一节旋转
+fn arm_scene() -> Scene {
+    let mut scene = Scene::new();
+    let segment = scene.add_mesh(segment_mesh());
+    let red = scene.add_material(Material::new("上臂", Vec4::new(1.0, 0.3, 0.3, 1.0)));
+    let green = scene.add_material(Material::new("前臂", Vec4::new(0.3, 1.0, 0.3, 1.0)));
+    let blue = scene.add_material(Material::new("手", Vec4::new(0.3, 0.3, 1.0, 1.0)));
+
+    let shoulder = scene
+        .add_node(
+            "肩",
+            Transform::from_translation(Vec3::new(-0.6, -0.4, 0.0))
+                .with_rotation(Quat::from_rotation_z(0.6)),
+            None,
+        )
+        .expect("根节点没有父节点");
+    scene.attach(shoulder, segment, Some(red));
+
+    // 子节点的平移是在父节点的局部空间里：沿着上一节的长度方向移动
+    let elbow = scene
+        .add_node(
+            "肘",
+            Transform::from_translation(Vec3::new(0.5, 0.0, 0.0))
+                .with_rotation(Quat::from_rotation_z(-0.9))
+                .with_scale(Vec3::splat(0.8)),
+            Some(shoulder),
+        )
+        .expect("父节点刚刚创建");
+    scene.attach(elbow, segment, Some(green));
+
+    let hand = scene
+        .add_node(
+            "手",
+            Transform::from_translation(Vec3::new(0.5, 0.0, 0.0))
+                .with_rotation(Quat::from_rotation_z(1.2))
+                .with_scale(Vec3::splat(0.6)),
+            Some(elbow),
+        )
+        .expect("父节点刚刚创建");
+    scene.attach(hand, segment, Some(blue));
+
+    scene.update_world_transforms();
+    scene
+}
+
+/// 从原点沿 x 轴伸出的长条，长 0.5
+fn segment_mesh() -> Mesh {
+    let white = [1.0, 1.0, 1.0, 1.0];
+    Mesh::new(
+        vec![
+            Vertex {
+                position: [0.0, -0.05, 0.0],
+                color: white,
+            },
+            Vertex {
+                position: [0.5, -0.05, 0.0],
+                color: white,
+            },
+            Vertex {
+                position: [0.5, 0.05, 0.0],
+                color: white,
+            },
+            Vertex {
+                position: [0.0, 0.05, 0.0],
+                color: white,
+            },
+        ],
+        vec![0, 1, 2, 0, 2, 3],
+    )
+}
+
+/// 场景图：一条三节的机械臂
+pub struct ArmScene {
+    /// 场景是静态的，构造时烘焙一次
+    baked: Mesh,
+}
+
+impl Default for ArmScene {
+    fn default() -> Self {
+        Self {
+            baked: arm_scene().bake(),
+        }
+    }
+}
+
+impl SpecialRenderPipeline for ArmScene {
+    fn special_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        texture_format: wgpu::TextureFormat,
+        cache: Option<&wgpu::PipelineCache>,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../assets/wgsls/demo.wgsl").into()),
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[],
+                immediate_size: 0,
+            });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[Vertex::desc()],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview_mask: None,
+            cache,
+        })
+    }
+
+    fn draw(&self, mut render_pass: wgpu::RenderPass, device: &wgpu::Device) {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Scene Vertex Buffer"),
+            contents: bytemuck::cast_slice(&self.baked.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Scene Index Buffer"),
+            contents: bytemuck::cast_slice(&self.baked.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.baked.indices.len() as u32, 0, 0..1);
+    }
+}
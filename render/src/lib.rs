@@ -4,13 +4,14 @@ use winit::{
     application::ApplicationHandler, event::WindowEvent, event_loop::ControlFlow, window::Window,
 };
 
+pub mod demos;
 pub mod render;
 
-pub struct WinitRunner<'window, T: SpecialRenderPipeline> {
-    renderer: Option<Renderer<'window, T>>,
+pub struct WinitRunner<T: SpecialRenderPipeline> {
+    renderer: Option<Renderer<T>>,
 }
 
-impl<T: SpecialRenderPipeline> WinitRunner<'_, T> {
+impl<T: SpecialRenderPipeline> WinitRunner<T> {
     pub fn new(render: T) -> Self {
         Self {
             renderer: Some(Renderer::new(render)),
@@ -18,7 +19,7 @@ impl<T: SpecialRenderPipeline> WinitRunner<'_, T> {
     }
 }
 
-impl<'a, T: SpecialRenderPipeline> ApplicationHandler for WinitRunner<'a, T> {
+impl<T: SpecialRenderPipeline> ApplicationHandler for WinitRunner<T> {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let window = event_loop
             .create_window(Window::default_attributes())
@@ -43,16 +44,16 @@ impl<'a, T: SpecialRenderPipeline> ApplicationHandler for WinitRunner<'a, T> {
                     match renderer.render() {
                         Ok(_) => {}
                         Err(e) => {
-                            eprintln!("{:?}", e);
+                            tracing::error!("{e:?}");
                         }
                     }
                 }
             }
-            WindowEvent::Resized(physical_size) => {
-                if physical_size.width > 0 && physical_size.height > 0 {
-                    if let Some(renderer) = &mut self.renderer {
-                        renderer.resize(physical_size);
-                    }
+            WindowEvent::Resized(physical_size)
+                if physical_size.width > 0 && physical_size.height > 0 =>
+            {
+                if let Some(renderer) = &mut self.renderer {
+                    renderer.resize(physical_size);
                 }
             }
             _ => {}
@@ -64,6 +65,7 @@ pub struct App;
 
 impl App {
     pub fn run(render: impl SpecialRenderPipeline) {
+        gpu::logging::init();
         let event_loop = winit::event_loop::EventLoop::new().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
         let mut app = WinitRunner::new(render);
@@ -73,10 +75,12 @@ impl App {
 }
 
 pub trait SpecialRenderPipeline {
+    /// `cache` 直接填进管线描述符的 `cache` 字段
     fn special_render_pipeline(
         &self,
         device: &wgpu::Device,
         texture_format: wgpu::TextureFormat,
+        cache: Option<&wgpu::PipelineCache>,
     ) -> RenderPipeline;
 
     fn draw(&self, render_pass: RenderPass, device: &wgpu::Device);
@@ -0,0 +1,23 @@
+use glam::Vec4;
+
+/// 材质，目前只有一个基础色，和顶点颜色相乘
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub base_color: Vec4,
+}
+
+impl Material {
+    pub fn new(name: impl Into<String>, base_color: Vec4) -> Self {
+        Self {
+            name: name.into(),
+            base_color,
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new("默认材质", Vec4::ONE)
+    }
+}
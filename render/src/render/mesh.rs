@@ -1,3 +1,5 @@
+use vertex_layout::VertexLayout;
+
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
@@ -10,34 +12,8 @@ impl Mesh {
 }
 
 #[repr(C)] // 保证结构体的内存布局和C语言一致，用于和C语言交互，共享数据
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub color: [f32; 4],
 }
-
-impl Vertex {
-    /// 使用宏定义顶点属性
-    /// 0: position, 1: color
-    const _ATTRIBUTES: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
-
-    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                    shader_location: 0,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    format: wgpu::VertexFormat::Float32x3,
-                    shader_location: 1,
-                },
-            ],
-        }
-    }
-}
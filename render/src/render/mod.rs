@@ -1,120 +1,52 @@
+use std::sync::Arc;
+
 use anyhow::Result;
+use gpu::{ContextOptions, GpuContext, Lesson, validation};
 use winit::{dpi::PhysicalSize, window::Window};
 
 use crate::SpecialRenderPipeline;
 
+pub mod material;
 pub mod mesh;
+pub mod scene;
 
-pub struct RenderRes<'window> {
-    pub surface: wgpu::Surface<'window>,
-    pub device: wgpu::Device,
-    pub queue: wgpu::Queue,
-    pub config: wgpu::SurfaceConfiguration,
+pub struct RenderRes {
+    pub gpu: GpuContext,
     pub pipeline: wgpu::RenderPipeline,
 }
 
-impl RenderRes<'_> {
+impl RenderRes {
     pub async fn new(
-        window: &Window,
+        window: Arc<Window>,
         special_render_pipeline: &impl SpecialRenderPipeline,
     ) -> Result<Self> {
-        let size = window.inner_size();
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
-            ..Default::default()
-        });
-        let surface = unsafe {
-            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(window)?)?
-        };
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .expect("没有找到可用适配器");
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: Some("设备"),
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                memory_hints: wgpu::MemoryHints::default(),
-                trace: wgpu::Trace::Off,
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            })
-            .await
-            .expect("创建设备失败");
-
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            desired_maximum_frame_latency: 2,
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-        };
-
-        let pipeline = special_render_pipeline.special_render_pipeline(&device, config.format);
-        Ok(Self {
-            surface,
-            device,
-            queue,
-            config,
-            pipeline,
-        })
+        let gpu = GpuContext::windowed(window, &ContextOptions::default()).await?;
+        let pipeline = validation::try_create(&gpu.device, "特殊渲染管线", |device| {
+            special_render_pipeline.special_render_pipeline(
+                device,
+                gpu.surface_format(),
+                gpu.pipeline_cache(),
+            )
+        })?;
+        Ok(Self { gpu, pipeline })
     }
-}
-
-pub struct Renderer<'window, T: SpecialRenderPipeline> {
-    pub window: Option<Window>,
-    pub render_res: Option<RenderRes<'window>>,
-    pub render: T,
-}
 
-impl<T: SpecialRenderPipeline> Renderer<'_, T> {
-    pub fn new(render: T) -> Self {
-        Self {
-            render,
-            render_res: None,
-            window: None,
-        }
-    }
-
-    pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
-        if let Some(render_res) = &mut self.render_res {
-            render_res.config.width = physical_size.width;
-            render_res.config.height = physical_size.height;
-            render_res
-                .surface
-                .configure(&render_res.device, &render_res.config);
-        }
-    }
-
-    pub fn render(&mut self) -> Result<()> {
-        let Some(render_res) = &mut self.render_res else {
-            return Err(anyhow::Error::msg("render_res 不存在"));
-        };
-        let output = render_res.surface.get_current_texture()?;
+    /// 画一帧并呈现
+    pub fn draw_frame(
+        &self,
+        render: &impl SpecialRenderPipeline,
+    ) -> Result<(), wgpu::SurfaceError> {
+        let output = self.gpu.surface().get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        let mut encoder =
-            render_res
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -130,17 +62,72 @@ impl<T: SpecialRenderPipeline> Renderer<'_, T> {
                 ..Default::default()
             });
 
-            render_pass.set_pipeline(&render_res.pipeline);
-            self.render.draw(render_pass, &render_res.device);
+            render_pass.set_pipeline(&self.pipeline);
+            render.draw(render_pass, &self.gpu.device);
         }
-        render_res.queue.submit(std::iter::once(encoder.finish()));
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         Ok(())
     }
+}
+
+pub struct Renderer<T: SpecialRenderPipeline> {
+    pub window: Option<Arc<Window>>,
+    pub render_res: Option<RenderRes>,
+    pub render: T,
+}
+
+impl<T: SpecialRenderPipeline> Renderer<T> {
+    pub fn new(render: T) -> Self {
+        Self {
+            render,
+            render_res: None,
+            window: None,
+        }
+    }
+
+    pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
+        if let Some(render_res) = &mut self.render_res {
+            render_res.gpu.resize(physical_size);
+        }
+    }
+
+    pub fn render(&mut self) -> Result<()> {
+        let Some(render_res) = &self.render_res else {
+            return Err(anyhow::Error::msg("render_res 不存在"));
+        };
+        render_res.draw_frame(&self.render)?;
+        Ok(())
+    }
+
+    /// 在调用方持有的窗口上创建，启动器切换课程时用
+    pub async fn attach(render: T, window: Arc<Window>) -> Result<Self> {
+        let render_res = RenderRes::new(window, &render).await?;
+        Ok(Self {
+            render,
+            render_res: Some(render_res),
+            window: None,
+        })
+    }
 
     pub(crate) fn set_window(&mut self, window: Window) {
-        self.render_res =
-            Some(futures::executor::block_on(RenderRes::new(&window, &self.render)).unwrap());
+        let window = Arc::new(window);
+        self.render_res = Some(
+            futures::executor::block_on(RenderRes::new(window.clone(), &self.render)).unwrap(),
+        );
         self.window = Some(window);
     }
 }
+
+impl<T: SpecialRenderPipeline> Lesson for Renderer<T> {
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        match &self.render_res {
+            Some(render_res) => render_res.draw_frame(&self.render),
+            None => Ok(()),
+        }
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        Renderer::resize(self, size);
+    }
+}
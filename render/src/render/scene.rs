@@ -0,0 +1,442 @@
+//! 场景图
+//!
+//! 节点保存相对父节点的局部变换，世界矩阵 = 父节点世界矩阵 × 局部矩阵。
+//! 节点、网格、材质都存在 [`Scene`] 的数组里，通过下标句柄互相引用。
+use anyhow::{Result, bail};
+use glam::{Mat4, Quat, Vec3, Vec4};
+
+use super::{
+    material::Material,
+    mesh::{Mesh, Vertex},
+};
+
+/// 局部变换：缩放 -> 旋转 -> 平移
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// 从矩阵分解，矩阵中不能有切变
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialId(usize);
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,
+    pub mesh: Option<MeshId>,
+    pub material: Option<MaterialId>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    /// 最近一次 [`Scene::update_world_transforms`] 算出的世界矩阵
+    world: Mat4,
+}
+
+impl Node {
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn world_matrix(&self) -> Mat4 {
+        self.world
+    }
+}
+
+/// 一次绘制需要的全部信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawItem {
+    pub node: NodeId,
+    pub mesh: MeshId,
+    pub material: Option<MaterialId>,
+    pub world: Mat4,
+}
+
+#[derive(Default)]
+pub struct Scene {
+    /// 删除的节点留下空位，其它节点的句柄不受影响
+    nodes: Vec<Option<Node>>,
+    roots: Vec<NodeId>,
+    meshes: Vec<Mesh>,
+    materials: Vec<Material>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_mesh(&mut self, mesh: Mesh) -> MeshId {
+        self.meshes.push(mesh);
+        MeshId(self.meshes.len() - 1)
+    }
+
+    pub fn add_material(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    /// 添加节点，`parent` 为 `None` 时作为根节点，父节点已经删除时返回错误
+    ///
+    /// 世界矩阵按父节点当前的世界矩阵算好，不用等下一次 [`Scene::update_world_transforms`]。
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        transform: Transform,
+        parent: Option<NodeId>,
+    ) -> Result<NodeId> {
+        let name = name.into();
+        let parent_world = match parent {
+            Some(parent) => {
+                self.check(parent)?;
+                self.node(parent).world
+            }
+            None => Mat4::IDENTITY,
+        };
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Some(Node {
+            name,
+            transform,
+            mesh: None,
+            material: None,
+            parent,
+            children: Vec::new(),
+            world: parent_world * transform.matrix(),
+        }));
+        match parent {
+            Some(parent) => self.node_mut(parent).children.push(id),
+            None => self.roots.push(id),
+        }
+        Ok(id)
+    }
+
+    /// 给节点挂上网格和材质
+    pub fn attach(&mut self, node: NodeId, mesh: MeshId, material: Option<MaterialId>) {
+        let node = self.node_mut(node);
+        node.mesh = Some(mesh);
+        node.material = material;
+    }
+
+    /// 修改父节点，不允许把节点挂到自己的子孙下面
+    pub fn set_parent(&mut self, node: NodeId, parent: Option<NodeId>) -> Result<()> {
+        self.check(node)?;
+        if let Some(parent) = parent {
+            self.check(parent)?;
+        }
+        let mut ancestor = parent;
+        while let Some(id) = ancestor {
+            if id == node {
+                bail!("节点 {} 不能挂到自己的子孙节点下", self.node(node).name);
+            }
+            ancestor = self.node(id).parent;
+        }
+
+        self.detach(node);
+        match parent {
+            Some(new) => self.node_mut(new).children.push(node),
+            None => self.roots.push(node),
+        }
+        self.node_mut(node).parent = parent;
+        Ok(())
+    }
+
+    /// 删除节点和它的整棵子树，返回删掉的节点，父节点在子节点前面
+    ///
+    /// 删掉的句柄不能再使用，[`Scene::contains`] 返回 `false`。
+    pub fn remove_node(&mut self, node: NodeId) -> Vec<NodeId> {
+        self.detach(node);
+        let mut removed = Vec::new();
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            let node = self.nodes[id.0].take().expect("子节点不会重复出现");
+            removed.push(id);
+            stack.extend(node.children.iter().rev());
+        }
+        removed
+    }
+
+    /// 从父节点的子节点列表或者根节点列表里摘下来
+    fn detach(&mut self, node: NodeId) {
+        match self.node(node).parent {
+            Some(old) => self.node_mut(old).children.retain(|&c| c != node),
+            None => self.roots.retain(|&r| r != node),
+        }
+    }
+
+    fn check(&self, id: NodeId) -> Result<()> {
+        if !self.contains(id) {
+            bail!("节点 {} 已经删除", id.0);
+        }
+        Ok(())
+    }
+
+    /// 节点是否还在场景里
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.get(id.0).is_some_and(Option::is_some)
+    }
+
+    /// 节点已经删除时 panic
+    pub fn node(&self, id: NodeId) -> &Node {
+        self.nodes[id.0].as_ref().expect("节点已经删除")
+    }
+
+    /// 节点已经删除时 panic
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes[id.0].as_mut().expect("节点已经删除")
+    }
+
+    pub fn mesh(&self, id: MeshId) -> &Mesh {
+        &self.meshes[id.0]
+    }
+
+    pub fn material(&self, id: MaterialId) -> &Material {
+        &self.materials[id.0]
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// 从根节点往下传播世界矩阵
+    pub fn update_world_transforms(&mut self) {
+        let mut stack: Vec<(NodeId, Mat4)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&r| (r, Mat4::IDENTITY))
+            .collect();
+        while let Some((id, parent_world)) = stack.pop() {
+            let node = self.node_mut(id);
+            // 父节点在左边：先做自己的局部变换，再做父节点的
+            node.world = parent_world * node.transform.matrix();
+            let world = node.world;
+            stack.extend(node.children.iter().rev().map(|&c| (c, world)));
+        }
+    }
+
+    /// 深度优先遍历，父节点总是先于子节点访问
+    pub fn traverse(&self, mut visit: impl FnMut(NodeId, &Node)) {
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            visit(id, node);
+            stack.extend(node.children.iter().rev());
+        }
+    }
+
+    /// 按遍历顺序收集所有挂了网格的节点
+    ///
+    /// 调用前需要先 [`Scene::update_world_transforms`]。
+    pub fn draw_list(&self) -> Vec<DrawItem> {
+        let mut items = Vec::new();
+        self.traverse(|id, node| {
+            if let Some(mesh) = node.mesh {
+                items.push(DrawItem {
+                    node: id,
+                    mesh,
+                    material: node.material,
+                    world: node.world,
+                });
+            }
+        });
+        items
+    }
+
+    /// 把所有绘制项合并成一个世界坐标下的网格，颜色乘上材质的基础色
+    ///
+    /// 适合没有 uniform 的简单着色器，一次 `draw_indexed` 画完整个场景。
+    pub fn bake(&self) -> Mesh {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for item in self.draw_list() {
+            let mesh = self.mesh(item.mesh);
+            let base_color = item
+                .material
+                .map_or(Vec4::ONE, |m| self.material(m).base_color);
+            let base_index = vertices.len() as u32;
+            vertices.extend(mesh.vertices.iter().map(|v| {
+                Vertex {
+                    position: item
+                        .world
+                        .transform_point3(Vec3::from_array(v.position))
+                        .to_array(),
+                    color: (Vec4::from_array(v.color) * base_color).to_array(),
+                }
+            }));
+            indices.extend(mesh.indices.iter().map(|i| i + base_index));
+        }
+        Mesh::new(vertices, indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    /// root -> child -> grandchild，每一级往 x 方向平移 1
+    fn chain() -> (Scene, [NodeId; 3]) {
+        let mut scene = Scene::new();
+        let step = Transform::from_translation(Vec3::X);
+        let root = scene.add_node("root", Transform::IDENTITY, None).unwrap();
+        let child = scene.add_node("child", step, Some(root)).unwrap();
+        let grandchild = scene.add_node("grandchild", step, Some(child)).unwrap();
+        (scene, [root, child, grandchild])
+    }
+
+    #[test]
+    fn moving_the_root_moves_the_grandchild() {
+        let (mut scene, [root, _, grandchild]) = chain();
+        scene.node_mut(root).transform = Transform::from_translation(Vec3::new(0.0, 5.0, 0.0))
+            .with_rotation(Quat::from_rotation_z(FRAC_PI_2));
+        scene.update_world_transforms();
+
+        // 先在根节点的局部坐标里走到 (2, 0, 0)，旋转 90° 到 (0, 2, 0)，再平移
+        let position = scene
+            .node(grandchild)
+            .world_matrix()
+            .transform_point3(Vec3::ZERO);
+        assert!(
+            position.abs_diff_eq(Vec3::new(0.0, 7.0, 0.0), 1e-5),
+            "{position}"
+        );
+    }
+
+    #[test]
+    fn reparenting_under_a_descendant_is_rejected() {
+        let (mut scene, [root, child, grandchild]) = chain();
+        assert!(scene.set_parent(root, Some(grandchild)).is_err());
+        assert!(scene.set_parent(child, Some(child)).is_err());
+        // 失败时结构不变
+        assert_eq!(scene.roots(), [root]);
+        assert_eq!(scene.node(root).children(), [child]);
+        assert_eq!(scene.node(child).parent(), Some(root));
+
+        scene.set_parent(grandchild, None).unwrap();
+        assert_eq!(scene.roots(), [root, grandchild]);
+        assert!(scene.node(child).children().is_empty());
+    }
+
+    #[test]
+    fn removing_a_node_removes_its_subtree() {
+        let (mut scene, [root, child, grandchild]) = chain();
+        let sibling = scene
+            .add_node("sibling", Transform::IDENTITY, Some(root))
+            .unwrap();
+
+        assert_eq!(scene.remove_node(child), [child, grandchild]);
+        assert!(!scene.contains(child) && !scene.contains(grandchild));
+        assert!(scene.contains(root) && scene.contains(sibling));
+        assert_eq!(scene.node(root).children(), [sibling]);
+
+        let mut visited = Vec::new();
+        scene.update_world_transforms();
+        scene.traverse(|id, _| visited.push(id));
+        assert_eq!(visited, [root, sibling]);
+
+        assert_eq!(scene.remove_node(root), [root, sibling]);
+        assert!(scene.roots().is_empty());
+
+        // 删掉的节点不能再当父节点
+        assert!(
+            scene
+                .add_node("orphan", Transform::IDENTITY, Some(child))
+                .is_err()
+        );
+        assert!(scene.set_parent(sibling, None).is_err());
+    }
+
+    #[test]
+    fn new_nodes_start_with_the_parent_world_matrix() {
+        let (mut scene, [_, _, grandchild]) = chain();
+        scene.update_world_transforms();
+        // 不调用 update_world_transforms，新节点的世界矩阵也已经算好
+        let leaf = scene
+            .add_node(
+                "leaf",
+                Transform::from_translation(Vec3::X),
+                Some(grandchild),
+            )
+            .unwrap();
+        let position = scene.node(leaf).world_matrix().transform_point3(Vec3::ZERO);
+        assert!(
+            position.abs_diff_eq(Vec3::new(3.0, 0.0, 0.0), 1e-6),
+            "{position}"
+        );
+    }
+}
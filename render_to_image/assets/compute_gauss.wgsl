@@ -1,3 +1,5 @@
+#include <color.wgsl>
+
 // 1. 定义绑定资源
 @group(0) @binding(0) var input_texture: texture_2d<f32>;
 // 输出图 格式要和Rust中创建的一致，如 rgba8unorm 且 不能是带 srgb 格式
@@ -11,11 +13,16 @@ const weights: array<f32, 9> = array<f32, 9>(
     0.0625, 0.125, 0.0625
 );
 
-// 1. 定义常数 (也可以通过 uniform 传进来，但这里为了简单直接写死)
-// 试着把这里改成 5, 8, 10 看看效果！(不要超过 15 哦，显卡会哭的)
-const RADIUS: i32 = 12;
+// 1. 定义常数 (Rust 端可以通过 RADIUS 宏注入，这里是默认值)
+// 试着运行时传 5, 8, 10 看看效果！(不要超过 15 哦，显卡会哭的)
+#ifndef RADIUS
+#define RADIUS 12i
+#endif
+#ifndef WORKGROUP_SIZE
+#define WORKGROUP_SIZE 8u
+#endif
 
-@compute @workgroup_size(8, 8)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     // 获取当前像素坐标
     let x = global_id.x;
@@ -113,14 +120,3 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     // 将结果写入输出纹理
     textureStore(output_texture, vec2<u32>(x, y), linear_to_srgb(color));
 }
-
-fn linear_to_srgb(linear: vec4<f32>) -> vec4<f32> {
-    var rgb: vec3<f32> = linear.rgb;
-    if (linear.a > 0.0) {
-        rgb = rgb / linear.a;
-    }
-    let a = 12.92 * rgb;
-    let b = 1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055;
-    let c = step(vec3<f32>(0.0031308), rgb);
-    return vec4<f32>(mix(a, b, c) * linear.a, linear.a);
-}
@@ -1,7 +1,33 @@
-use render_backend::{backend::get_device_and_queue, image_utils};
+//! 高斯模糊：暴力版和可分离版
+//!
+//! 两个版本各跑一遍并保存图片，再各跑 `BENCHMARK_ROUNDS` 遍比较耗时，
+//! 计时结果记到日志里，同时写到 output/gauss_profile.csv 和 output/gauss_profile.json
+//! （用 chrome://tracing 打开）。
+use std::{path::Path, time::Duration};
 
+use gpu::{
+    hot_reload::hot_reload_enabled, shader_source, validation, Defines, GpuProfiler, HotShader,
+};
+use render_backend::{backend::create_gpu_context, image_utils};
+use tracing::{info, warn};
+
+/// 工作组边长，通过 `WORKGROUP_SIZE` 宏注入着色器
+const WORKGROUP_SIZE: u32 = 8;
+/// 计时的轮数，第一轮包含驱动的预热，取平均更稳定
+const BENCHMARK_ROUNDS: usize = 20;
+
+/// 用法：`cargo run -p render_backend --example computer_gauss -- [模糊半径]`
 fn main() -> anyhow::Result<()> {
-    let (device, queue) = futures::executor::block_on(get_device_and_queue())?;
+    // 模糊半径通过 `RADIUS` 宏注入着色器，不传参数时用着色器里的默认值
+    let mut defines = Defines::new().set("WORKGROUP_SIZE", WORKGROUP_SIZE);
+    if let Some(radius) = std::env::args().nth(1) {
+        let radius: i32 = radius.parse()?;
+        defines.insert("RADIUS", radius);
+    }
+
+    gpu::logging::init();
+    let gpu = futures::executor::block_on(create_gpu_context())?;
+    let (device, queue) = (&gpu.device, &gpu.queue);
 
     let (image, dimensions) = image_utils::load_image_from_file("xiongmao.jpg");
 
@@ -53,84 +79,241 @@ fn main() -> anyhow::Result<()> {
 
     let output_texture_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-    let texture_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("纹理绑定组布局"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
+    // 可分离版的中间结果：水平模糊后的线性颜色
+    let intermediate_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("水平模糊结果"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba16Float,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let intermediate_view =
+        intermediate_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let output_layout = blur_bind_group_layout(device, wgpu::TextureFormat::Rgba8Unorm);
+    let intermediate_layout = blur_bind_group_layout(device, wgpu::TextureFormat::Rgba16Float);
+
+    let bind_group = blur_bind_group(device, &output_layout, &texture_view, &output_texture_view);
+    let horizontal_bind_group = blur_bind_group(
+        device,
+        &intermediate_layout,
+        &texture_view,
+        &intermediate_view,
+    );
+    let vertical_bind_group = blur_bind_group(
+        device,
+        &output_layout,
+        &intermediate_view,
+        &output_texture_view,
+    );
+
+    let mut shader = HotShader::with_defines(
+        device,
+        shader_source!("assets/compute_gauss.wgsl"),
+        defines.clone(),
+    )?;
+    let horizontal_shader = HotShader::with_defines(
+        device,
+        shader_source!("assets/compute_gauss_separable.wgsl"),
+        defines.clone(),
+    )?;
+    let vertical_shader = HotShader::with_defines(
+        device,
+        shader_source!("assets/compute_gauss_separable.wgsl"),
+        defines.flag("VERTICAL"),
+    )?;
+
+    let compute_pipeline_layout = pipeline_layout(device, &output_layout);
+    let horizontal_pipeline = create_pipeline(
+        device,
+        &pipeline_layout(device, &intermediate_layout),
+        horizontal_shader.module(),
+        gpu.pipeline_cache(),
+    )?;
+    let vertical_pipeline = create_pipeline(
+        device,
+        &compute_pipeline_layout,
+        vertical_shader.module(),
+        gpu.pipeline_cache(),
+    )?;
+
+    let workgroups = (
+        size.width.div_ceil(WORKGROUP_SIZE),
+        size.height.div_ceil(WORKGROUP_SIZE),
+    );
+    let mut profiler = GpuProfiler::new(device, queue, 4);
+
+    // 每个版本单独提交，CPU 计时时也能分开两个版本
+    let brute_force = |profiler: &mut GpuProfiler, compute_pipeline: &wgpu::ComputePipeline| {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("命令编码器"),
+        });
+        {
+            let mut compute_pass = profiler.begin_compute_pass(&mut encoder, "暴力模糊");
+            compute_pass.set_pipeline(compute_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+        }
+        profiler.resolve(&mut encoder);
+        queue.submit(Some(encoder.finish()));
+        profiler.end_frame(device, queue);
+    };
+    let separable = |profiler: &mut GpuProfiler| {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("命令编码器"),
+        });
+        for (name, pipeline, bind_group) in [
+            (
+                "可分离模糊-水平",
+                &horizontal_pipeline,
+                &horizontal_bind_group,
+            ),
+            ("可分离模糊-垂直", &vertical_pipeline, &vertical_bind_group),
+        ] {
+            let mut compute_pass = profiler.begin_compute_pass(&mut encoder, name);
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+        }
+        profiler.resolve(&mut encoder);
+        queue.submit(Some(encoder.finish()));
+        profiler.end_frame(device, queue);
+    };
+    let save = |file_name: &str| {
+        image_utils::copy_buffer_save_image(file_name, &output_texture, size, device, queue);
+    };
+
+    let compute_pipeline = create_pipeline(
+        device,
+        &compute_pipeline_layout,
+        shader.module(),
+        gpu.pipeline_cache(),
+    )?;
+    brute_force(&mut profiler, &compute_pipeline);
+    save("test_work_gauss");
+    separable(&mut profiler);
+    save("test_work_gauss_separable");
+
+    profiler.clear();
+    for _ in 0..BENCHMARK_ROUNDS {
+        brute_force(&mut profiler, &compute_pipeline);
+        separable(&mut profiler);
+    }
+    profiler.wait(device);
+    profiler.log_summary();
+    let output = Path::new(env!("CARGO_MANIFEST_DIR")).join("output");
+    let [csv, json] = profiler.write_reports(&output, "gauss_profile")?;
+    info!(csv = %csv.display(), json = %json.display(), "计时结果已写入");
+
+    // 开发模式下一直运行，着色器保存后重新模糊并覆盖输出图片
+    if hot_reload_enabled() {
+        info!(shader = shader.source().path, "正在监视，Ctrl+C 退出");
+        loop {
+            std::thread::sleep(Duration::from_millis(200));
+            if !shader.poll(device) {
+                continue;
+            }
+            match create_pipeline(
+                device,
+                &compute_pipeline_layout,
+                shader.module(),
+                gpu.pipeline_cache(),
+            ) {
+                Ok(pipeline) => {
+                    brute_force(&mut profiler, &pipeline);
+                    save("test_work_gauss");
+                }
+                Err(e) => warn!("重建计算管线失败：{e}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 输入纹理 + 指定格式的输出存储纹理
+fn blur_bind_group_layout(
+    device: &wgpu::Device,
+    output_format: wgpu::TextureFormat,
+) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("纹理绑定组布局"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
                 },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rgba8Unorm,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: output_format,
+                    view_dimension: wgpu::TextureViewDimension::D2,
                 },
-            ],
-        });
+                count: None,
+            },
+        ],
+    })
+}
 
-    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+fn blur_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    input: &wgpu::TextureView,
+    output: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("纹理绑定组"),
-        layout: &texture_bind_group_layout,
+        layout,
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
-                resource: wgpu::BindingResource::TextureView(&texture_view),
+                resource: wgpu::BindingResource::TextureView(input),
             },
             wgpu::BindGroupEntry {
                 binding: 1,
-                resource: wgpu::BindingResource::TextureView(&output_texture_view),
+                resource: wgpu::BindingResource::TextureView(output),
             },
         ],
-    });
-
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("着色器模块"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("../assets/compute_gauss.wgsl").into()),
-    });
+    })
+}
 
-    let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+fn pipeline_layout(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+) -> wgpu::PipelineLayout {
+    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("compute_pipeline_layout"),
-        bind_group_layouts: &[&texture_bind_group_layout],
+        bind_group_layouts: &[bind_group_layout],
         immediate_size: 0,
-    });
-
-    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("compute_pipeline_layout"),
-        layout: Some(&compute_pipeline_layout),
-        module: &shader,
-        entry_point: Some("main"),
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
-        cache: None,
-    });
-
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("命令编码器"),
-    });
-    {
-        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some("计算着色器"),
-            timestamp_writes: None,
-        });
-
-        compute_pass.set_pipeline(&compute_pipeline);
-        compute_pass.set_bind_group(0, &bind_group, &[]);
-        compute_pass.dispatch_workgroups(size.width.div_ceil(8), size.height.div_ceil(8), 1);
-    }
-    queue.submit(Some(encoder.finish()));
-
-    image_utils::copy_buffer_save_image("test_work_gauss", &output_texture, size, &device, &queue);
+    })
+}
 
-    Ok(())
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    cache: Option<&wgpu::PipelineCache>,
+) -> anyhow::Result<wgpu::ComputePipeline> {
+    validation::create_compute_pipeline(
+        device,
+        &wgpu::ComputePipelineDescriptor {
+            label: Some("compute_pipeline_layout"),
+            layout: Some(layout),
+            module: shader,
+            entry_point: Some("main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache,
+        },
+    )
 }
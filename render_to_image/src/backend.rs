@@ -1,114 +1,18 @@
-use anyhow::anyhow;
+use gpu::{ContextOptions, GpuContext};
 use tracing::info;
 
-async fn create_wgpu_instance() -> anyhow::Result<(wgpu::Instance, wgpu::Backends)> {
-    for backend in wgpu::Backends::all() {
-        if let Some(instance) = try_wgpu_backend(backend).await {
-            return Ok((instance, backend));
-        }
-    }
-    Err(anyhow!("没有找到可用渲染后端"))
-}
-
-async fn try_wgpu_backend(backends: wgpu::Backends) -> Option<wgpu::Instance> {
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        backends,
-        flags: wgpu::InstanceFlags::default().with_env(),
+/// 离屏渲染用的上下文，能用 GPU 时间戳时顺便打开
+pub async fn create_gpu_context() -> anyhow::Result<GpuContext> {
+    let gpu = GpuContext::headless(&ContextOptions {
+        optional_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::FLOAT32_FILTERABLE
+            | wgpu::Features::TIMESTAMP_QUERY,
         ..Default::default()
-    });
-    if instance.enumerate_adapters(backends).await.is_empty() {
-        None
-    } else {
-        Some(instance)
-    }
-}
-
-pub async fn get_device_and_queue() -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
-    let (instance, backend) = create_wgpu_instance().await?;
+    })
+    .await?;
 
-    let (adapter, device, queue) = request_adapter_and_device(
-        backend,
-        &instance,
-        None,
-        wgpu::PowerPreference::HighPerformance,
-    )
-    .await
-    .map_err(|e| anyhow!(e.to_string()))?;
-
-    let adapter_info = adapter.get_info();
+    let adapter_info = gpu.adapter.get_info();
     info!("适配器信息：{:?}", adapter_info);
-    Ok((device, queue))
-}
-
-type Error = Box<dyn std::error::Error>;
-
-pub async fn request_adapter_and_device(
-    backend: wgpu::Backends,
-    instance: &wgpu::Instance,
-    surface: Option<&wgpu::Surface<'static>>,
-    power_preference: wgpu::PowerPreference,
-) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), Error> {
-    let adapter = instance
-        .request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference,
-            compatible_surface: surface,
-            force_fallback_adapter: false,
-        })
-        .await
-        .map_err(|_| {
-            let names = get_backend_names(backend);
-            if names.is_empty() {
-                "没有找到适配器".to_string()
-            } else {
-                format!("没有找到适配器，可用适配器：{}", names.join(", "))
-            }
-        })?;
-
-    let mut features = Default::default();
-
-    let try_features = [
-        wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
-        wgpu::Features::TEXTURE_COMPRESSION_BC,
-        wgpu::Features::FLOAT32_FILTERABLE,
-    ];
-
-    for feature in try_features {
-        if adapter.features().contains(feature) {
-            features |= feature;
-        }
-    }
-
-    let (device, queue) = adapter
-        .request_device(&wgpu::DeviceDescriptor {
-            label: Some("设备"),
-            required_features: features,
-            required_limits: wgpu::Limits::default(),
-            memory_hints: Default::default(),
-            trace: wgpu::Trace::Off,
-            experimental_features: wgpu::ExperimentalFeatures::disabled(),
-        })
-        .await?;
-    Ok((adapter, device, queue))
-}
-
-pub fn get_backend_names(backends: wgpu::Backends) -> Vec<&'static str> {
-    let mut names = Vec::new();
-
-    if backends.contains(wgpu::Backends::VULKAN) {
-        names.push("Vulkan");
-    }
-    if backends.contains(wgpu::Backends::DX12) {
-        names.push("DirectX 12");
-    }
-    if backends.contains(wgpu::Backends::METAL) {
-        names.push("Metal");
-    }
-    if backends.contains(wgpu::Backends::GL) {
-        names.push("Open GL");
-    }
-    if backends.contains(wgpu::Backends::BROWSER_WEBGPU) {
-        names.push("Web GPU");
-    }
-
-    names
+    Ok(gpu)
 }
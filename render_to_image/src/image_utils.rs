@@ -33,9 +33,9 @@ pub fn copy_buffer_save_image(
     // 将纹理数据复制到CPU内存
     // 纹理宽度
     // 乘以 4 是因为每个 RGBA 像素需要4字节。
-    let unpadded_byte_per_row = size.width as u32 * 4;
+    let unpadded_byte_per_row = size.width * 4;
     // GPU 中数据访问需要内存对齐，通常是256字节
-    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u32;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
     // upadded_byte_per_row_padding % align: 当前字节数除以对齐值的余数。
     // align - 余数: 需要补充的字节数。
     // 外层的 % align: 计算需要补充的字节数 如果为余数为0，则不需要填充。
@@ -56,7 +56,7 @@ pub fn copy_buffer_save_image(
 
     encoder.copy_texture_to_buffer(
         wgpu::TexelCopyTextureInfo {
-            texture: &texture,
+            texture,
             mip_level: 0,
             origin: wgpu::Origin3d::ZERO,
             aspect: wgpu::TextureAspect::All,
@@ -0,0 +1,392 @@
+//! WGSL 预处理器
+//!
+//! 支持的指令，每条独占一行（`#` 前面可以有空白）：
+//! - `#include "particle.wgsl"`：相对当前文件所在的目录
+//! - `#include <random.wgsl>`：在 [`Preprocessor::include_dir`] 添加的目录里按顺序查找
+//! - `#define NAME 值` / `#define NAME` / `#undef NAME`
+//! - `#ifdef NAME` / `#ifndef NAME` / `#else` / `#endif`，可以嵌套
+//!
+//! 同一个文件只会展开一次（相当于每个文件都自带 `#pragma once`）。
+//! WGSL 不允许重复声明结构体和函数，几个文件都包含同一个公共文件时也不会冲突。
+//!
+//! 有值的宏在代码里按标识符整词替换（`//` 之后的注释保持原样），没有值的宏只用于 `#ifdef`。
+//! Rust 端用 [`Defines`] 注入常量，着色器里用 `#ifndef` 写默认值：
+//!
+//! ```text
+//! #ifndef RADIUS
+//! #define RADIUS 12i
+//! #endif
+//! ```
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow, bail};
+
+/// 可以写进 WGSL 的常量值
+///
+/// 整数带上 `u` / `i` 后缀，浮点数总是带小数点，避免 `1.0` 变成整数 `1` 之后类型对不上。
+pub trait ToWgsl {
+    fn to_wgsl(&self) -> String;
+}
+
+impl ToWgsl for f32 {
+    fn to_wgsl(&self) -> String {
+        // Debug 格式总会保留小数点：1.0 而不是 1
+        format!("{self:?}")
+    }
+}
+
+impl ToWgsl for u32 {
+    fn to_wgsl(&self) -> String {
+        format!("{self}u")
+    }
+}
+
+impl ToWgsl for i32 {
+    fn to_wgsl(&self) -> String {
+        format!("{self}i")
+    }
+}
+
+impl ToWgsl for bool {
+    fn to_wgsl(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// 原样插入的 WGSL 代码，比如 `vec2<f32>(0.0, 1.0)`
+impl ToWgsl for &str {
+    fn to_wgsl(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToWgsl for String {
+    fn to_wgsl(&self) -> String {
+        self.clone()
+    }
+}
+
+/// 从 Rust 注入的宏
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Defines {
+    values: BTreeMap<String, String>,
+}
+
+impl Defines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 定义一个有值的宏，着色器里同名的标识符会被替换成这个值
+    pub fn set(mut self, name: &str, value: impl ToWgsl) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// 定义一个没有值的宏，只用于 `#ifdef`
+    pub fn flag(mut self, name: &str) -> Self {
+        self.values.insert(name.to_string(), String::new());
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, value: impl ToWgsl) {
+        self.values.insert(name.to_string(), value.to_wgsl());
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.values.remove(name);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// 宏的值，没有值的宏返回空字符串
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+}
+
+/// 预处理的结果
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    /// 展开后的 WGSL
+    pub code: String,
+    /// 用到的所有文件，第一个是入口文件，热重载时要监视全部
+    pub files: Vec<PathBuf>,
+    /// 展开后每一行来自哪里：`files` 的下标和原文件里从 1 开始的行号
+    pub lines: Vec<(usize, usize)>,
+}
+
+impl Preprocessed {
+    /// 展开后的第 `line` 行（从 1 开始）对应的原文件和行号，编译器报错时用来找回原来的位置
+    pub fn source_line(&self, line: usize) -> Option<(&Path, usize)> {
+        let &(file, line) = self.lines.get(line.checked_sub(1)?)?;
+        Some((&self.files[file], line))
+    }
+}
+
+type Loader<'a> = Box<dyn Fn(&Path) -> io::Result<String> + 'a>;
+
+pub struct Preprocessor<'a> {
+    defines: Defines,
+    include_dirs: Vec<PathBuf>,
+    loader: Loader<'a>,
+}
+
+impl<'a> Preprocessor<'a> {
+    /// 默认从磁盘读取被包含的文件
+    pub fn new(defines: Defines) -> Self {
+        Self {
+            defines,
+            include_dirs: Vec::new(),
+            loader: Box::new(|path| std::fs::read_to_string(path)),
+        }
+    }
+
+    /// 添加 `#include <...>` 的搜索目录
+    pub fn include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// 替换读取文件的方式，比如改成从 `include_str!` 嵌入的内容里查找
+    ///
+    /// 传进来的路径已经规范化过（去掉了 `.` 和 `..`）。
+    pub fn loader(mut self, loader: impl Fn(&Path) -> io::Result<String> + 'a) -> Self {
+        self.loader = Box::new(loader);
+        self
+    }
+
+    /// 读取并展开 `path`
+    pub fn process_file(&self, path: impl AsRef<Path>) -> Result<Preprocessed> {
+        let path = normalize(path.as_ref());
+        let source =
+            (self.loader)(&path).map_err(|e| anyhow!("读取 {} 失败：{e}", path.display()))?;
+        self.process(path, &source)
+    }
+
+    /// 展开已经读到内存里的 `source`，`path` 用来解析相对的 `#include` 和报错
+    pub fn process(&self, path: impl AsRef<Path>, source: &str) -> Result<Preprocessed> {
+        let path = normalize(path.as_ref());
+        let mut state = State {
+            defines: self.defines.clone(),
+            files: vec![path.clone()],
+            code: String::new(),
+            lines: Vec::new(),
+        };
+        self.expand_file(&mut state, 0, source)?;
+        Ok(Preprocessed {
+            code: state.code,
+            files: state.files,
+            lines: state.lines,
+        })
+    }
+
+    /// 展开 `state.files[file]`
+    fn expand_file(&self, state: &mut State, file: usize, source: &str) -> Result<()> {
+        let path = state.files[file].clone();
+        let path = path.as_path();
+        let mut conditions: Vec<Condition> = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let at = || format!("{}:{line_number}", path.display());
+            let active = conditions.iter().all(|c| c.active);
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    state
+                        .code
+                        .push_str(&substitute(line, &state.defines, &mut Vec::new()));
+                    state.code.push('\n');
+                    state.lines.push((file, line_number));
+                }
+                continue;
+            };
+            let (keyword, argument) = directive
+                .trim()
+                .split_once(char::is_whitespace)
+                .map(|(k, a)| (k, a.trim()))
+                .unwrap_or((directive.trim(), ""));
+
+            match keyword {
+                "ifdef" | "ifndef" => {
+                    let name = identifier(argument)
+                        .ok_or_else(|| anyhow!("{}: #{keyword} 缺少宏名", at()))?;
+                    let defined = state.defines.is_defined(name);
+                    conditions.push(Condition {
+                        active: defined == (keyword == "ifdef"),
+                        has_else: false,
+                        line: line_number,
+                    });
+                }
+                "else" => {
+                    let condition = conditions
+                        .last_mut()
+                        .ok_or_else(|| anyhow!("{}: #else 没有对应的 #ifdef", at()))?;
+                    if condition.has_else {
+                        bail!("{}: 重复的 #else", at());
+                    }
+                    condition.has_else = true;
+                    condition.active = !condition.active;
+                }
+                "endif" => {
+                    conditions
+                        .pop()
+                        .ok_or_else(|| anyhow!("{}: #endif 没有对应的 #ifdef", at()))?;
+                }
+                // 下面的指令在不生效的分支里直接跳过
+                _ if !active => {}
+                "define" => {
+                    let (name, value) = argument
+                        .split_once(char::is_whitespace)
+                        .map(|(n, v)| (n, v.trim()))
+                        .unwrap_or((argument, ""));
+                    let name =
+                        identifier(name).ok_or_else(|| anyhow!("{}: #define 缺少宏名", at()))?;
+                    if let Some(old) = state.defines.get(name)
+                        && old != value
+                    {
+                        bail!(
+                            "{}: {name} 重复定义（已有的值是 `{old}`），默认值请放在 #ifndef {name} 里",
+                            at()
+                        );
+                    }
+                    state
+                        .defines
+                        .values
+                        .insert(name.to_string(), value.to_string());
+                }
+                "undef" => {
+                    let name =
+                        identifier(argument).ok_or_else(|| anyhow!("{}: #undef 缺少宏名", at()))?;
+                    state.defines.remove(name);
+                }
+                "include" => {
+                    let (include, source) = self
+                        .resolve(path, argument)
+                        .map_err(|e| anyhow!("{}: {e}", at()))?;
+                    if state.files.contains(&include) {
+                        continue;
+                    }
+                    state.files.push(include);
+                    self.expand_file(state, state.files.len() - 1, &source)?;
+                }
+                _ => bail!("{}: 不认识的指令 #{keyword}", at()),
+            }
+        }
+        if let Some(condition) = conditions.last() {
+            bail!(
+                "{}:{}: #ifdef 缺少对应的 #endif",
+                path.display(),
+                condition.line
+            );
+        }
+        Ok(())
+    }
+
+    /// 找到被包含的文件并读出内容
+    fn resolve(&self, from: &Path, argument: &str) -> Result<(PathBuf, String)> {
+        if let Some(name) = argument.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+            let dir = from.parent().unwrap_or(Path::new(""));
+            let path = normalize(&dir.join(name));
+            let source =
+                (self.loader)(&path).map_err(|e| anyhow!("读取 {} 失败：{e}", path.display()))?;
+            return Ok((path, source));
+        }
+        if let Some(name) = argument.strip_prefix('<').and_then(|a| a.strip_suffix('>')) {
+            for dir in &self.include_dirs {
+                let path = normalize(&dir.join(name));
+                if let Ok(source) = (self.loader)(&path) {
+                    return Ok((path, source));
+                }
+            }
+            let dirs: Vec<String> = self
+                .include_dirs
+                .iter()
+                .map(|d| d.display().to_string())
+                .collect();
+            bail!("找不到 <{name}>，搜索过的目录：[{}]", dirs.join(", "));
+        }
+        bail!("#include 的参数要写成 \"相对路径\" 或 <库文件>，实际是 `{argument}`")
+    }
+}
+
+struct State {
+    defines: Defines,
+    files: Vec<PathBuf>,
+    code: String,
+    lines: Vec<(usize, usize)>,
+}
+
+struct Condition {
+    /// 当前分支是否生效
+    active: bool,
+    has_else: bool,
+    /// `#ifdef` 所在的行，报缺少 `#endif` 时用
+    line: usize,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn identifier(text: &str) -> Option<&str> {
+    let valid = text
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(is_identifier_char);
+    valid.then_some(text)
+}
+
+/// 整词替换一行里有值的宏，宏的值里再出现的宏也会展开，`expanding` 防止自引用死循环
+fn substitute(line: &str, defines: &Defines, expanding: &mut Vec<String>) -> String {
+    let (code, comment) = match line.find("//") {
+        Some(at) => line.split_at(at),
+        None => (line, ""),
+    };
+    let mut out = String::with_capacity(line.len());
+    let mut rest = code;
+    while let Some(start) = rest.find(is_identifier_char) {
+        out.push_str(&rest[..start]);
+        let word_len = rest[start..]
+            .find(|c| !is_identifier_char(c))
+            .unwrap_or(rest.len() - start);
+        let word = &rest[start..start + word_len];
+        match defines.get(word) {
+            // 数字（比如 1e5、0x1fu）以数字开头，不会被当成宏
+            Some(value) if !value.is_empty() && !expanding.iter().any(|w| w == word) => {
+                expanding.push(word.to_string());
+                out.push_str(&substitute(value, defines, expanding));
+                expanding.pop();
+            }
+            _ => out.push_str(word),
+        }
+        rest = &rest[start + word_len..];
+    }
+    out.push_str(rest);
+    out.push_str(comment);
+    out
+}
+
+/// 去掉路径里的 `.` 和 `..`，让同一个文件不管从哪里包含都得到同一个路径
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
@@ -0,0 +1,207 @@
+use std::{collections::HashMap, io, path::Path};
+
+use shader_preprocess::{Defines, Preprocessor};
+
+/// 内存里的文件，避免测试依赖磁盘
+fn files(entries: &[(&str, &str)]) -> impl Fn(&Path) -> io::Result<String> {
+    let map: HashMap<String, String> = entries
+        .iter()
+        .map(|&(path, code)| (path.to_string(), code.to_string()))
+        .collect();
+    move |path| {
+        map.get(path.to_str().unwrap())
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+fn lines(code: &str) -> Vec<&str> {
+    code.lines().filter(|l| !l.trim().is_empty()).collect()
+}
+
+#[test]
+fn includes_are_expanded_once() {
+    let loader = files(&[
+        (
+            "shaders/main.wgsl",
+            "#include \"common/particle.wgsl\"\n#include \"helper.wgsl\"\nfn main() {}",
+        ),
+        (
+            "shaders/helper.wgsl",
+            "#include \"./common/../common/particle.wgsl\"\nfn helper() {}",
+        ),
+        (
+            "shaders/common/particle.wgsl",
+            "struct Particle { pos: vec2<f32> };",
+        ),
+    ]);
+    let out = Preprocessor::new(Defines::new())
+        .loader(loader)
+        .process_file("shaders/main.wgsl")
+        .unwrap();
+    assert_eq!(
+        lines(&out.code),
+        [
+            "struct Particle { pos: vec2<f32> };",
+            "fn helper() {}",
+            "fn main() {}"
+        ]
+    );
+    let files: Vec<_> = out.files.iter().map(|f| f.to_str().unwrap()).collect();
+    assert_eq!(
+        files,
+        [
+            "shaders/main.wgsl",
+            "shaders/common/particle.wgsl",
+            "shaders/helper.wgsl"
+        ]
+    );
+}
+
+#[test]
+fn library_includes_search_include_dirs_in_order() {
+    let loader = files(&[
+        ("app/main.wgsl", "#include <random.wgsl>"),
+        ("lib/b/random.wgsl", "fn from_b() {}"),
+        ("lib/c/random.wgsl", "fn from_c() {}"),
+    ]);
+    let out = Preprocessor::new(Defines::new())
+        .include_dir("lib/a")
+        .include_dir("lib/b")
+        .include_dir("lib/c")
+        .loader(loader)
+        .process_file("app/main.wgsl")
+        .unwrap();
+    assert_eq!(lines(&out.code), ["fn from_b() {}"]);
+
+    let error = Preprocessor::new(Defines::new())
+        .loader(files(&[("main.wgsl", "#include <missing.wgsl>")]))
+        .process_file("main.wgsl")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("main.wgsl:1"), "{error}");
+    assert!(error.contains("<missing.wgsl>"), "{error}");
+}
+
+#[test]
+fn injected_constants_override_shader_defaults() {
+    let source = "\
+#ifndef RADIUS
+#define RADIUS 12i
+#endif
+const R: i32 = RADIUS; // RADIUS 在注释里不替换
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)";
+
+    let defaults = Preprocessor::new(Defines::new().set("WORKGROUP_SIZE", 8u32))
+        .process("gauss.wgsl", source)
+        .unwrap();
+    assert_eq!(
+        lines(&defaults.code),
+        [
+            "const R: i32 = 12i; // RADIUS 在注释里不替换",
+            "@compute @workgroup_size(8u, 8u)"
+        ]
+    );
+
+    let injected = Preprocessor::new(Defines::new().set("RADIUS", 5).set("WORKGROUP_SIZE", 16u32))
+        .process("gauss.wgsl", source)
+        .unwrap();
+    assert_eq!(
+        lines(&injected.code),
+        [
+            "const R: i32 = 5i; // RADIUS 在注释里不替换",
+            "@compute @workgroup_size(16u, 16u)"
+        ]
+    );
+}
+
+#[test]
+fn substitution_matches_whole_identifiers_only() {
+    let defines = Defines::new()
+        .set("SIZE", 1.0f32)
+        .set("HALF", "SIZE * 0.5")
+        .set("LOOP", "LOOP + 1");
+    let out = Preprocessor::new(defines)
+        .process(
+            "a.wgsl",
+            "let a = SIZE + MAX_SIZE + SIZE_2 + HALF + LOOP + 1e5;",
+        )
+        .unwrap();
+    assert_eq!(
+        out.code.trim(),
+        "let a = 1.0 + MAX_SIZE + SIZE_2 + 1.0 * 0.5 + LOOP + 1 + 1e5;"
+    );
+}
+
+#[test]
+fn conditionals_nest() {
+    let source = "\
+#ifdef A
+a
+#ifdef B
+ab
+#else
+a_not_b
+#endif
+#else
+not_a
+#ifndef B
+not_a_not_b
+#endif
+#endif";
+    let run = |defines: Defines| {
+        let out = Preprocessor::new(defines)
+            .process("x.wgsl", source)
+            .unwrap();
+        lines(&out.code).join(" ")
+    };
+    assert_eq!(run(Defines::new().flag("A").flag("B")), "a ab");
+    assert_eq!(run(Defines::new().flag("A")), "a a_not_b");
+    assert_eq!(run(Defines::new().flag("B")), "not_a");
+    assert_eq!(run(Defines::new()), "not_a not_a_not_b");
+}
+
+#[test]
+fn errors_point_at_file_and_line() {
+    let cases = [
+        ("fn a() {}\n#ifdef A\nfn b() {}", "x.wgsl:2", "#endif"),
+        ("#endif", "x.wgsl:1", "#endif"),
+        ("\n\n#pragma once", "x.wgsl:3", "#pragma"),
+        ("#define R 1\n#define R 2", "x.wgsl:2", "#ifndef R"),
+        ("#include particle.wgsl", "x.wgsl:1", "#include"),
+    ];
+    for (source, location, message) in cases {
+        let error = Preprocessor::new(Defines::new())
+            .process("x.wgsl", source)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains(location), "{source:?}: {error}");
+        assert!(error.contains(message), "{source:?}: {error}");
+    }
+}
+
+#[test]
+fn expanded_lines_map_back_to_their_files() {
+    let loader = files(&[
+        (
+            "main.wgsl",
+            "#include \"common.wgsl\"\n#ifdef A\nfn skipped() {}\n#endif\nfn main() {}",
+        ),
+        ("common.wgsl", "// 公共定义\nstruct P { x: f32 };"),
+    ]);
+    let out = Preprocessor::new(Defines::new())
+        .loader(loader)
+        .process_file("main.wgsl")
+        .unwrap();
+    assert_eq!(out.code.lines().count(), out.lines.len());
+    let location = |line| {
+        out.source_line(line)
+            .map(|(path, line)| (path.to_str().unwrap(), line))
+    };
+    assert_eq!(location(1), Some(("common.wgsl", 1)));
+    assert_eq!(location(2), Some(("common.wgsl", 2)));
+    // 指令和不生效的分支不占行
+    assert_eq!(location(3), Some(("main.wgsl", 5)));
+    assert_eq!(location(0), None);
+    assert_eq!(location(4), None);
+}
@@ -0,0 +1,561 @@
+//! 用 naga 反射 WGSL，在创建管线之前发现布局错误
+//!
+//! - [`ShaderReflection::validate_vertex_buffers`]：顶点着色器的输入和 `VertexBufferLayout` 是否一致
+//! - [`ShaderReflection::check_struct`]：Rust 结构体大小和 WGSL 结构体布局是否一致
+//! - [`ShaderReflection::bind_group_layout_entries`]：根据着色器里的绑定生成 `BindGroupLayoutEntry`
+use std::{fmt::Write, num::NonZeroU64};
+
+use anyhow::{Context, Result, anyhow, bail};
+use naga::{
+    AddressSpace, Binding, ImageClass, ImageDimension, ScalarKind, ShaderStage, Span,
+    StorageAccess, TypeInner,
+    proc::Layouter,
+    valid::{Capabilities, ModuleInfo, ValidationFlags, Validator},
+};
+use shader_preprocess::Preprocessed;
+
+/// 已经解析并校验过的着色器模块
+pub struct ShaderReflection {
+    module: naga::Module,
+    info: ModuleInfo,
+    layouter: Layouter,
+}
+
+/// 顶点属性的分量类型，`Unorm`/`Snorm` 在着色器里读出来是浮点数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    Float,
+    Sint,
+    Uint,
+}
+
+/// 顶点着色器的一个 `@location` 输入
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexInput {
+    pub name: Option<String>,
+    pub location: u32,
+    pub kind: ComponentKind,
+    pub components: u32,
+}
+
+/// WGSL 结构体的内存布局
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+    pub name: String,
+    /// 结构体大小，已经按对齐向上取整
+    pub size: u32,
+    pub alignment: u32,
+    /// 作为数组元素时的步长
+    pub stride: u32,
+    pub members: Vec<MemberLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberLayout {
+    pub name: Option<String>,
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl ShaderReflection {
+    /// 解析 WGSL 并校验，出错时返回带行列号的 naga 错误信息
+    pub fn from_wgsl(source: &str) -> Result<Self> {
+        Self::from_wgsl_with_path(source, "shader.wgsl")
+    }
+
+    pub fn from_wgsl_with_path(source: &str, path: &str) -> Result<Self> {
+        let module = naga::front::wgsl::parse_str(source)
+            .map_err(|e| anyhow!(e.emit_to_string_with_path(source, path)))?;
+        Self::from_module(module).map_err(|e| anyhow!("{path}: {e}"))
+    }
+
+    /// 解析预处理过的 WGSL，报错的位置换算回 `#include` 之前的文件和行号
+    pub fn from_preprocessed(preprocessed: &Preprocessed) -> Result<Self> {
+        let code = &preprocessed.code;
+        let module = naga::front::wgsl::parse_str(code).map_err(|e| {
+            anyhow!(
+                "{}{}",
+                e.message(),
+                source_locations(preprocessed, e.labels())
+            )
+        })?;
+        let info = validator().validate(&module).map_err(|e| {
+            let spans = e.spans().map(|(span, label)| (*span, label.as_str()));
+            anyhow!(
+                "着色器校验失败：{}{}",
+                e.as_inner(),
+                source_locations(preprocessed, spans)
+            )
+        })?;
+        Self::from_parts(module, info)
+    }
+
+    pub fn from_module(module: naga::Module) -> Result<Self> {
+        let info = validator()
+            .validate(&module)
+            .map_err(|e| anyhow!("着色器校验失败：{}", e.into_inner()))?;
+        Self::from_parts(module, info)
+    }
+
+    fn from_parts(module: naga::Module, info: ModuleInfo) -> Result<Self> {
+        let mut layouter = Layouter::default();
+        layouter
+            .update(module.to_ctx())
+            .map_err(|e| anyhow!("计算类型布局失败：{e}"))?;
+        Ok(Self {
+            module,
+            info,
+            layouter,
+        })
+    }
+
+    pub fn module(&self) -> &naga::Module {
+        &self.module
+    }
+
+    fn entry_point(&self, name: &str) -> Result<(usize, &naga::EntryPoint)> {
+        self.module
+            .entry_points
+            .iter()
+            .enumerate()
+            .find(|(_, ep)| ep.name == name)
+            .ok_or_else(|| anyhow!("着色器里没有入口函数 {name}"))
+    }
+
+    /// 列出顶点入口函数的所有 `@location` 输入，按位置排序
+    pub fn vertex_inputs(&self, entry_point: &str) -> Result<Vec<VertexInput>> {
+        let (_, ep) = self.entry_point(entry_point)?;
+        if ep.stage != ShaderStage::Vertex {
+            bail!("{entry_point} 不是顶点着色器入口");
+        }
+        let mut inputs = Vec::new();
+        for argument in &ep.function.arguments {
+            match &argument.binding {
+                Some(binding) => {
+                    self.push_input(&mut inputs, argument.name.clone(), binding, argument.ty)?
+                }
+                None => {
+                    // 没有绑定的参数一定是结构体，绑定写在成员上
+                    if let TypeInner::Struct { members, .. } = &self.module.types[argument.ty].inner
+                    {
+                        for member in members {
+                            if let Some(binding) = &member.binding {
+                                self.push_input(
+                                    &mut inputs,
+                                    member.name.clone(),
+                                    binding,
+                                    member.ty,
+                                )?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        inputs.sort_by_key(|input| input.location);
+        Ok(inputs)
+    }
+
+    fn push_input(
+        &self,
+        inputs: &mut Vec<VertexInput>,
+        name: Option<String>,
+        binding: &Binding,
+        ty: naga::Handle<naga::Type>,
+    ) -> Result<()> {
+        let Binding::Location { location, .. } = binding else {
+            // @builtin(vertex_index) 之类的内建变量不来自顶点缓冲区
+            return Ok(());
+        };
+        let (scalar, components) = match &self.module.types[ty].inner {
+            TypeInner::Scalar(scalar) => (*scalar, 1),
+            TypeInner::Vector { size, scalar } => (*scalar, *size as u32),
+            other => bail!("顶点输入 @location({location}) 的类型不受支持：{other:?}"),
+        };
+        let kind = match scalar.kind {
+            ScalarKind::Float => ComponentKind::Float,
+            ScalarKind::Sint => ComponentKind::Sint,
+            ScalarKind::Uint => ComponentKind::Uint,
+            other => bail!("顶点输入 @location({location}) 的标量类型不受支持：{other:?}"),
+        };
+        inputs.push(VertexInput {
+            name,
+            location: *location,
+            kind,
+            components,
+        });
+        Ok(())
+    }
+
+    /// 检查顶点着色器的每个输入都能在缓冲区布局里找到，并且分量类型和个数都一致
+    ///
+    /// wgpu 允许格式分量少于着色器（缺的分量会被补成默认值），但这几乎总是写错了，
+    /// 所以这里要求分量个数严格相等。布局里多出来、着色器没用到的属性不算错误。
+    pub fn validate_vertex_buffers(
+        &self,
+        entry_point: &str,
+        buffers: &[wgpu::VertexBufferLayout],
+    ) -> Result<()> {
+        let inputs = self.vertex_inputs(entry_point)?;
+        let mut problems = String::new();
+        for input in &inputs {
+            let label = input.name.as_deref().unwrap_or("?");
+            let attribute = buffers.iter().enumerate().find_map(|(slot, layout)| {
+                layout
+                    .attributes
+                    .iter()
+                    .find(|a| a.shader_location == input.location)
+                    .map(|a| (slot, a))
+            });
+            let Some((slot, attribute)) = attribute else {
+                let _ = writeln!(
+                    problems,
+                    "@location({}) {label}：没有任何顶点缓冲区提供这个属性",
+                    input.location
+                );
+                continue;
+            };
+            let (kind, components) = vertex_format_signature(attribute.format);
+            if kind != input.kind || components != input.components {
+                let _ = writeln!(
+                    problems,
+                    "@location({}) {label}：着色器需要 {:?}x{}，缓冲区 {slot} 提供的是 {:?}",
+                    input.location, input.kind, input.components, attribute.format
+                );
+            }
+            if attribute.offset + attribute.format.size() > buffers[slot].array_stride
+                && buffers[slot].array_stride != 0
+            {
+                let _ = writeln!(
+                    problems,
+                    "@location({}) {label}：偏移 {} 加上格式大小超出了步长 {}",
+                    input.location, attribute.offset, buffers[slot].array_stride
+                );
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            bail!("{entry_point} 的顶点布局和着色器不一致：\n{problems}")
+        }
+    }
+
+    /// 按名字查找 WGSL 结构体的布局
+    pub fn struct_layout(&self, name: &str) -> Option<StructLayout> {
+        let (handle, ty) = self
+            .module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))?;
+        let TypeInner::Struct { members, span } = &ty.inner else {
+            return None;
+        };
+        let layout = self.layouter[handle];
+        Some(StructLayout {
+            name: name.to_string(),
+            size: *span,
+            // `Alignment` 只能通过乘法取出数值
+            alignment: layout.alignment * 1u32,
+            stride: layout.to_stride(),
+            members: members
+                .iter()
+                .map(|m| MemberLayout {
+                    name: m.name.clone(),
+                    offset: m.offset,
+                    size: self.layouter[m.ty].size,
+                })
+                .collect(),
+        })
+    }
+
+    /// 检查 Rust 类型和同名（或指定名字的）WGSL 结构体大小一致
+    ///
+    /// 放进数组时 WGSL 的步长是按对齐取整后的大小，所以 Rust 一侧的填充也必须补齐。
+    pub fn check_struct<T>(&self, wgsl_name: &str) -> Result<()> {
+        let layout = self
+            .struct_layout(wgsl_name)
+            .with_context(|| format!("着色器里没有结构体 {wgsl_name}"))?;
+        let rust_size = std::mem::size_of::<T>() as u32;
+        let stride = layout.stride;
+        if rust_size != stride {
+            let members = layout
+                .members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "  {} @ {} ({} 字节)",
+                        m.name.as_deref().unwrap_or("?"),
+                        m.offset,
+                        m.size
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            bail!(
+                "{} 是 {rust_size} 字节，但 WGSL 结构体 {wgsl_name} 是 {stride} 字节（对齐 {}）：\n{members}",
+                std::any::type_name::<T>(),
+                layout.alignment
+            );
+        }
+        Ok(())
+    }
+
+    /// 根据着色器里 `@group(group)` 的绑定生成布局条目，按 binding 排序
+    ///
+    /// 着色器里看不出纹理是否可过滤，浮点纹理一律按可过滤、采样器按 `Filtering` 生成，
+    /// 需要 `NonFiltering` 时可以在返回的条目上修改。
+    pub fn bind_group_layout_entries(&self, group: u32) -> Result<Vec<wgpu::BindGroupLayoutEntry>> {
+        let mut entries = Vec::new();
+        for (handle, global) in self.module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+            if binding.group != group {
+                continue;
+            }
+
+            let mut visibility = wgpu::ShaderStages::NONE;
+            for (index, ep) in self.module.entry_points.iter().enumerate() {
+                if !self.info.get_entry_point(index)[handle].is_empty() {
+                    visibility |= match ep.stage {
+                        ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+                        ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+                        ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+                        _ => wgpu::ShaderStages::NONE,
+                    };
+                }
+            }
+
+            let name = global.name.as_deref().unwrap_or("?");
+            let inner = &self.module.types[global.ty].inner;
+            let ty = match global.space {
+                AddressSpace::Uniform => wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: self.min_binding_size(global.ty),
+                },
+                AddressSpace::Storage { access } => wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: !access.contains(StorageAccess::STORE),
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: self.min_binding_size(global.ty),
+                },
+                AddressSpace::Handle => match inner {
+                    TypeInner::Sampler { comparison } => {
+                        wgpu::BindingType::Sampler(if *comparison {
+                            wgpu::SamplerBindingType::Comparison
+                        } else {
+                            wgpu::SamplerBindingType::Filtering
+                        })
+                    }
+                    TypeInner::Image {
+                        dim,
+                        arrayed,
+                        class,
+                    } => image_binding_type(*dim, *arrayed, *class)
+                        .with_context(|| format!("绑定 {name} 的纹理类型不受支持"))?,
+                    other => bail!("绑定 {name} 的类型不受支持：{other:?}"),
+                },
+                other => bail!("绑定 {name} 的地址空间不受支持：{other:?}"),
+            };
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: binding.binding,
+                visibility,
+                ty,
+                count: None,
+            });
+        }
+        entries.sort_by_key(|e| e.binding);
+        Ok(entries)
+    }
+
+    /// 直接从着色器创建绑定组布局
+    pub fn create_bind_group_layout(
+        &self,
+        device: &wgpu::Device,
+        group: u32,
+        label: Option<&str>,
+    ) -> Result<wgpu::BindGroupLayout> {
+        let entries = self.bind_group_layout_entries(group)?;
+        Ok(
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label,
+                entries: &entries,
+            }),
+        )
+    }
+
+    /// 运行时数组（`array<T>`）的大小是 0，这时至少要放得下一个元素
+    fn min_binding_size(&self, ty: naga::Handle<naga::Type>) -> Option<NonZeroU64> {
+        let size = match &self.module.types[ty].inner {
+            TypeInner::Array {
+                size: naga::ArraySize::Dynamic,
+                stride,
+                ..
+            } => *stride,
+            TypeInner::Struct { span, .. } => *span,
+            _ => self.layouter[ty].size,
+        };
+        NonZeroU64::new(size as u64)
+    }
+}
+
+fn image_binding_type(
+    dim: ImageDimension,
+    arrayed: bool,
+    class: ImageClass,
+) -> Option<wgpu::BindingType> {
+    let view_dimension = match (dim, arrayed) {
+        (ImageDimension::D1, false) => wgpu::TextureViewDimension::D1,
+        (ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+        (ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+        (ImageDimension::D3, false) => wgpu::TextureViewDimension::D3,
+        (ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+        (ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+        _ => return None,
+    };
+    let ty = match class {
+        ImageClass::Sampled { kind, multi } => wgpu::BindingType::Texture {
+            sample_type: match kind {
+                ScalarKind::Float => wgpu::TextureSampleType::Float { filterable: !multi },
+                ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                _ => return None,
+            },
+            view_dimension,
+            multisampled: multi,
+        },
+        ImageClass::Depth { multi } => wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Depth,
+            view_dimension,
+            multisampled: multi,
+        },
+        ImageClass::Storage { format, access } => wgpu::BindingType::StorageTexture {
+            access: match (
+                access.contains(StorageAccess::LOAD),
+                access.contains(StorageAccess::STORE),
+            ) {
+                (true, true) => wgpu::StorageTextureAccess::ReadWrite,
+                (true, false) => wgpu::StorageTextureAccess::ReadOnly,
+                _ => wgpu::StorageTextureAccess::WriteOnly,
+            },
+            format: storage_format(format)?,
+            view_dimension,
+        },
+        ImageClass::External => return None,
+    };
+    Some(ty)
+}
+
+fn storage_format(format: naga::StorageFormat) -> Option<wgpu::TextureFormat> {
+    use naga::StorageFormat as S;
+    use wgpu::TextureFormat as T;
+    let format = match format {
+        S::R8Unorm => T::R8Unorm,
+        S::R8Snorm => T::R8Snorm,
+        S::R8Uint => T::R8Uint,
+        S::R8Sint => T::R8Sint,
+        S::R16Uint => T::R16Uint,
+        S::R16Sint => T::R16Sint,
+        S::R16Float => T::R16Float,
+        S::Rg8Unorm => T::Rg8Unorm,
+        S::Rg8Snorm => T::Rg8Snorm,
+        S::Rg8Uint => T::Rg8Uint,
+        S::Rg8Sint => T::Rg8Sint,
+        S::R32Uint => T::R32Uint,
+        S::R32Sint => T::R32Sint,
+        S::R32Float => T::R32Float,
+        S::Rg16Uint => T::Rg16Uint,
+        S::Rg16Sint => T::Rg16Sint,
+        S::Rg16Float => T::Rg16Float,
+        S::Rgba8Unorm => T::Rgba8Unorm,
+        S::Rgba8Snorm => T::Rgba8Snorm,
+        S::Rgba8Uint => T::Rgba8Uint,
+        S::Rgba8Sint => T::Rgba8Sint,
+        S::Bgra8Unorm => T::Bgra8Unorm,
+        S::Rgb10a2Uint => T::Rgb10a2Uint,
+        S::Rgb10a2Unorm => T::Rgb10a2Unorm,
+        S::Rg11b10Ufloat => T::Rg11b10Ufloat,
+        S::Rg32Uint => T::Rg32Uint,
+        S::Rg32Sint => T::Rg32Sint,
+        S::Rg32Float => T::Rg32Float,
+        S::Rgba16Uint => T::Rgba16Uint,
+        S::Rgba16Sint => T::Rgba16Sint,
+        S::Rgba16Float => T::Rgba16Float,
+        S::Rgba32Uint => T::Rgba32Uint,
+        S::Rgba32Sint => T::Rgba32Sint,
+        S::Rgba32Float => T::Rgba32Float,
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// 顶点格式在着色器里读出来的分量类型和个数
+pub fn vertex_format_signature(format: wgpu::VertexFormat) -> (ComponentKind, u32) {
+    use ComponentKind::{Float, Sint, Uint};
+    use wgpu::VertexFormat as F;
+    // 不写 `_` 分支，wgpu 增加新格式时这里编译不过，提醒补上
+    match format {
+        F::Uint8 | F::Uint16 | F::Uint32 => (Uint, 1),
+        F::Uint8x2 | F::Uint16x2 | F::Uint32x2 => (Uint, 2),
+        F::Uint32x3 => (Uint, 3),
+        F::Uint8x4 | F::Uint16x4 | F::Uint32x4 => (Uint, 4),
+        F::Sint8 | F::Sint16 | F::Sint32 => (Sint, 1),
+        F::Sint8x2 | F::Sint16x2 | F::Sint32x2 => (Sint, 2),
+        F::Sint32x3 => (Sint, 3),
+        F::Sint8x4 | F::Sint16x4 | F::Sint32x4 => (Sint, 4),
+        // 归一化格式在着色器里读出来是浮点数
+        F::Unorm8 | F::Snorm8 | F::Unorm16 | F::Snorm16 | F::Float16 | F::Float32 | F::Float64 => {
+            (Float, 1)
+        }
+        F::Unorm8x2
+        | F::Snorm8x2
+        | F::Unorm16x2
+        | F::Snorm16x2
+        | F::Float16x2
+        | F::Float32x2
+        | F::Float64x2 => (Float, 2),
+        F::Float32x3 | F::Float64x3 => (Float, 3),
+        F::Unorm8x4
+        | F::Snorm8x4
+        | F::Unorm16x4
+        | F::Snorm16x4
+        | F::Float16x4
+        | F::Float32x4
+        | F::Float64x4
+        // 打包格式，名字里看不出分量个数
+        | F::Unorm10_10_10_2
+        | F::Unorm8x4Bgra => (Float, 4),
+    }
+}
+
+fn validator() -> Validator {
+    Validator::new(ValidationFlags::all(), Capabilities::all())
+}
+
+/// 每个标注一段：原文件和行号、展开后的那一行代码、标注的说明
+fn source_locations<'a>(
+    preprocessed: &Preprocessed,
+    spans: impl Iterator<Item = (Span, &'a str)>,
+) -> String {
+    let code = &preprocessed.code;
+    let mut out = String::new();
+    for (span, label) in spans.filter(|(span, _)| span.is_defined()) {
+        let line = span.location(code).line_number as usize;
+        let text = code.lines().nth(line - 1).unwrap_or_default().trim();
+        match preprocessed.source_line(line) {
+            Some((path, line)) => {
+                let _ = write!(out, "\n  --> {}:{line}", path.display());
+            }
+            None => {
+                let _ = write!(out, "\n  --> 展开后第 {line} 行");
+            }
+        }
+        let _ = write!(out, "\n   | {text}");
+        if !label.is_empty() {
+            let _ = write!(out, "\n   = {label}");
+        }
+    }
+    out
+}
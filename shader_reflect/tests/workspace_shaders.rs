@@ -0,0 +1,426 @@
+use std::path::{Path, PathBuf};
+
+use shader_preprocess::{Defines, Preprocessor};
+use shader_reflect::ShaderReflection;
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn collect_wgsl(dir: &Path, out: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            let name = path.file_name().unwrap();
+            if name != "target" && name != ".git" {
+                collect_wgsl(&path, out);
+            }
+        } else if path.extension().is_some_and(|e| e == "wgsl") {
+            out.push(path);
+        }
+    }
+}
+
+/// 展开 `#include` 和宏，`#include <...>` 在 gpu/wgsl 里查找
+fn preprocess(path: &Path) -> String {
+    Preprocessor::new(Defines::new())
+        .include_dir(workspace_root().join("gpu/wgsl"))
+        .process_file(path)
+        .unwrap_or_else(|e| panic!("{e:#}"))
+        .code
+}
+
+fn load(relative: &str) -> ShaderReflection {
+    let source = preprocess(&workspace_root().join(relative));
+    ShaderReflection::from_wgsl_with_path(&source, relative).unwrap()
+}
+
+#[test]
+fn every_workspace_shader_parses_and_validates() {
+    let mut shaders = Vec::new();
+    collect_wgsl(&workspace_root(), &mut shaders);
+    assert!(shaders.len() >= 10, "只找到 {} 个着色器", shaders.len());
+
+    let mut failures = Vec::new();
+    for path in &shaders {
+        let source = preprocess(path);
+        let display = path
+            .strip_prefix(workspace_root())
+            .unwrap()
+            .display()
+            .to_string();
+        if let Err(e) = ShaderReflection::from_wgsl_with_path(&source, &display) {
+            failures.push(format!("{e:#}"));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// 入口文件先包含 common.wgsl，再在第 4 行写 `body`，返回报错信息
+fn error_after_include(common: &str, body: &str) -> String {
+    let main = format!("#include \"common.wgsl\"\n\nfn main() -> f32 {{\n{body}\n}}");
+    let preprocessed = Preprocessor::new(Defines::new())
+        .loader(|path: &Path| match path.to_str().unwrap() {
+            "shaders/main.wgsl" => Ok(main.clone()),
+            "shaders/common.wgsl" => Ok(common.to_string()),
+            _ => Err(std::io::ErrorKind::NotFound.into()),
+        })
+        .process_file("shaders/main.wgsl")
+        .unwrap();
+    match ShaderReflection::from_preprocessed(&preprocessed) {
+        Ok(_) => panic!("应该报错"),
+        Err(e) => format!("{e:#}"),
+    }
+}
+
+#[test]
+fn errors_after_an_include_point_at_the_original_file() {
+    const COMMON: &str = "struct P {\n    x: f32,\n};\n\nfn scale() -> f32 {\n    return 2.0;\n}";
+    // 展开后错误在第 10 行，要报入口文件的第 4 行
+    let message = error_after_include(COMMON, "    return undefined_name;");
+    assert!(message.contains("shaders/main.wgsl:4"), "{message}");
+    assert!(message.contains("return undefined_name;"), "{message}");
+
+    // 校验错误同样换算
+    let message = error_after_include(COMMON, "    return 1u;");
+    assert!(message.contains("shaders/main.wgsl:4"), "{message}");
+
+    // 错误在被包含的文件里
+    let message = error_after_include(&COMMON.replace("2.0", "missing"), "    return scale();");
+    assert!(message.contains("shaders/common.wgsl:6"), "{message}");
+}
+
+#[test]
+fn every_vertex_entry_point_has_reflectable_inputs() {
+    let mut shaders = Vec::new();
+    collect_wgsl(&workspace_root(), &mut shaders);
+    for path in &shaders {
+        let source = preprocess(path);
+        let reflection = ShaderReflection::from_wgsl(&source).unwrap();
+        for ep in &reflection.module().entry_points {
+            if ep.stage == naga::ShaderStage::Vertex {
+                reflection
+                    .vertex_inputs(&ep.name)
+                    .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+            }
+        }
+    }
+}
+
+#[test]
+fn lesson_vertices_match_their_shaders() {
+    // 着色器、顶点入口、对应的顶点缓冲区布局
+    let table = [
+        (
+            "render/assets/wgsls/demo.wgsl",
+            "vs_main",
+            render::render::mesh::Vertex::desc(),
+        ),
+        (
+            "vertex_buffer/src/wgsls/shader.wgsl",
+            "vs_main",
+            vertex_buffer::Vertex::desc(),
+        ),
+        (
+            "index_buffer/src/wgsls/shader.wgsl",
+            "vs_main",
+            index_buffer::Vertex::desc(),
+        ),
+        (
+            "texture/src/wgsls/shader.wgsl",
+            "vs_main",
+            texture::Vertex::desc(),
+        ),
+        (
+            "transform/src/wgsls/shader.wgsl",
+            "vs_main",
+            transform::Vertex::desc(),
+        ),
+        (
+            "copy_texture_to_texture/src/shaders/vertex.wgsl",
+            "vertex",
+            copy_texture_to_texture::vertex::Mesh::desc(),
+        ),
+        (
+            "copy_texture_to_texture/src/shaders/texture.wgsl",
+            "vertex",
+            copy_texture_to_texture::vertex::Mesh::desc(),
+        ),
+    ];
+
+    let mut failures = Vec::new();
+    for (shader, entry_point, layout) in table {
+        if let Err(e) = load(shader).validate_vertex_buffers(entry_point, &[layout]) {
+            failures.push(format!("{shader}：{e}"));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn mismatched_vertex_format_is_reported() {
+    let reflection = load("render/assets/wgsls/demo.wgsl");
+    // 修复前的 render::mesh::Vertex：颜色是 4 个分量，布局却写成 Float32x3
+    let attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+    let layout = wgpu::VertexBufferLayout {
+        array_stride: 28,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &attributes,
+    };
+    let error = reflection
+        .validate_vertex_buffers("vs_main", &[layout])
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("@location(1)"), "{error}");
+
+    let missing = wgpu::VertexBufferLayout {
+        array_stride: 12,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &attributes[..1],
+    };
+    assert!(
+        reflection
+            .validate_vertex_buffers("vs_main", &[missing])
+            .is_err()
+    );
+}
+
+#[test]
+fn vertex_formats_map_to_shader_components() {
+    use shader_reflect::{ComponentKind, vertex_format_signature};
+    use wgpu::VertexFormat as F;
+    for (format, expected) in [
+        (F::Uint8, (ComponentKind::Uint, 1)),
+        (F::Sint16x2, (ComponentKind::Sint, 2)),
+        (F::Uint32x3, (ComponentKind::Uint, 3)),
+        // 归一化的整数格式读出来是浮点数
+        (F::Snorm8x4, (ComponentKind::Float, 4)),
+        (F::Unorm16, (ComponentKind::Float, 1)),
+        (F::Float64x3, (ComponentKind::Float, 3)),
+        (F::Unorm10_10_10_2, (ComponentKind::Float, 4)),
+        (F::Unorm8x4Bgra, (ComponentKind::Float, 4)),
+    ] {
+        assert_eq!(vertex_format_signature(format), expected, "{format:?}");
+    }
+}
+
+#[test]
+fn particle_struct_matches_every_particle_shader() {
+    for shader in [
+        "compute_particle/src/wgsls/compute.wgsl",
+        "compute_particle/src/wgsls/compute_init.wgsl",
+        "compute_particle/src/wgsls/shader.wgsl",
+    ] {
+        load(shader)
+            .check_struct::<compute_particle::Particle>("Particle")
+            .unwrap_or_else(|e| panic!("{shader}: {e}"));
+    }
+}
+
+/// 检查一个 Rust 类型和着色器里的结构体，`T` 不同的检查可以放进同一张表
+type StructCheck = fn(&ShaderReflection, &str) -> anyhow::Result<()>;
+
+#[test]
+fn simulation_structs_match_their_shaders() {
+    use compute_particle::{
+        Particle,
+        boids::FlockParams,
+        emitter::GpuEmitter,
+        forces::GpuForceField,
+        grid::GridParams,
+        lifecycle::{Counters, SimParams},
+        nbody::{Energy, NBodyParams},
+        render_params::RenderParams,
+        sort::{SortEntry, SortStage},
+        sph::SphParams,
+    };
+
+    // 着色器、WGSL 结构体名、对应的 Rust 类型
+    let table: &[(&str, &str, StructCheck)] = &[
+        (
+            "compute.wgsl",
+            "SimParams",
+            ShaderReflection::check_struct::<SimParams>,
+        ),
+        (
+            "compute.wgsl",
+            "Counters",
+            ShaderReflection::check_struct::<Counters>,
+        ),
+        (
+            "compute.wgsl",
+            "Emitter",
+            ShaderReflection::check_struct::<GpuEmitter>,
+        ),
+        (
+            "compute.wgsl",
+            "ForceField",
+            ShaderReflection::check_struct::<GpuForceField>,
+        ),
+        (
+            "shader.wgsl",
+            "RenderParams",
+            ShaderReflection::check_struct::<RenderParams>,
+        ),
+        (
+            "sort.wgsl",
+            "SortEntry",
+            ShaderReflection::check_struct::<SortEntry>,
+        ),
+        (
+            "sort.wgsl",
+            "SortStage",
+            ShaderReflection::check_struct::<SortStage>,
+        ),
+        (
+            "sort.wgsl",
+            "Particle",
+            ShaderReflection::check_struct::<Particle>,
+        ),
+        (
+            "boids.wgsl",
+            "FlockParams",
+            ShaderReflection::check_struct::<FlockParams>,
+        ),
+        (
+            "boids.wgsl",
+            "GridParams",
+            ShaderReflection::check_struct::<GridParams>,
+        ),
+        (
+            "nbody.wgsl",
+            "NBodyParams",
+            ShaderReflection::check_struct::<NBodyParams>,
+        ),
+        (
+            "nbody.wgsl",
+            "Energy",
+            ShaderReflection::check_struct::<Energy>,
+        ),
+        (
+            "sph.wgsl",
+            "SphParams",
+            ShaderReflection::check_struct::<SphParams>,
+        ),
+        (
+            "sph.wgsl",
+            "GridParams",
+            ShaderReflection::check_struct::<GridParams>,
+        ),
+    ];
+
+    // 每一行都检查完再报告，一次看到所有不一致的结构体
+    let mut failures = Vec::new();
+    for &(shader, name, check) in table {
+        let path = format!("compute_particle/src/wgsls/{shader}");
+        if let Err(e) = check(&load(&path), name) {
+            failures.push(format!("{shader} 的 {name}：{e}"));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn unpadded_struct_is_rejected() {
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct UnpaddedParticle {
+        pos: [f32; 2],
+        vel: [f32; 2],
+        color: [f32; 4],
+        life: f32,
+    }
+    let reflection = load("compute_particle/src/wgsls/compute.wgsl");
+    assert!(
+        reflection
+            .check_struct::<UnpaddedParticle>("Particle")
+            .is_err()
+    );
+    let layout = reflection.struct_layout("Particle").unwrap();
+    assert_eq!(layout.stride, 48);
+    assert_eq!(layout.members[3].offset, 32);
+}
+
+#[test]
+fn bind_group_layouts_are_generated_from_bindings() {
+    let compute = load("compute_particle/src/wgsls/compute.wgsl");
+    let entries = compute.bind_group_layout_entries(0).unwrap();
+    assert_eq!(entries.len(), 7);
+    assert_eq!(entries[0].visibility, wgpu::ShaderStages::COMPUTE);
+    // 上一帧的粒子只读，新状态写进绑定 5
+    assert!(matches!(
+        entries[0].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            ..
+        }
+    ));
+    assert_eq!(entries[5].binding, 5);
+    assert!(matches!(
+        entries[5].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            ..
+        }
+    ));
+    // 死亡粒子的号码由发号码的入口写、更新的入口读
+    assert_eq!(entries[6].binding, 6);
+    assert!(matches!(
+        entries[1].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            ..
+        }
+    ));
+    assert!(matches!(
+        entries[2].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            ..
+        }
+    ));
+    assert!(matches!(
+        entries[3].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            ..
+        }
+    ));
+
+    let render = load("compute_particle/src/wgsls/shader.wgsl");
+    let entries = render.bind_group_layout_entries(0).unwrap();
+    assert_eq!(entries[0].visibility, wgpu::ShaderStages::VERTEX);
+    assert!(matches!(
+        entries[0].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            ..
+        }
+    ));
+    assert!(matches!(
+        entries[1].ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            ..
+        }
+    ));
+
+    let gauss = load("render_to_image/assets/compute_gauss.wgsl");
+    let entries = gauss.bind_group_layout_entries(0).unwrap();
+    assert!(matches!(
+        entries[1].ty,
+        wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            ..
+        }
+    ));
+
+    let texture = load("texture/src/wgsls/shader.wgsl");
+    let entries = texture.bind_group_layout_entries(0).unwrap();
+    assert_eq!(entries[0].visibility, wgpu::ShaderStages::FRAGMENT);
+    assert!(matches!(entries[1].ty, wgpu::BindingType::Sampler(_)));
+}
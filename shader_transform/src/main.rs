@@ -3,5 +3,6 @@ mod app;
 use app::App;
 
 fn main() {
+    gpu::logging::init();
     App::run();
 }
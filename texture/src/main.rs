@@ -2,5 +2,6 @@ mod app;
 use app::App;
 
 fn main() {
+    gpu::logging::init();
     App::run();
 }
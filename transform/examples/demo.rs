@@ -2,6 +2,7 @@ use glam::{Quat, Vec3, Vec4};
 
 fn main() {
     let v4 = Vec4::new(1.0, 2.0, 3.0, 0.0);
+    println!("v4: {:?}", v4);
 
     // 创建一个平移矩阵
     let translation = glam::Mat4::from_translation(glam::Vec3::new(0.5, 0.0, 0.0));
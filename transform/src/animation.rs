@@ -0,0 +1,365 @@
+//! 关键帧动画
+//!
+//! 纯 CPU 逻辑，不依赖 wgpu，可以脱离渲染单独测试。
+//! 一个 [`Animation`] 由若干条轨道（平移、旋转、缩放、颜色）组成，
+//! 每条轨道是按时间排序的关键帧，关键帧之间按缓动曲线插值。
+use std::time::Duration;
+
+use glam::{Mat4, Quat, Vec3, Vec4};
+
+/// 缓动曲线，把归一化的进度 `t ∈ [0, 1]` 映射为插值系数
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    /// 匀速
+    #[default]
+    Linear,
+    /// 三次方，先慢后快
+    CubicIn,
+    /// 三次方，先快后慢
+    CubicOut,
+    /// 三次方，两头慢中间快
+    CubicInOut,
+    /// 弹性，冲过终点后来回振荡并收敛
+    ElasticOut,
+    /// 和 CSS `cubic-bezier(x1, y1, x2, y2)` 一致，起点 (0,0)，终点 (1,1)
+    Bezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    return t;
+                }
+                // 周期为 0.3，振幅按 2^(-10t) 衰减
+                let c4 = std::f32::consts::TAU / 3.0;
+                2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c4).sin() + 1.0
+            }
+            Easing::Bezier(x1, y1, x2, y2) => {
+                let s = solve_bezier_x(t, x1, x2);
+                bezier(s, y1, y2)
+            }
+        }
+    }
+}
+
+/// 一维三次贝塞尔，端点固定为 0 和 1
+fn bezier(s: f32, p1: f32, p2: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+}
+
+fn bezier_derivative(s: f32, p1: f32, p2: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * p1 + 6.0 * inv * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
+
+/// 求贝塞尔参数 s，使得 x(s) = x
+///
+/// 先用牛顿迭代，导数太小时退回二分法。
+fn solve_bezier_x(x: f32, x1: f32, x2: f32) -> f32 {
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(s, x1, x2) - x;
+        if error.abs() < 1e-6 {
+            return s;
+        }
+        let derivative = bezier_derivative(s, x1, x2);
+        if derivative.abs() < 1e-6 {
+            break;
+        }
+        s -= error / derivative;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(s, x1, x2);
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) * 0.5;
+    }
+    s
+}
+
+/// 可以在两个关键帧之间插值的类型
+pub trait Interpolate: Copy {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Interpolate for Vec4 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+/// 旋转使用球面插值，保证角速度均匀
+impl Interpolate for Quat {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.slerp(*other, t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T> {
+    /// 关键帧所在时间（秒）
+    pub time: f32,
+    pub value: T,
+    /// 从这一帧过渡到下一帧时使用的缓动曲线
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f32, value: T) -> Self {
+        Self {
+            time,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// 一条关键帧轨道，关键帧总是按时间升序保存
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keyframes }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// 最后一个关键帧的时间，空轨道为 0
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// 在给定时间采样，超出范围时取首尾关键帧的值
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keyframes.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        // 第一个时间大于 time 的关键帧
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        let Some(to) = self.keyframes.get(next) else {
+            return self.keyframes.last().map(|k| k.value);
+        };
+        let from = &self.keyframes[next - 1];
+        let span = to.time - from.time;
+        let t = if span > 0.0 {
+            (time - from.time) / span
+        } else {
+            1.0
+        };
+        Some(from.value.interpolate(&to.value, from.easing.apply(t)))
+    }
+}
+
+/// 播放到结尾之后怎么办
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaybackMode {
+    /// 停在最后一帧
+    #[default]
+    Once,
+    /// 从头再来
+    Loop,
+    /// 倒着播回去，再正着播，来回往复
+    PingPong,
+}
+
+impl PlaybackMode {
+    /// 把播放器的累计时间换算成动画内部的时间
+    pub fn local_time(&self, time: f32, duration: f32) -> f32 {
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            PlaybackMode::Once => time.clamp(0.0, duration),
+            PlaybackMode::Loop => time.rem_euclid(duration),
+            PlaybackMode::PingPong => {
+                let t = time.rem_euclid(duration * 2.0);
+                if t > duration { duration * 2.0 - t } else { t }
+            }
+        }
+    }
+}
+
+/// 一次采样的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationSample {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    pub color: Vec4,
+}
+
+impl Default for AnimationSample {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+            color: Vec4::ONE,
+        }
+    }
+}
+
+impl AnimationSample {
+    /// 模型矩阵：先缩放、再旋转、最后平移
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+/// 由多条轨道组成的动画，没有设置的轨道保持默认值
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Animation {
+    pub translation: Option<Track<Vec3>>,
+    pub rotation: Option<Track<Quat>>,
+    pub scale: Option<Track<Vec3>>,
+    /// 颜色，和顶点颜色相乘
+    pub color: Option<Track<Vec4>>,
+    pub mode: PlaybackMode,
+}
+
+impl Animation {
+    pub fn new(mode: PlaybackMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_translation(mut self, keyframes: Vec<Keyframe<Vec3>>) -> Self {
+        self.translation = Some(Track::new(keyframes));
+        self
+    }
+
+    pub fn with_rotation(mut self, keyframes: Vec<Keyframe<Quat>>) -> Self {
+        self.rotation = Some(Track::new(keyframes));
+        self
+    }
+
+    pub fn with_scale(mut self, keyframes: Vec<Keyframe<Vec3>>) -> Self {
+        self.scale = Some(Track::new(keyframes));
+        self
+    }
+
+    pub fn with_color(mut self, keyframes: Vec<Keyframe<Vec4>>) -> Self {
+        self.color = Some(Track::new(keyframes));
+        self
+    }
+
+    /// 所有轨道中最长的那条决定动画时长
+    pub fn duration(&self) -> f32 {
+        [
+            self.translation.as_ref().map(Track::duration),
+            self.rotation.as_ref().map(Track::duration),
+            self.scale.as_ref().map(Track::duration),
+            self.color.as_ref().map(Track::duration),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0.0, f32::max)
+    }
+
+    /// 按播放器的累计时间采样，会先根据播放模式换算时间
+    pub fn sample(&self, time: f32) -> AnimationSample {
+        let time = self.mode.local_time(time, self.duration());
+        let default = AnimationSample::default();
+        AnimationSample {
+            translation: sample_or(&self.translation, time, default.translation),
+            rotation: sample_or(&self.rotation, time, default.rotation),
+            scale: sample_or(&self.scale, time, default.scale),
+            color: sample_or(&self.color, time, default.color),
+        }
+    }
+}
+
+fn sample_or<T: Interpolate>(track: &Option<Track<T>>, time: f32, default: T) -> T {
+    track
+        .as_ref()
+        .and_then(|track| track.sample(time))
+        .unwrap_or(default)
+}
+
+/// 动画播放器，负责累计时间
+///
+/// 速度只和传进来的 `delta_time` 有关，和 `update` 调用的频率无关。
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    pub animation: Animation,
+    /// 累计播放时间（秒）
+    pub time: f32,
+    /// 播放速度倍率，负数表示倒放
+    pub speed: f32,
+    pub playing: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            animation,
+            time: 0.0,
+            speed: 1.0,
+            playing: true,
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: Duration) {
+        if self.playing {
+            self.time += delta_time.as_secs_f32() * self.speed;
+        }
+    }
+
+    pub fn sample(&self) -> AnimationSample {
+        self.animation.sample(self.time)
+    }
+
+    /// 单次播放模式下是否已经播完
+    pub fn finished(&self) -> bool {
+        self.animation.mode == PlaybackMode::Once && self.time >= self.animation.duration()
+    }
+}
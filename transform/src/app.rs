@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use transform::State;
 use winit::{
@@ -9,20 +9,21 @@ use winit::{
 };
 
 #[derive(Default)]
-pub struct App<'window> {
-    window: Option<Window>,
-    state: Option<State<'window>>,
+pub struct App {
+    window: Option<Arc<Window>>,
+    state: Option<State>,
     time: Option<Instant>,
 }
-impl ApplicationHandler for App<'_> {
+impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         self.window = Some(
             event_loop
                 .create_window(Window::default_attributes())
+                .map(Arc::new)
                 .unwrap(),
         );
         if let Some(window) = &self.window {
-            self.state = Some(futures::executor::block_on(State::new(window)).unwrap());
+            self.state = Some(futures::executor::block_on(State::new(window.clone())).unwrap());
         }
         self.time = Some(Instant::now());
     }
@@ -39,14 +40,13 @@ impl ApplicationHandler for App<'_> {
             }
             WindowEvent::RedrawRequested => {
                 if let Some(state) = &mut self.state {
-                    state.update();
                     match state.render() {
                         Ok(_) => {}
                         Err(wgpu::SurfaceError::Lost) => {
-                            dbg!("surface lost");
+                            tracing::warn!("surface lost");
                         }
                         Err(e) => {
-                            eprintln!("{:?}", e);
+                            tracing::error!("{e:?}");
                         }
                     }
                 }
@@ -68,14 +68,14 @@ impl ApplicationHandler for App<'_> {
         if duration.as_secs_f32() > 1.0 / 60.0 {
             self.time = Some(new_time);
             if let Some(state) = &mut self.state {
-                state.update();
+                state.update(duration);
                 state.render().unwrap();
             }
         }
     }
 }
 
-impl App<'_> {
+impl App {
     pub fn run() {
         let event_loop = EventLoop::new().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
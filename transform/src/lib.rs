@@ -1,54 +1,30 @@
-use anyhow::anyhow;
-use glam::{Mat4, Vec3};
-use wgpu::{SurfaceError, util::DeviceExt};
-use winit::window::Window;
-pub struct State<'window> {
-    surface: wgpu::Surface<'window>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
+use std::{sync::Arc, time::Duration};
+
+use animation::{Animation, AnimationPlayer, Easing, Keyframe, PlaybackMode};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use gpu::{ContextOptions, GpuContext, Lesson, Tracked, validation};
+use vertex_layout::VertexLayout;
+use wgpu::SurfaceError;
+use winit::{dpi::PhysicalSize, window::Window};
+
+pub mod animation;
+
+pub struct State {
+    gpu: GpuContext,
     render_pipeline: wgpu::RenderPipeline,
+    /// 动画之前的原始顶点，每帧都从这里重新计算
+    base_vertex: [Vertex; 3],
     vertex: [Vertex; 3],
-    vertex_buffer: wgpu::Buffer,
+    vertex_buffer: Tracked<wgpu::Buffer>,
     num_vertices: u32,
+    player: AnimationPlayer,
 }
 
-impl State<'_> {
-    pub async fn new(window: &Window) -> anyhow::Result<Self> {
-        let (instance, _backend) = create_wgpu_instance().await?;
-        let surface = unsafe {
-            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(&window)?)
-        }?;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                label: Some("Device"),
-                memory_hints: wgpu::MemoryHints::default(),
-                trace: wgpu::Trace::Off,
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            })
-            .await?;
-        let caps = surface.get_capabilities(&adapter);
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: caps.formats[0],
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&device, &config);
+impl State {
+    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+        let gpu = GpuContext::windowed(window, &ContextOptions::default()).await?;
+        let device = &gpu.device;
+        let config = gpu.config();
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
@@ -60,44 +36,47 @@ impl State<'_> {
                 bind_group_layouts: &[],
                 immediate_size: 0,
             });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                compilation_options: Default::default(),
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            // 图元，描述了如何将顶点数据转换为图元
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+        let render_pipeline = validation::create_render_pipeline(
+            device,
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("Render Pipeline"),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[Vertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    compilation_options: Default::default(),
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                // 图元，描述了如何将顶点数据转换为图元
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview_mask: None,
+                cache: gpu.pipeline_cache(),
             },
-            multiview_mask: None,
-            cache: None,
-        });
+        )?;
         let vertex = [
             Vertex {
                 position: Vec3::from_array([0.0, 0.5, 0.0]),
@@ -112,28 +91,30 @@ impl State<'_> {
                 color: Vec3::from_array([0.0, 0.0, 1.0]),
             },
         ];
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertex),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let vertex_buffer = gpu
+            .resources
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertex),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
         Ok(Self {
-            surface,
-            device,
-            queue,
-            config,
+            gpu,
             render_pipeline,
+            base_vertex: vertex,
             vertex,
             vertex_buffer,
             num_vertices: vertex.len() as u32,
+            player: AnimationPlayer::new(demo_animation()),
         })
     }
     pub fn render(&mut self) -> Result<(), SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let output = self.gpu.surface().get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self
+            .gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
@@ -164,83 +145,95 @@ impl State<'_> {
             // 怎么画，3个顶点，1个实例
             render_pass.draw(0..self.num_vertices, 0..1);
         }
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+        self.gpu.resources.end_frame();
         Ok(())
     }
-    pub fn update(&mut self) {
-        let rotation = glam::Mat4::from_rotation_z(0.01);
-        Vertex::transform(&mut self.vertex, rotation);
-        self.vertex_buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: bytemuck::cast_slice(&self.vertex),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
+    /// 按经过的时间推进动画，转速和调用频率无关
+    pub fn update(&mut self, delta_time: Duration) {
+        self.player.advance(delta_time);
+        let sample = self.player.sample();
+        self.vertex = self.base_vertex;
+        Vertex::transform(&mut self.vertex, sample.matrix());
+        Vertex::tint(&mut self.vertex, sample.color);
+        // 故意每帧重建顶点缓冲区，资源统计会对这里发出警告；
+        // 更好的做法是创建时加上 COPY_DST，每帧用 queue.write_buffer 更新
+        self.vertex_buffer =
+            self.gpu
+                .resources
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&self.vertex),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
     }
-    pub fn resize(&mut self, physical_size: winit::dpi::PhysicalSize<u32>) {
-        if physical_size.width > 0 && physical_size.height > 0 {
-            self.config.width = physical_size.width;
-            self.config.height = physical_size.height;
-            self.surface.configure(&self.device, &self.config);
-        }
+    pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
+        self.gpu.resize(physical_size);
     }
 }
 
-async fn create_wgpu_instance() -> anyhow::Result<(wgpu::Instance, wgpu::Backends)> {
-    for backend in wgpu::Backends::all() {
-        if let Some(instance) = try_wgpu_backend(backend).await {
-            return Ok((instance, backend));
-        }
+impl Lesson for State {
+    fn update(&mut self, delta_time: Duration) {
+        State::update(self, delta_time);
     }
-    Err(anyhow!("没有找到可用渲染后端"))
-}
-async fn try_wgpu_backend(backend: wgpu::Backends) -> Option<wgpu::Instance> {
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        backends: backend,
-        flags: wgpu::InstanceFlags::default().with_env(),
-        ..Default::default()
-    });
-    if instance.enumerate_adapters(backend).await.is_empty() {
-        None
-    } else {
-        Some(instance)
+
+    fn render(&mut self) -> Result<(), SurfaceError> {
+        State::render(self)
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        State::resize(self, size);
     }
 }
 
 #[repr(C)] // 保证结构体的内存布局和C语言一致，用于和C语言交互，共享数据
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct Vertex {
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
+pub struct Vertex {
     position: Vec3,
     color: Vec3,
 }
 
 impl Vertex {
-    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            // 顶点缓冲区的步长，即两个顶点数据间的间隔
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                    shader_location: 0,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    format: wgpu::VertexFormat::Float32x3,
-                    shader_location: 1,
-                },
-            ],
-        }
-    }
-
     fn transform(vertex: &mut [Vertex], matrix: Mat4) {
         for v in vertex {
             let position: Vec3 = matrix.transform_point3(v.position);
             v.position = position;
         }
     }
+
+    fn tint(vertex: &mut [Vertex], color: Vec4) {
+        for v in vertex {
+            v.color *= color.truncate();
+        }
+    }
+}
+
+/// 演示动画：4 秒转一圈，同时缩放、平移、变色
+fn demo_animation() -> Animation {
+    let turn = std::f32::consts::TAU / 3.0;
+    Animation::new(PlaybackMode::Loop)
+        // 四元数插值走最短路径，转一整圈需要中间关键帧
+        .with_rotation(vec![
+            Keyframe::new(0.0, Quat::IDENTITY),
+            Keyframe::new(4.0 / 3.0, Quat::from_rotation_z(turn)),
+            Keyframe::new(8.0 / 3.0, Quat::from_rotation_z(turn * 2.0)),
+            Keyframe::new(4.0, Quat::from_rotation_z(turn * 3.0)),
+        ])
+        .with_scale(vec![
+            Keyframe::new(0.0, Vec3::ONE).with_easing(Easing::ElasticOut),
+            Keyframe::new(2.0, Vec3::splat(1.4)).with_easing(Easing::CubicInOut),
+            Keyframe::new(4.0, Vec3::ONE),
+        ])
+        .with_translation(vec![
+            Keyframe::new(0.0, Vec3::new(-0.3, 0.0, 0.0))
+                .with_easing(Easing::Bezier(0.68, -0.55, 0.27, 1.55)),
+            Keyframe::new(2.0, Vec3::new(0.3, 0.0, 0.0)).with_easing(Easing::CubicOut),
+            Keyframe::new(4.0, Vec3::new(-0.3, 0.0, 0.0)),
+        ])
+        .with_color(vec![
+            Keyframe::new(0.0, Vec4::ONE).with_easing(Easing::CubicIn),
+            Keyframe::new(2.0, Vec4::new(0.4, 0.4, 1.0, 1.0)).with_easing(Easing::CubicOut),
+            Keyframe::new(4.0, Vec4::ONE),
+        ])
 }
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use glam::{Quat, Vec3};
+use transform::animation::{Animation, AnimationPlayer, Easing, Keyframe, PlaybackMode, Track};
+
+const EPSILON: f32 = 1e-4;
+
+#[test]
+fn easing_endpoints() {
+    let curves = [
+        Easing::Linear,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticOut,
+        Easing::Bezier(0.25, 0.1, 0.25, 1.0),
+    ];
+    for easing in curves {
+        assert!(easing.apply(0.0).abs() < EPSILON, "{easing:?}");
+        assert!((easing.apply(1.0) - 1.0).abs() < EPSILON, "{easing:?}");
+    }
+    // 控制点在对角线上的贝塞尔等价于线性
+    let bezier = Easing::Bezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0);
+    for t in [0.1, 0.25, 0.5, 0.9] {
+        assert!((bezier.apply(t) - t).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn track_sample_clamps_and_interpolates() {
+    let track = Track::new(vec![
+        Keyframe::new(1.0, Vec3::ONE),
+        Keyframe::new(0.0, Vec3::ZERO),
+    ]);
+    assert_eq!(track.sample(-1.0), Some(Vec3::ZERO));
+    assert_eq!(track.sample(2.0), Some(Vec3::ONE));
+    assert!(
+        track
+            .sample(0.5)
+            .unwrap()
+            .abs_diff_eq(Vec3::splat(0.5), EPSILON)
+    );
+}
+
+#[test]
+fn rotation_uses_slerp() {
+    let track = Track::new(vec![
+        Keyframe::new(0.0, Quat::IDENTITY),
+        Keyframe::new(1.0, Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+    ]);
+    let half = track.sample(0.5).unwrap();
+    assert!(half.abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4), EPSILON));
+}
+
+#[test]
+fn playback_modes() {
+    assert_eq!(PlaybackMode::Once.local_time(3.0, 2.0), 2.0);
+    assert!((PlaybackMode::Loop.local_time(2.5, 2.0) - 0.5).abs() < EPSILON);
+    assert!((PlaybackMode::PingPong.local_time(2.5, 2.0) - 1.5).abs() < EPSILON);
+    assert!((PlaybackMode::PingPong.local_time(4.5, 2.0) - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn player_speed_is_independent_of_update_rate() {
+    let animation = Animation::new(PlaybackMode::Loop).with_translation(vec![
+        Keyframe::new(0.0, Vec3::ZERO),
+        Keyframe::new(2.0, Vec3::X * 2.0),
+    ]);
+    let mut fast = AnimationPlayer::new(animation.clone());
+    let mut slow = AnimationPlayer::new(animation);
+    for _ in 0..120 {
+        fast.advance(Duration::from_secs_f32(1.0 / 120.0));
+    }
+    for _ in 0..30 {
+        slow.advance(Duration::from_secs_f32(1.0 / 30.0));
+    }
+    let a = fast.sample().translation;
+    let b = slow.sample().translation;
+    assert!(a.abs_diff_eq(Vec3::X, 1e-3), "{a:?}");
+    assert!(a.abs_diff_eq(b, 1e-3));
+}
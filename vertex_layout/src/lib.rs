@@ -0,0 +1,323 @@
+//! `#[derive(VertexLayout)]`：根据结构体字段生成顶点缓冲区布局
+//!
+//! ```ignore
+//! #[repr(C)]
+//! #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
+//! struct Vertex {
+//!     position: [f32; 3],      // @location(0) Float32x3
+//!     color: glam::Vec4,       // @location(1) Float32x4
+//!     #[vertex(format = Unorm8x4)]
+//!     tint: [u8; 4],           // @location(2) Unorm8x4
+//!     #[vertex(skip)]
+//!     _padding: u32,           // 不生成属性
+//! }
+//! ```
+//!
+//! 生成 `Vertex::ATTRIBUTES` 和 `Vertex::desc()`，偏移量用 `offset_of!` 计算，
+//! 不需要手写 `size_of::<[f32; 3]>()`。结构体必须是 `#[repr(C)]`，两个字段用了同一个位置时编译报错。
+//!
+//! 结构体上可以写 `#[vertex(instance)]` 把步进模式改成按实例，
+//! 以及 `#[vertex(start_location = 5)]` 让着色器位置从 5 开始编号；
+//! 字段上可以写 `#[vertex(location = 3)]` 指定位置，之后的字段从 4 继续编号。
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Error, Expr, ExprLit, Fields, Ident, Lit, Member, Result, Type,
+    parse_macro_input, spanned::Spanned,
+};
+
+#[proc_macro_derive(VertexLayout, attributes(vertex))]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(name, "VertexLayout 只能用于结构体"));
+    };
+    let members: Vec<(Member, &syn::Field)> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| (Member::Named(f.ident.clone().unwrap()), f))
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (Member::Unnamed(i.into()), f))
+            .collect(),
+        Fields::Unit => return Err(Error::new_spanned(name, "VertexLayout 需要至少一个字段")),
+    };
+
+    if !has_repr_c(&input.attrs) {
+        return Err(Error::new_spanned(
+            name,
+            "VertexLayout 需要 #[repr(C)]，否则字段的顺序和偏移由编译器决定，和着色器对不上",
+        ));
+    }
+    let options = StructOptions::parse(&input.attrs)?;
+    let step_mode = if options.instance {
+        quote!(wgpu::VertexStepMode::Instance)
+    } else {
+        quote!(wgpu::VertexStepMode::Vertex)
+    };
+
+    let mut location = options.start_location;
+    // 已经用过的位置和占用它的字段
+    let mut used: Vec<(u32, &syn::Field)> = Vec::new();
+    let mut attributes = Vec::new();
+    let mut errors: Option<Error> = None;
+    for (member, field) in &members {
+        let field_options = match FieldOptions::parse(&field.attrs) {
+            Ok(options) => options,
+            Err(e) => {
+                push_error(&mut errors, e);
+                continue;
+            }
+        };
+        if field_options.skip {
+            continue;
+        }
+        let format = match field_options.format {
+            Some(format) => format,
+            None => match vertex_format(&field.ty) {
+                Some(format) => Ident::new(format, field.ty.span()),
+                None => {
+                    push_error(
+                        &mut errors,
+                        Error::new_spanned(
+                            &field.ty,
+                            "VertexLayout 不支持这个字段类型，\
+                             可以用 #[vertex(format = ...)] 指定格式或 #[vertex(skip)] 跳过",
+                        ),
+                    );
+                    continue;
+                }
+            },
+        };
+        if let Some(explicit) = field_options.location {
+            location = explicit;
+        }
+        if let Some((_, other)) = used.iter().find(|(used, _)| *used == location) {
+            let message = match &other.ident {
+                Some(ident) => format!("@location({location}) 和字段 `{ident}` 重复"),
+                None => format!("@location({location}) 和前面的字段重复"),
+            };
+            push_error(&mut errors, Error::new_spanned(field, message));
+        }
+        used.push((location, field));
+        attributes.push(quote! {
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::#format,
+                offset: ::core::mem::offset_of!(#name #ty_generics, #member) as wgpu::BufferAddress,
+                shader_location: #location,
+            }
+        });
+        location += 1;
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    let count = attributes.len();
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// 由 `#[derive(VertexLayout)]` 生成
+            pub const ATTRIBUTES: [wgpu::VertexAttribute; #count] = [#(#attributes),*];
+
+            /// 由 `#[derive(VertexLayout)]` 生成
+            pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+                wgpu::VertexBufferLayout {
+                    array_stride: ::core::mem::size_of::<Self>() as wgpu::BufferAddress,
+                    step_mode: #step_mode,
+                    attributes: &Self::ATTRIBUTES,
+                }
+            }
+        }
+    })
+}
+
+/// `#[repr(C)]`，也可以和 `align` 之类写在一起
+fn has_repr_c(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .any(|attr| {
+            let mut c = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                c |= meta.path.is_ident("C");
+                // 跳过 align(16) 这样带参数的项
+                if meta.input.peek(syn::token::Paren) {
+                    let _ = meta.input.parse::<proc_macro2::Group>();
+                }
+                Ok(())
+            });
+            c
+        })
+}
+
+fn push_error(errors: &mut Option<Error>, error: Error) {
+    match errors {
+        Some(errors) => errors.combine(error),
+        None => *errors = Some(error),
+    }
+}
+
+/// 根据 Rust 类型推断 `wgpu::VertexFormat` 的变体名
+fn vertex_format(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Array(array) => {
+            let len = array_len(&array.len)?;
+            let scalar = type_name(&array.elem)?;
+            let format = match (scalar.as_str(), len) {
+                ("f32", 1) => "Float32",
+                ("f32", 2) => "Float32x2",
+                ("f32", 3) => "Float32x3",
+                ("f32", 4) => "Float32x4",
+                ("u32", 1) => "Uint32",
+                ("u32", 2) => "Uint32x2",
+                ("u32", 3) => "Uint32x3",
+                ("u32", 4) => "Uint32x4",
+                ("i32", 1) => "Sint32",
+                ("i32", 2) => "Sint32x2",
+                ("i32", 3) => "Sint32x3",
+                ("i32", 4) => "Sint32x4",
+                ("f64", 1) => "Float64",
+                ("f64", 2) => "Float64x2",
+                ("f64", 3) => "Float64x3",
+                ("f64", 4) => "Float64x4",
+                ("u16", 2) => "Uint16x2",
+                ("u16", 4) => "Uint16x4",
+                ("i16", 2) => "Sint16x2",
+                ("i16", 4) => "Sint16x4",
+                ("u8", 2) => "Uint8x2",
+                ("u8", 4) => "Uint8x4",
+                ("i8", 2) => "Sint8x2",
+                ("i8", 4) => "Sint8x4",
+                _ => return None,
+            };
+            Some(format)
+        }
+        Type::Path(_) => {
+            let format = match type_name(ty)?.as_str() {
+                "f32" => "Float32",
+                "u32" => "Uint32",
+                "i32" => "Sint32",
+                "f64" => "Float64",
+                // glam
+                "Vec2" => "Float32x2",
+                "Vec3" => "Float32x3",
+                "Vec4" => "Float32x4",
+                "UVec2" => "Uint32x2",
+                "UVec3" => "Uint32x3",
+                "UVec4" => "Uint32x4",
+                "IVec2" => "Sint32x2",
+                "IVec3" => "Sint32x3",
+                "IVec4" => "Sint32x4",
+                "DVec2" => "Float64x2",
+                "DVec3" => "Float64x3",
+                "DVec4" => "Float64x4",
+                _ => return None,
+            };
+            Some(format)
+        }
+        Type::Group(group) => vertex_format(&group.elem),
+        Type::Paren(paren) => vertex_format(&paren.elem),
+        _ => None,
+    }
+}
+
+/// 路径类型的最后一段，`glam::Vec3` 和 `Vec3` 都得到 `Vec3`
+fn type_name(ty: &Type) -> Option<String> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let segment = path.path.segments.last()?;
+    if !segment.arguments.is_none() {
+        return None;
+    }
+    Some(segment.ident.to_string())
+}
+
+fn array_len(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct StructOptions {
+    instance: bool,
+    start_location: u32,
+}
+
+impl StructOptions {
+    fn parse(attrs: &[syn::Attribute]) -> Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("instance") {
+                    options.instance = true;
+                    Ok(())
+                } else if meta.path.is_ident("start_location") {
+                    options.start_location =
+                        meta.value()?.parse::<syn::LitInt>()?.base10_parse()?;
+                    Ok(())
+                } else {
+                    Err(meta.error("未知的结构体参数，可用：instance, start_location"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    skip: bool,
+    location: Option<u32>,
+    format: Option<Ident>,
+}
+
+impl FieldOptions {
+    fn parse(attrs: &[syn::Attribute]) -> Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    options.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("location") {
+                    options.location = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("format") {
+                    options.format = Some(meta.value()?.parse::<Ident>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("未知的字段参数，可用：skip, location, format"))
+                }
+            })?;
+        }
+        if options.skip && (options.location.is_some() || options.format.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "#[vertex(skip)] 不能和 location、format 一起使用",
+            ));
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,90 @@
+use vertex_layout::VertexLayout;
+
+/// 各种推断出来的格式、手动指定的格式、跳过的字段和指定的位置混在一起
+#[repr(C)]
+#[derive(Clone, Copy, VertexLayout)]
+struct Mixed {
+    position: [f32; 3],
+    normal: glam::Vec3,
+    #[vertex(format = Unorm8x4)]
+    color: [u8; 4],
+    uv: [u16; 2],
+    #[vertex(skip)]
+    _padding: u32,
+    id: u32,
+    #[vertex(location = 7)]
+    weight: f32,
+    cell: glam::IVec2,
+}
+
+#[test]
+fn mixed_fields_match_a_hand_written_layout() {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 7] = [
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x3,
+            offset: 0,
+            shader_location: 0,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x3,
+            offset: 12,
+            shader_location: 1,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Unorm8x4,
+            offset: 24,
+            shader_location: 2,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Uint16x2,
+            offset: 28,
+            shader_location: 3,
+        },
+        // 32 是跳过的 _padding
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Uint32,
+            offset: 36,
+            shader_location: 4,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32,
+            offset: 40,
+            shader_location: 7,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Sint32x2,
+            offset: 44,
+            shader_location: 8,
+        },
+    ];
+    let expected = wgpu::VertexBufferLayout {
+        array_stride: 52,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &ATTRIBUTES,
+    };
+    assert_eq!(Mixed::desc(), expected);
+}
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, VertexLayout)]
+#[vertex(instance, start_location = 5)]
+struct Instance([f32; 4], glam::Vec2);
+
+#[test]
+fn instance_layouts_start_at_the_given_location() {
+    let layout = Instance::desc();
+    assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+    // 按 16 字节对齐，末尾有 8 字节的填充
+    assert_eq!(layout.array_stride, 32);
+    let locations: Vec<(u32, u64)> = layout
+        .attributes
+        .iter()
+        .map(|a| (a.shader_location, a.offset))
+        .collect();
+    assert_eq!(locations, [(5, 0), (6, 16)]);
+}
+
+#[test]
+fn invalid_structs_do_not_compile() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
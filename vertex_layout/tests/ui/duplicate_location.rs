@@ -0,0 +1,11 @@
+use vertex_layout::VertexLayout;
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct Vertex {
+    position: [f32; 3],
+    #[vertex(location = 0)]
+    color: [f32; 4],
+}
+
+fn main() {}
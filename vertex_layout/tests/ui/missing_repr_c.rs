@@ -0,0 +1,9 @@
+use vertex_layout::VertexLayout;
+
+#[derive(VertexLayout)]
+struct Vertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use vertex_layout::VertexLayout;
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct Vertex {
+    position: [f32; 3],
+    flags: [bool; 4],
+}
+
+fn main() {}